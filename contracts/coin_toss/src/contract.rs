@@ -1,23 +1,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
 
 use linera_sdk::{
     abi::WithContractAbi,
     views::{RootView, View},
+    linera_base_types::AccountOwner,
     Contract, ContractRuntime,
-    linera_base_types::{AccountOwner, Amount, ApplicationId},
 };
-use linera_base::crypto::{CryptoHash, BcsHashable};
-use coin_toss::{Operation, CoinTossAbi, InstantiationArgument, Prediction, GameLog};
+use coin_toss::{Operation, CoinTossAbi, CoinTossParams, InstantiationArgument, Prediction, GameLog, TossOutcome};
+use pulse_common::{bet, pending, GameConfig, GameKind, GameSettled, PendingBet, SettledBet};
 use state::CoinTossState;
-use serde::{Serialize, Deserialize};
-use std::str::FromStr;
-
-#[derive(Serialize, Deserialize)]
-struct SeedWrapper(Vec<u8>);
-
-impl<'de> BcsHashable<'de> for SeedWrapper {}
 
 pub struct CoinTossContract {
     state: CoinTossState,
@@ -30,13 +26,11 @@ impl WithContractAbi for CoinTossContract {
     type Abi = CoinTossAbi;
 }
 
-const PULSE_TOKEN_APP_ID: &str = "8e7498a4564d33c50bc4a3053eba7b51a4f5e7085111dbcc7cd3efe6072a7961";
-
 impl Contract for CoinTossContract {
     type Message = ();
-    type Parameters = ();
+    type Parameters = GameConfig;
     type InstantiationArgument = InstantiationArgument;
-    type EventValue = ();
+    type EventValue = GameSettled<TossOutcome>;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = CoinTossState::load(runtime.root_view_storage_context())
@@ -46,13 +40,28 @@ impl Contract for CoinTossContract {
     }
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
-        self.state.pulse_token_id.set(Some(argument.pulse_token_id));
+        self.state.setup.instantiate(&mut self.runtime, argument.pulse_token_id, argument.leaderboard_id);
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
         match operation {
-            Operation::TossCoin { amount, prediction, owner } => {
-                self.execute_toss(amount, prediction, owner).await
+            Operation::TossCoin { amount, prediction, owner, client_seed } => {
+                self.execute_toss(amount, prediction, owner, client_seed).await
+            }
+            Operation::Refund { owner } => {
+                self.state.setup.refund_bet(&mut self.runtime, &mut self.state.pending, owner).await;
+            }
+            Operation::UpdateConfig { config } => self.state.setup.update_config(&mut self.runtime, config),
+            Operation::CommitHouseSeeds { hash } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                self.state.seeds.commit_house_chain(hash);
+            }
+            Operation::PublishHouseSeed { seed } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                let revealed = self.state.seeds.publish_house_seed(&mut self.runtime, seed);
+                for toss in pending::take_due(&mut self.state.pending, &revealed.server_seed_hash).await {
+                    self.settle(toss).await;
+                }
             }
         }
     }
@@ -65,60 +74,49 @@ impl Contract for CoinTossContract {
 }
 
 impl CoinTossContract {
-    async fn execute_toss(&mut self, amount: u64, prediction: Prediction, owner: String) {
-        assert!(amount > 0, "Bet amount must be positive");
-        
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID)
-                        .expect("Invalid hardcoded PulseToken App ID"),
-        };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-        let account_owner = AccountOwner::from_str(&owner).expect("Invalid owner address");
-        let signer = self.runtime.authenticated_signer();
-        assert_eq!(signer, Some(account_owner), "Operation must be signed by the owner");
-
-        // 1. Debit Tokens
-        let debit_op = pulse_token::Operation::GameDebit {
-            owner: account_owner,
-            amount: Amount::from_tokens(amount.into()),
-        };
-        self.runtime.call_application(true, token_app_id, &debit_op);
-
-        // 2. Generate Randomness (0 or 1)
-        let nonce = *self.state.nonce.get();
-        self.state.nonce.set(nonce + 1);
-
-        let data = (self.runtime.chain_id(), self.runtime.system_time(), amount, nonce, "COIN_TOSS");
-        let bytes = bcs::to_bytes(&data).expect("Serialization failed");
-        let seed = SeedWrapper(bytes);
-        let hash = CryptoHash::new(&seed);
-        
-        let roll = (hash.as_bytes()[0] as u64) % 2; 
-        
-        let result_prediction = if roll == 0 { Prediction::Heads } else { Prediction::Tails };
-
-        // 3. Determine Win/Loss
-        let won = prediction == result_prediction;
-        let payout = if won { amount * 2 } else { 0 };
+    async fn execute_toss(&mut self, amount: u64, prediction: Prediction, owner: AccountOwner, client_seed: String) {
+        bet::check_signer(&mut self.runtime, owner);
+        let config = self.state.setup.config();
+        config.check_stake(amount);
+        config.check_payout(bet::apply_multiplier(amount, coin_toss::win_multiplier_percent(config.house_edge_bps)));
+        let waiting = self.state.pending.contains_key(&owner).await.expect("Failed to read pending tosses");
+        assert!(!waiting, "A toss is already waiting for the house");
+
+        // The stake stays on hold until the house publishes the link the toss is drawn from
+        let params = CoinTossParams { amount, prediction };
+        let toss = self.state.setup.lock_bet(&mut self.runtime, &mut self.state.seeds, owner, amount, params, client_seed);
+        self.state.pending.insert(&owner, toss).expect("Failed to save toss");
+    }
 
-        if won && payout > 0 {
-             let credit_op = pulse_token::Operation::GameCredit {
-                owner: account_owner,
-                amount: Amount::from_tokens(payout.into()),
-            };
-            self.runtime.call_application(true, token_app_id, &credit_op);
-        }
+    async fn settle(&mut self, toss: PendingBet<CoinTossParams>) {
+        let mut rng = self.state.seeds.rng_for(&toss.seeds).await.expect("the toss's link is published");
+        let outcome = coin_toss::toss_coin(&mut rng, &toss.params, toss.house_edge_bps);
+        let TossOutcome { result, won, payout } = outcome.clone();
+        self.state.setup.token().settle(&mut self.runtime, toss.hold_reference, bet::tokens(payout));
 
-        // 4. Save State active_game / last_game
         let game_log = GameLog {
-            owner,
-            bet_amount: amount,
-            prediction: if prediction == Prediction::Heads { 0 } else { 1 },
-            result: roll as u8,
+            owner: toss.owner,
+            bet_amount: toss.stake,
+            prediction: if toss.params.prediction == Prediction::Heads { 0 } else { 1 },
+            result: if result == Prediction::Heads { 0 } else { 1 },
             payout,
             won,
+            seeds: toss.seeds.clone(),
+        };
+        let settled = SettledBet {
+            game: GameKind::CoinToss,
+            owner: toss.owner,
+            stake: toss.stake,
+            params: toss.params,
+            outcome,
+            payout,
+            house_edge_bps: toss.house_edge_bps,
+            seeds: toss.seeds,
         };
+        self.state
+            .setup
+            .record_round(&mut self.runtime, &mut self.state.rounds, &mut self.state.round_index, settled)
+            .await;
         self.state.last_game.set(Some(game_log));
     }
 }
@@ -1,14 +1,18 @@
-use async_graphql::{Enum, Request, Response};
+use async_graphql::{Enum, InputObject, Request, Response, SimpleObject};
 use linera_sdk::{
     abi::{ContractAbi, ServiceAbi},
     graphql::GraphQLMutationRoot,
-    linera_base_types::ApplicationId,
+    linera_base_types::{AccountOwner, ApplicationId},
 };
+use pulse_common::{bet, config, history, BetSeeds, GameConfig, GameRng};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InstantiationArgument {
     pub pulse_token_id: ApplicationId,
+    /// Leaderboard the game reports its settled bets to, if any
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId>,
 }
 
 pub struct CoinTossAbi;
@@ -25,26 +29,85 @@ impl ServiceAbi for CoinTossAbi {
 
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
+    /// Holds the stake; the coin is tossed when the house publishes its next link
     TossCoin {
         amount: u64,
         prediction: Prediction, // 0 for Heads, 1 for Tails
-        owner: String,
+        owner: AccountOwner,
+        client_seed: String,
     },
+    /// Returns the stake of a toss the house has left waiting too long
+    Refund { owner: AccountOwner },
+    /// Admin only
+    UpdateConfig { config: GameConfig },
+    /// Admin only: starts a house seed chain from the hash of its first link
+    CommitHouseSeeds { hash: String },
+    /// Admin only: publishes the next link of the house seed chain and settles the tosses
+    /// waiting for it
+    PublishHouseSeed { seed: String },
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Enum)]
 pub enum Prediction {
+    #[serde(alias = "HEADS")]
     Heads, // 0
+    #[serde(alias = "TAILS")]
     Tails, // 1
 }
 
+/// The bet a toss is verified against
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "CoinTossParamsInput")]
+pub struct CoinTossParams {
+    pub amount: u64,
+    pub prediction: Prediction,
+}
+
+impl CoinTossParams {
+    pub fn check(&self) -> Result<(), &'static str> {
+        if self.amount > 0 {
+            Ok(())
+        } else {
+            Err("Bet amount must be positive")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject)]
+pub struct TossOutcome {
+    pub result: Prediction,
+    pub won: bool,
+    pub payout: u64,
+}
+
+/// What a winning toss pays, in percent of the stake.
+pub fn win_multiplier_percent(house_edge_bps: u16) -> u64 {
+    config::apply_house_edge(200, house_edge_bps)
+}
+
+/// Tosses the coin for a bet; shared by the contract, the service and pulsebet-verify.
+pub fn toss_coin(rng: &mut GameRng, params: &CoinTossParams, house_edge_bps: u16) -> TossOutcome {
+    let result = if rng.below(2) == 0 { Prediction::Heads } else { Prediction::Tails };
+    let won = params.prediction == result;
+    let payout = if won {
+        bet::apply_multiplier(params.amount, win_multiplier_percent(house_edge_bps))
+    } else {
+        0
+    };
+    TossOutcome { result, won, payout }
+}
+
 // Helper struct for Game Result (internal use)
 #[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
 pub struct GameLog {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub bet_amount: u64,
     pub prediction: u8, // 0: Heads, 1: Tails
     pub result: u8,     // 0: Heads, 1: Tails
     pub payout: u64,
     pub won: bool,
+    pub seeds: BetSeeds,
 }
+
+/// A settled toss, as kept in its owner's history
+pub type Round = history::Round<CoinTossParams, TossOutcome>;
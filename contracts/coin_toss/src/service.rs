@@ -5,15 +5,17 @@ mod state;
 use async_graphql::{EmptySubscription, Object, Schema};
 use linera_sdk::{
     abi::WithServiceAbi,
+    linera_base_types::AccountOwner,
     views::View,
     Service, ServiceRuntime,
 };
-use coin_toss::{CoinTossAbi, Operation, Prediction, GameLog};
+use coin_toss::{CoinTossAbi, CoinTossParams, Operation, Prediction, GameLog, TossOutcome, Round};
+use pulse_common::{fairness, history, GameConfig, HouseChain, PendingBet, RevealedSeed, SeedCommitment, Verification};
 use state::CoinTossState;
 use std::sync::Arc;
 
 pub struct CoinTossService {
-    state: CoinTossState,
+    state: Arc<CoinTossState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -24,21 +26,22 @@ impl WithServiceAbi for CoinTossService {
 }
 
 impl Service for CoinTossService {
-    type Parameters = ();
+    type Parameters = GameConfig;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = CoinTossState::load(runtime.root_view_storage_context())
             .await
             .expect("Failed to load state");
         CoinTossService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, request: Self::Query) -> Self::QueryResponse {
         let query_root = QueryRoot { 
-            last_game: self.state.last_game.get().clone() 
+            last_game: self.state.last_game.get().clone(),
+            state: self.state.clone(),
         };
         
         Schema::build(
@@ -54,6 +57,7 @@ impl Service for CoinTossService {
 
 pub struct QueryRoot {
     last_game: Option<GameLog>,
+    state: Arc<CoinTossState>,
 }
 
 #[Object]
@@ -61,6 +65,55 @@ impl QueryRoot {
     async fn last_game(&self) -> Option<GameLog> {
         self.last_game.clone()
     }
+
+    /// The owner's toss waiting for the house to publish its link
+    async fn pending_toss(&self, owner: AccountOwner) -> Option<PendingBet<CoinTossParams>> {
+        self.state.pending.get(&owner).await.expect("Failed to read pending tosses")
+    }
+
+    /// Hash of the house link the next toss will be drawn from
+    async fn seed_commitment(&self) -> Option<SeedCommitment> {
+        self.state.seeds.commitment()
+    }
+
+    async fn revealed_seed(&self, server_seed_hash: String) -> Option<RevealedSeed> {
+        self.state.seeds.revealed(&server_seed_hash).await
+    }
+
+    /// Latest published link of the house seed chain
+    async fn house_chain(&self) -> Option<HouseChain> {
+        self.state.seeds.house_chain()
+    }
+
+    /// The owner's settled rounds, newest first. Pass the last `roundId` of a page as `after`
+    /// to get the next one.
+    async fn history(&self, owner: AccountOwner, first: Option<u32>, after: Option<u64>) -> Vec<Round> {
+        self.state.round_index.rounds(&self.state.rounds, &owner, first, after).await
+    }
+
+    async fn round(&self, round_id: u64) -> Option<Round> {
+        history::round(&self.state.rounds, round_id).await
+    }
+
+    async fn config(&self) -> Option<GameConfig> {
+        self.state.setup.config.get().clone()
+    }
+
+    /// Recomputes a toss from its revealed server seed
+    async fn verify(
+        &self,
+        server_seed: String,
+        client_seed: String,
+        nonce: u64,
+        params: CoinTossParams,
+        /// Edge of the config the bet was placed under; defaults to the current one
+        house_edge_bps: Option<u16>,
+    ) -> async_graphql::Result<Verification<TossOutcome>> {
+        params.check()?;
+        let (server_seed_hash, mut rng) = fairness::replay(&server_seed, &client_seed, nonce)?;
+        let outcome = coin_toss::toss_coin(&mut rng, &params, self.state.setup.house_edge_bps(house_edge_bps));
+        Ok(Verification { server_seed_hash, outcome })
+    }
 }
 
 pub struct MutationRoot {
@@ -69,13 +122,34 @@ pub struct MutationRoot {
 
 #[Object]
 impl MutationRoot {
-    async fn play_toss(&self, amount: u64, prediction: Prediction, owner: String) -> Vec<u8> {
+    async fn play_toss(&self, amount: u64, prediction: Prediction, owner: AccountOwner, client_seed: String) -> Vec<u8> {
         let operation = Operation::TossCoin {
             amount,
             prediction,
             owner,
+            client_seed,
         };
         self.runtime.schedule_operation(&operation);
         Vec::new()
     }
+
+    async fn refund(&self, owner: AccountOwner) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::Refund { owner });
+        Vec::new()
+    }
+
+    async fn update_config(&self, config: GameConfig) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::UpdateConfig { config });
+        Vec::new()
+    }
+
+    async fn commit_house_seeds(&self, hash: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::CommitHouseSeeds { hash });
+        Vec::new()
+    }
+
+    async fn publish_house_seed(&self, seed: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::PublishHouseSeed { seed });
+        Vec::new()
+    }
 }
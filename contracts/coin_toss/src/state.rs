@@ -1,13 +1,20 @@
-use coin_toss::GameLog;
+use coin_toss::{CoinTossParams, GameLog, Round};
+use pulse_common::{GameSetup, PendingBet, RoundIndex, SeedBook};
 use linera_sdk::{
-    linera_base_types::ApplicationId,
-    views::{RegisterView, RootView, ViewStorageContext},
+    linera_base_types::AccountOwner,
+    views::{LogView, MapView, RegisterView, RootView, ViewStorageContext},
 };
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct CoinTossState {
-    pub pulse_token_id: RegisterView<Option<ApplicationId>>,
+    pub setup: GameSetup,
     pub nonce: RegisterView<u64>,
     pub last_game: RegisterView<Option<GameLog>>,
-}
\ No newline at end of file
+    /// Tosses waiting for the house link they are drawn from
+    pub pending: MapView<AccountOwner, PendingBet<CoinTossParams>>,
+    pub seeds: SeedBook,
+    /// Every settled round; a round's id is its index
+    pub rounds: LogView<Round>,
+    pub round_index: RoundIndex,
+}
@@ -0,0 +1,85 @@
+use super::*;
+use linera_sdk::{linera_base_types::Amount, util::BlockingWait};
+use pulse_common::{
+    fairness,
+    testing::{self, GameUnderTest, ALICE},
+    GameSetup,
+};
+
+impl GameUnderTest for CoinTossContract {
+    fn deploy(runtime: ContractRuntime<Self>) -> Self {
+        let state = CoinTossState::load(runtime.root_view_storage_context())
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        CoinTossContract { state, runtime }
+    }
+
+    fn parts(&mut self) -> (&mut GameSetup, &mut ContractRuntime<Self>) {
+        (&mut self.state.setup, &mut self.runtime)
+    }
+
+    fn bet(owner: AccountOwner) -> Operation {
+        Operation::TossCoin { amount: 10, prediction: Prediction::Heads, owner, client_seed: "seed".into() }
+    }
+
+    fn refund(owner: AccountOwner) -> Operation {
+        Operation::Refund { owner }
+    }
+
+    fn update_config(config: GameConfig) -> Operation {
+        Operation::UpdateConfig { config }
+    }
+
+    fn commit_house_seeds(hash: String) -> Operation {
+        Operation::CommitHouseSeeds { hash }
+    }
+
+    fn publish_house_seed(seed: String) -> Operation {
+        Operation::PublishHouseSeed { seed }
+    }
+}
+
+#[test]
+fn test_shared_game_rules() {
+    testing::check_shared_rules::<CoinTossContract>();
+}
+
+/// Settles a toss of Alice's on heads that wins or loses as asked, returning its outcome and the
+/// payout of its hold.
+fn settled_toss(won: bool) -> (CoinTossContract, TossOutcome, Amount) {
+    let params = CoinTossParams { amount: 10, prediction: Prediction::Heads };
+    let link = &testing::house_links()[0];
+    let client_seed = testing::client_seed_where(link, 0, |rng| coin_toss::toss_coin(rng, &params, 100).won == won);
+    let (_, mut rng) = fairness::replay(link, &client_seed, 0).expect("test seeds are hex-encoded");
+    let outcome = coin_toss::toss_coin(&mut rng, &params, 100);
+
+    let owner = bet::parse_owner(ALICE);
+    let toss = Operation::TossCoin { amount: 10, prediction: Prediction::Heads, owner, client_seed };
+    let (contract, payout) = testing::settle_bet::<CoinTossContract>(toss, 10);
+    (contract, outcome, payout)
+}
+
+#[test]
+fn test_winning_toss_pays_out() {
+    let (contract, outcome, payout) = settled_toss(true);
+
+    assert!(outcome.payout > 10);
+    assert_eq!(payout, bet::tokens(outcome.payout));
+    let game = contract.state.last_game.get().clone().expect("the toss is saved");
+    assert!(game.won);
+    assert_eq!((game.result, game.payout), (0, outcome.payout));
+    let waiting = contract.state.pending.contains_key(&bet::parse_owner(ALICE)).blocking_wait();
+    assert!(!waiting.expect("Failed to read pending tosses"));
+}
+
+#[test]
+fn test_losing_toss_pays_nothing() {
+    let (contract, _, payout) = settled_toss(false);
+
+    assert_eq!(payout, Amount::ZERO);
+    let game = contract.state.last_game.get().clone().expect("the toss is saved");
+    assert!(!game.won);
+    assert_eq!((game.result, game.payout), (1, 0));
+    let waiting = contract.state.pending.contains_key(&bet::parse_owner(ALICE)).blocking_wait();
+    assert!(!waiting.expect("Failed to read pending tosses"));
+}
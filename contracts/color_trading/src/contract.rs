@@ -6,18 +6,11 @@ use linera_sdk::{
     abi::{WithContractAbi},
     views::{RootView, View},
     Contract, ContractRuntime,
-    linera_base_types::{Amount, ApplicationId},
+    linera_base_types::{Amount, StreamName},
 };
-use linera_base::crypto::{CryptoHash, BcsHashable};
 use color_trading::{Operation, ColorTradingAbi, Color, InstantiationArgument};
+use pulse_common::{bet, events::SETTLED_STREAM, GameConfig, GameKind, GameRng, GameSettled, LeaderboardClient, TokenClient};
 use state::{ColorTradingState, Round, RoundState, BetData};
-use serde::{Serialize, Deserialize};
-use std::str::FromStr;
-
-#[derive(Serialize, Deserialize)]
-struct SeedWrapper(Vec<u8>);
-
-impl<'de> BcsHashable<'de> for SeedWrapper {}
 
 pub struct ColorTradingContract {
     state: ColorTradingState,
@@ -32,9 +25,9 @@ impl WithContractAbi for ColorTradingContract {
 
 impl Contract for ColorTradingContract {
     type Message = ();
-    type Parameters = ();
+    type Parameters = GameConfig;
     type InstantiationArgument = InstantiationArgument;
-    type EventValue = ();
+    type EventValue = GameSettled<Color>;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = ColorTradingState::load(runtime.root_view_storage_context())
@@ -45,6 +38,11 @@ impl Contract for ColorTradingContract {
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
         self.state.pulse_token_id.set(Some(argument.pulse_token_id));
+        self.state.leaderboard_id.set(argument.leaderboard_id);
+        let config = self.runtime.application_parameters();
+        config.check();
+        let house_edge_bps = config.house_edge_bps;
+        self.state.config.set(Some(config));
         let now = self.runtime.system_time();
         let first_round = Round {
             round_id: 1,
@@ -52,6 +50,7 @@ impl Contract for ColorTradingContract {
             state: RoundState::Betting,
             winning_color: None,
             bets: Vec::new(),
+            house_edge_bps,
         };
         self.state.active_round.set(Some(first_round));
     }
@@ -61,6 +60,7 @@ impl Contract for ColorTradingContract {
             Operation::Bet { amount, color } => self.execute_bet(amount, color).await,
             Operation::Reveal => self.execute_reveal().await,
             Operation::StartRound => self.execute_start_round().await,
+            Operation::UpdateConfig { config } => self.execute_update_config(config),
         }
     }
 
@@ -71,8 +71,6 @@ impl Contract for ColorTradingContract {
     }
 }
 
-const PULSE_TOKEN_APP_ID: &str = "8e7498a4564d33c50bc4a3053eba7b51a4f5e7085111dbcc7cd3efe6072a7961";
-
 impl ColorTradingContract {
     async fn execute_bet(&mut self, amount: u64, color: Color) {
         let mut round = self.state.active_round.get().clone().expect("No active round");
@@ -85,28 +83,19 @@ impl ColorTradingContract {
         // 40 seconds = 40_000_000 microseconds
         let elapsed = now.micros().saturating_sub(round.start_time.micros());
         assert!(elapsed < 40_000_000, "Betting phase ended");
-        assert!(amount > 0, "Bet amount must be positive");
+        let config = self.config();
+        config.check_stake(amount);
+        config.check_payout(bet::apply_multiplier(amount, color.multiplier(round.house_edge_bps)));
 
         let owner = self.runtime.authenticated_signer().expect("User must be signed");
 
         // Debit tokens (CPI to PulseToken)
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID).expect("Invalid hardcoded PulseToken App ID"),
-        };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-        
-        let debit_op = pulse_token::Operation::GameDebit {
-            owner,
-            amount: Amount::from_tokens(amount.into()),
-        };
-        
-        self.runtime.call_application(true, token_app_id, &debit_op);
+        self.token().debit(&mut self.runtime, owner, bet::tokens(amount));
 
         // Record Bet
         let bet_data = BetData {
             owner,
-            amount: Amount::from_tokens(amount.into()),
+            amount: bet::tokens(amount),
             color,
             round_id: round.round_id,
         };
@@ -125,29 +114,10 @@ impl ColorTradingContract {
         assert!(elapsed >= 40_000_000, "Betting phase not yet over");
 
         // Generate Winning Color
-        let rng_seed = (self.runtime.chain_id(), round.round_id, now);
-        let bytes = bcs::to_bytes(&rng_seed).expect("Serialization failed");
-        let seed = SeedWrapper(bytes);
-        let hash = CryptoHash::new(&seed);
-        let random_val = hash.as_bytes()[0] % 100; // 0-99
-
+        let mut rng = GameRng::new(&(self.runtime.chain_id(), round.round_id, now));
         // Probabilities: A(30), B(30), C(15), D(15), E(10)
-        // A: 0-29
-        // B: 30-59
-        // C: 60-74
-        // D: 75-89
-        // E: 90-99
-        let winning_color = if random_val < 30 {
-            Color::ColorA
-        } else if random_val < 60 {
-            Color::ColorB
-        } else if random_val < 75 {
-            Color::ColorC
-        } else if random_val < 90 {
-            Color::ColorD
-        } else {
-            Color::ColorE
-        };
+        let colors = [Color::ColorA, Color::ColorB, Color::ColorC, Color::ColorD, Color::ColorE];
+        let winning_color = colors[rng.weighted(&[30, 30, 15, 15, 10])];
 
         round.winning_color = Some(winning_color);
         round.state = RoundState::Revealing; // Transient state, effectively we are doing it now.
@@ -155,27 +125,36 @@ impl ColorTradingContract {
         // But for clarity, we can set it.
         
         // Payout Winners
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID).expect("Invalid hardcoded PulseToken App ID"),
-        };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-
-        // Iterate all bets
+        // Collect every winner and pay them in a single call; every bet, won or lost, is announced
+        let mut entries = Vec::new();
+        let leaderboard = self.leaderboard();
         for bet in &round.bets {
+            let mut payout = Amount::ZERO;
             if bet.color == winning_color {
-                let multiplier = winning_color.multiplier();
+                let multiplier = winning_color.multiplier(round.house_edge_bps);
                 let val: u128 = bet.amount.into();
                 let payout_raw = (val * (multiplier as u128)) / 100;
-                let payout = Amount::from_attos(payout_raw);
+                payout = Amount::from_attos(payout_raw);
 
-                let credit_op = pulse_token::Operation::GameCredit {
+                entries.push(pulse_token::CreditEntry {
                     owner: bet.owner,
                     amount: payout,
-                };
-                self.runtime.call_application(true, token_app_id, &credit_op);
+                });
+            }
+            let event = GameSettled {
+                game: GameKind::ColorTrading,
+                owner: bet.owner,
+                bet: bet.amount,
+                outcome: winning_color,
+                payout,
+                round_id: round.round_id,
+            };
+            self.runtime.emit(StreamName::from(SETTLED_STREAM), &event);
+            if let Some(leaderboard) = leaderboard {
+                leaderboard.record(&mut self.runtime, bet.owner, bet.amount, payout);
             }
         }
+        self.token().batch_credit(&mut self.runtime, entries);
 
         // Update Round State
         round.state = RoundState::Cooldown;
@@ -207,8 +186,28 @@ impl ColorTradingContract {
             state: RoundState::Betting,
             winning_color: None,
             bets: Vec::new(),
+            house_edge_bps: self.config().house_edge_bps,
         };
 
         self.state.active_round.set(Some(new_round));
     }
+
+    /// Replaces the limits and house edge; only the current admin may do this
+    fn execute_update_config(&mut self, config: GameConfig) {
+        self.config().check_admin(&mut self.runtime);
+        config.check();
+        self.state.config.set(Some(config));
+    }
+
+    fn config(&self) -> GameConfig {
+        self.state.config.get().clone().expect("Game config is not set")
+    }
+
+    fn token(&self) -> TokenClient {
+        TokenClient::new(self.state.pulse_token_id.get().expect("PulseToken application is not configured"))
+    }
+
+    fn leaderboard(&self) -> Option<LeaderboardClient> {
+        self.state.leaderboard_id.get().map(LeaderboardClient::new)
+    }
 }
@@ -3,6 +3,7 @@ use linera_sdk::{
     abi::{ContractAbi, ServiceAbi},
     linera_base_types::ApplicationId,
 };
+use pulse_common::{config, GameConfig};
 use serde::{Deserialize, Serialize};
 
 pub struct ColorTradingAbi;
@@ -20,6 +21,9 @@ impl ServiceAbi for ColorTradingAbi {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InstantiationArgument {
     pub pulse_token_id: ApplicationId,
+    /// Leaderboard the game reports its settled bets to, if any
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy, Enum, PartialEq, Eq)]
@@ -32,12 +36,14 @@ pub enum Color {
 }
 
 impl Color {
-    pub fn multiplier(&self) -> u64 {
-        match self {
+    /// Payout multiplier of a winning bet after the house edge, scaled by 100
+    pub fn multiplier(&self, house_edge_bps: u16) -> u64 {
+        let multiplier = match self {
             Color::ColorA | Color::ColorB => 300, // 3.0x (scaled by 100)
             Color::ColorC | Color::ColorD => 600, // 6.0x
             Color::ColorE => 900,                 // 9.0x
-        }
+        };
+        config::apply_house_edge(multiplier, house_edge_bps)
     }
 }
 
@@ -46,6 +52,8 @@ pub enum Operation {
     Bet { amount: u64, color: Color },
     Reveal,
     StartRound,
+    /// Admin only
+    UpdateConfig { config: GameConfig },
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -11,6 +11,7 @@ use linera_sdk::{
     Service, ServiceRuntime,
 };
 use color_trading::{Color, ColorTradingAbi, Operation};
+use pulse_common::GameConfig;
 use state::{ColorTradingState, Round, BetData};
 
 pub struct ColorTradingService {
@@ -29,7 +30,7 @@ impl WithServiceAbi for ColorTradingService {
 
 
 impl Service for ColorTradingService {
-    type Parameters = ();
+    type Parameters = GameConfig;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = ColorTradingState::load(runtime.root_view_storage_context())
@@ -66,6 +67,10 @@ impl QueryRoot {
         self.state.active_round.get().clone()
     }
 
+    async fn config(&self) -> Option<GameConfig> {
+        self.state.config.get().clone()
+    }
+
     async fn bet(&self, owner: AccountOwner) -> Option<BetData> {
         if let Some(round) = self.state.active_round.get() {
             return round.bets.iter().find(|b| b.owner == owner).cloned();
@@ -97,5 +102,11 @@ impl MutationRoot {
         self.runtime.schedule_operation(&op);
         Vec::new()
     }
+
+    async fn update_config(&self, config: GameConfig) -> Vec<u8> {
+        let op = Operation::UpdateConfig { config };
+        self.runtime.schedule_operation(&op);
+        Vec::new()
+    }
 }
 
@@ -3,12 +3,15 @@ use linera_sdk::{
     linera_base_types::{AccountOwner, Amount, ApplicationId, Timestamp},
     views::{RegisterView, RootView, ViewStorageContext},
 };
+use pulse_common::GameConfig;
 use serde::{Deserialize, Serialize};
 
 #[derive(RootView, async_graphql::SimpleObject)]
 #[view(context = ViewStorageContext)]
 pub struct ColorTradingState {
     pub pulse_token_id: RegisterView<Option<ApplicationId>>,
+    pub leaderboard_id: RegisterView<Option<ApplicationId>>,
+    pub config: RegisterView<Option<GameConfig>>,
     pub active_round: RegisterView<Option<Round>>,
 }
 
@@ -19,6 +22,7 @@ pub struct Round {
     pub state: RoundState,
     pub winning_color: Option<Color>,
     pub bets: Vec<BetData>,
+    pub house_edge_bps: u16, // Fixed when the round starts, so config updates only affect later rounds
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, async_graphql::Enum, Copy)]
@@ -1,23 +1,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
 
 use linera_sdk::{
     abi::{WithContractAbi, ContractAbi},
     views::{RootView, View},
+    linera_base_types::AccountOwner,
     Contract, ContractRuntime,
-    linera_base_types::{AccountOwner, Amount, ApplicationId},
 };
-use linera_base::crypto::{CryptoHash, BcsHashable};
-use dice::{Operation, DiceAbi, InstantiationArgument, RollType, GameResult};
+use dice::{Operation, DiceAbi, DiceParams, InstantiationArgument, RollType, GameResult};
+use pulse_common::{bet, pending, GameConfig, GameKind, GameSettled, PendingBet, SettledBet};
 use state::{DiceState, Game};
-use serde::{Serialize, Deserialize};
-use std::str::FromStr;
-
-#[derive(Serialize, Deserialize)]
-struct SeedWrapper(Vec<u8>);
-
-impl<'de> BcsHashable<'de> for SeedWrapper {}
 
 pub struct DiceContract {
     state: DiceState,
@@ -30,15 +26,11 @@ impl WithContractAbi for DiceContract {
     type Abi = DiceAbi;
 }
 
-
-const PULSE_TOKEN_APP_ID: &str = "8e7498a4564d33c50bc4a3053eba7b51a4f5e7085111dbcc7cd3efe6072a7961";
-const HOUSE_EDGE_PERCENT: u64 = 0; 
-
 impl Contract for DiceContract {
     type Message = ();
-    type Parameters = ();
+    type Parameters = GameConfig;
     type InstantiationArgument = InstantiationArgument;
-    type EventValue = (); 
+    type EventValue = GameSettled<GameResult>;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = DiceState::load(runtime.root_view_storage_context())
@@ -48,13 +40,28 @@ impl Contract for DiceContract {
     }
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
-        self.state.pulse_token_id.set(Some(argument.pulse_token_id));
+        self.state.setup.instantiate(&mut self.runtime, argument.pulse_token_id, argument.leaderboard_id);
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
         match operation {
-            Operation::RollDice { amount, target, roll_type, owner } => {
-                self.execute_roll(amount, target, roll_type, owner).await
+            Operation::RollDice { amount, target, roll_type, owner, client_seed } => {
+                self.execute_roll(amount, target, roll_type, owner, client_seed).await
+            }
+            Operation::Refund { owner } => {
+                self.state.setup.refund_bet(&mut self.runtime, &mut self.state.pending, owner).await;
+            }
+            Operation::UpdateConfig { config } => self.state.setup.update_config(&mut self.runtime, config),
+            Operation::CommitHouseSeeds { hash } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                self.state.seeds.commit_house_chain(hash);
+            }
+            Operation::PublishHouseSeed { seed } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                let revealed = self.state.seeds.publish_house_seed(&mut self.runtime, seed);
+                for roll in pending::take_due(&mut self.state.pending, &revealed.server_seed_hash).await {
+                    self.settle(roll).await;
+                }
             }
         }
     }
@@ -67,70 +74,50 @@ impl Contract for DiceContract {
 }
 
 impl DiceContract {
-    async fn execute_roll(&mut self, amount: u64, target: u8, roll_type: RollType, owner: String) {
-        assert!(amount > 0, "Bet amount must be positive");
-        assert!(target >= 1 && target <= 98, "Target must be between 1 and 98"); 
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID)
-                        .expect("Invalid hardcoded PulseToken App ID"),
-        };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-        let account_owner = AccountOwner::from_str(&owner).expect("Invalid owner address");
-        let signer = self.runtime.authenticated_signer();
-        assert_eq!(signer, Some(account_owner), "Operation must be signed by the owner");
-
-        // 3. Debit Tokens
-        let debit_op = pulse_token::Operation::GameDebit {
-            owner: account_owner,
-            amount: Amount::from_tokens(amount.into()),
-        };
-        self.runtime.call_application(true, token_app_id, &debit_op);
-
-        // 4. Generate Randomness (0-99)
-        let nonce = *self.state.nonce.get();
-        self.state.nonce.set(nonce + 1);
-
-        let data = (self.runtime.chain_id(), self.runtime.system_time(), amount, nonce, target);
-        let bytes = bcs::to_bytes(&data).expect("Serialization failed");
-        let seed = SeedWrapper(bytes);
-        let hash = CryptoHash::new(&seed);
-        
-        let roll = (hash.as_bytes()[0] as u64) % 100; 
-        let roll = roll as u8; // 0 to 99
-
-        // 5. Determine Win/Loss & Multiplier
-        let (won, win_chance) = match roll_type {
-            RollType::Under => (roll < target, target as u64),
-            RollType::Over => (roll >= target, 100 - target as u64),
-        };
-
-        let multiplier_x100 = if win_chance == 0 { 0 } else {
-             (100 * (100 - HOUSE_EDGE_PERCENT)) / win_chance
-        };
+    async fn execute_roll(&mut self, amount: u64, target: u8, roll_type: RollType, owner: AccountOwner, client_seed: String) {
+        let config = self.state.setup.config();
+        config.check_stake(amount);
+        let params = DiceParams { amount, target, roll_type };
+        params.check().unwrap_or_else(|error| panic!("{error}"));
+        config.check_payout(bet::apply_multiplier(amount, dice::multiplier_x100(&params, config.house_edge_bps)));
+        bet::check_signer(&mut self.runtime, owner);
+        let waiting = self.state.pending.contains_key(&owner).await.expect("Failed to read pending rolls");
+        assert!(!waiting, "A roll is already waiting for the house");
+
+        // The stake stays on hold until the house publishes the link the roll is drawn from
+        let roll = self.state.setup.lock_bet(&mut self.runtime, &mut self.state.seeds, owner, amount, params, client_seed);
+        self.state.pending.insert(&owner, roll).expect("Failed to save roll");
+    }
 
-        let payout = if won {
-            (amount * multiplier_x100) / 100
-        } else {
-            0
-        };
-        if won && payout > 0 {
-             let credit_op = pulse_token::Operation::GameCredit {
-                owner: account_owner,
-                amount: Amount::from_tokens(payout.into()),
-            };
-            self.runtime.call_application(true, token_app_id, &credit_op);
-        }
+    async fn settle(&mut self, roll: PendingBet<DiceParams>) {
+        let mut rng = self.state.seeds.rng_for(&roll.seeds).await.expect("the roll's link is published");
+        let result = dice::roll_dice(&mut rng, &roll.params, roll.house_edge_bps);
+        let payout = result.payout;
+        self.state.setup.token().settle(&mut self.runtime, roll.hold_reference, bet::tokens(payout));
 
-        // 7. Save State
         let game = Game {
-            owner,
-            bet_amount: amount,
-            target,
-            result_roll: roll,
+            owner: roll.owner,
+            bet_amount: roll.stake,
+            target: roll.params.target,
+            result_roll: result.roll,
+            payout,
+            multiplier: result.multiplier_x100,
+            seeds: roll.seeds.clone(),
+        };
+        let settled = SettledBet {
+            game: GameKind::Dice,
+            owner: roll.owner,
+            stake: roll.stake,
+            params: roll.params,
+            outcome: result,
             payout,
-            multiplier: multiplier_x100,
+            house_edge_bps: roll.house_edge_bps,
+            seeds: roll.seeds,
         };
-        self.state.active_game.set(Some(game));
+        self.state
+            .setup
+            .record_round(&mut self.runtime, &mut self.state.rounds, &mut self.state.round_index, settled)
+            .await;
+        self.state.active_games.insert(&game.owner, game).expect("Failed to save game");
     }
 }
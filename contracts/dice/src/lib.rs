@@ -1,14 +1,18 @@
-use async_graphql::{Enum, Request, Response};
+use async_graphql::{Enum, InputObject, Request, Response, SimpleObject};
 use linera_sdk::{
     abi::{ContractAbi, ServiceAbi},
     graphql::GraphQLMutationRoot,
-    linera_base_types::ApplicationId,
+    linera_base_types::{AccountOwner, ApplicationId},
 };
+use pulse_common::{bet, config, history, GameConfig, GameRng};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InstantiationArgument {
     pub pulse_token_id: ApplicationId,
+    /// Leaderboard the game reports its settled bets to, if any
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId>,
 }
 
 pub struct DiceAbi;
@@ -25,25 +29,98 @@ impl ServiceAbi for DiceAbi {
 
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
+    /// Holds the stake; the roll is drawn when the house publishes its next link
     RollDice {
         amount: u64,
         target: u8,
         roll_type: RollType,
-        owner: String,
+        owner: AccountOwner,
+        client_seed: String,
     },
+    /// Returns the stake of a roll the house has left waiting too long
+    Refund { owner: AccountOwner },
+    /// Admin only
+    UpdateConfig { config: GameConfig },
+    /// Admin only: starts a house seed chain from the hash of its first link
+    CommitHouseSeeds { hash: String },
+    /// Admin only: publishes the next link of the house seed chain and settles the rolls
+    /// waiting for it
+    PublishHouseSeed { seed: String },
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Enum)]
 pub enum RollType {
+    #[serde(alias = "OVER")]
     Over,
+    #[serde(alias = "UNDER")]
     Under,
 }
 
+/// The bet a roll is verified against
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "DiceParamsInput")]
+pub struct DiceParams {
+    pub amount: u64,
+    pub target: u8,
+    // The aliases take the GraphQL names too, so rounds exported from the service load as they are
+    #[serde(alias = "rollType")]
+    pub roll_type: RollType,
+}
+
+impl DiceParams {
+    pub fn check(&self) -> Result<(), &'static str> {
+        if (1..=98).contains(&self.target) {
+            Ok(())
+        } else {
+            Err("Target must be between 1 and 98")
+        }
+    }
+}
+
 // Kept for State usage, but not as Response
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject)]
 pub struct GameResult {
     pub roll: u8,
     pub won: bool,
     pub payout: u64,
     pub multiplier_x100: u64,
 }
+
+/// Odds of winning, in percent.
+fn win_chance(params: &DiceParams) -> u64 {
+    match params.roll_type {
+        RollType::Under => params.target as u64,
+        RollType::Over => 100 - params.target as u64,
+    }
+}
+
+/// Payout multiplier (x100) of a winning roll: the fair odds less the house edge.
+pub fn multiplier_x100(params: &DiceParams, house_edge_bps: u16) -> u64 {
+    let win_chance = win_chance(params);
+    if win_chance == 0 { 0 } else {
+        config::apply_house_edge(100 * 100 / win_chance, house_edge_bps)
+    }
+}
+
+/// Rolls the dice for a bet. The contract, the service's `verify` query and pulsebet-verify
+/// all go through here, so they cannot disagree.
+pub fn roll_dice(rng: &mut GameRng, params: &DiceParams, house_edge_bps: u16) -> GameResult {
+    let roll = rng.below(100) as u8; // 0 to 99
+
+    let won = match params.roll_type {
+        RollType::Under => roll < params.target,
+        RollType::Over => roll >= params.target,
+    };
+    let multiplier_x100 = multiplier_x100(params, house_edge_bps);
+
+    let payout = if won {
+        bet::apply_multiplier(params.amount, multiplier_x100)
+    } else {
+        0
+    };
+
+    GameResult { roll, won, payout, multiplier_x100 }
+}
+
+/// A settled roll, as kept in its owner's history
+pub type Round = history::Round<DiceParams, GameResult>;
@@ -5,15 +5,17 @@ mod state;
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema, SimpleObject};
 use linera_sdk::{
     abi::WithServiceAbi,
+    linera_base_types::AccountOwner,
     views::View,
     Service, ServiceRuntime,
 };
 use std::sync::Arc;
-use dice::{Operation, DiceAbi, RollType};
-use state::DiceState;
+use dice::{Operation, DiceAbi, DiceParams, GameResult, RollType, Round};
+use pulse_common::{fairness, history, BetSeeds, GameConfig, HouseChain, PendingBet, RevealedSeed, SeedCommitment, Verification};
+use state::{DiceState, Game};
 
 pub struct DiceService {
-    state: DiceState,
+    state: Arc<DiceState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -24,32 +26,21 @@ impl WithServiceAbi for DiceService {
 }
 
 impl Service for DiceService {
-    type Parameters = ();
+    type Parameters = GameConfig;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = DiceState::load(runtime.root_view_storage_context())
             .await
             .expect("Failed to load state");
         DiceService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, request: Request) -> Response {
-        let game_opt = self.state.active_game.get();
-        
-        let public_game = game_opt.as_ref().map(|game| PublicGame {
-            owner: game.owner.clone(),
-            bet_amount: game.bet_amount,
-            target: game.target,
-            result_roll: game.result_roll,
-            payout: game.payout,
-            multiplier: game.multiplier,
-        });
-
         let schema = Schema::build(
-            QueryRoot { public_game },
+            QueryRoot { state: self.state.clone() },
             MutationRoot { runtime: self.runtime.clone() },
             EmptySubscription,
         )
@@ -59,13 +50,64 @@ impl Service for DiceService {
 }
 
 struct QueryRoot {
-    public_game: Option<PublicGame>,
+    state: Arc<DiceState>,
 }
 
 #[Object]
 impl QueryRoot {
-    async fn active_game(&self) -> &Option<PublicGame> {
-        &self.public_game
+    /// The owner's last roll
+    async fn active_game(&self, owner: AccountOwner) -> Option<PublicGame> {
+        let game = self.state.active_games.get(&owner).await.expect("Failed to read games");
+        game.map(PublicGame::from)
+    }
+
+    /// The owner's roll waiting for the house to publish its link
+    async fn pending_roll(&self, owner: AccountOwner) -> Option<PendingBet<DiceParams>> {
+        self.state.pending.get(&owner).await.expect("Failed to read pending rolls")
+    }
+
+    /// Hash of the house link the next roll will be drawn from
+    async fn seed_commitment(&self) -> Option<SeedCommitment> {
+        self.state.seeds.commitment()
+    }
+
+    async fn revealed_seed(&self, server_seed_hash: String) -> Option<RevealedSeed> {
+        self.state.seeds.revealed(&server_seed_hash).await
+    }
+
+    /// Latest published link of the house seed chain
+    async fn house_chain(&self) -> Option<HouseChain> {
+        self.state.seeds.house_chain()
+    }
+
+    /// The owner's settled rounds, newest first. Pass the last `roundId` of a page as `after`
+    /// to get the next one.
+    async fn history(&self, owner: AccountOwner, first: Option<u32>, after: Option<u64>) -> Vec<Round> {
+        self.state.round_index.rounds(&self.state.rounds, &owner, first, after).await
+    }
+
+    async fn round(&self, round_id: u64) -> Option<Round> {
+        history::round(&self.state.rounds, round_id).await
+    }
+
+    async fn config(&self) -> Option<GameConfig> {
+        self.state.setup.config.get().clone()
+    }
+
+    /// Recomputes a roll from its revealed server seed
+    async fn verify(
+        &self,
+        server_seed: String,
+        client_seed: String,
+        nonce: u64,
+        params: DiceParams,
+        /// Edge of the config the bet was placed under; defaults to the current one
+        house_edge_bps: Option<u16>,
+    ) -> async_graphql::Result<Verification<GameResult>> {
+        params.check()?;
+        let (server_seed_hash, mut rng) = fairness::replay(&server_seed, &client_seed, nonce)?;
+        let outcome = dice::roll_dice(&mut rng, &params, self.state.setup.house_edge_bps(house_edge_bps));
+        Ok(Verification { server_seed_hash, outcome })
     }
 }
 
@@ -75,19 +117,54 @@ struct MutationRoot {
 
 #[Object]
 impl MutationRoot {
-    async fn roll_dice(&self, amount: u64, target: u8, roll_type: RollType, owner: String) -> Vec<u8> {
-        let op = Operation::RollDice { amount, target, roll_type, owner };
+    async fn roll_dice(&self, amount: u64, target: u8, roll_type: RollType, owner: AccountOwner, client_seed: String) -> Vec<u8> {
+        let op = Operation::RollDice { amount, target, roll_type, owner, client_seed };
         self.runtime.schedule_operation(&op);
         Vec::new()
     }
+
+    async fn refund(&self, owner: AccountOwner) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::Refund { owner });
+        Vec::new()
+    }
+
+    async fn update_config(&self, config: GameConfig) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::UpdateConfig { config });
+        Vec::new()
+    }
+
+    async fn commit_house_seeds(&self, hash: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::CommitHouseSeeds { hash });
+        Vec::new()
+    }
+
+    async fn publish_house_seed(&self, seed: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::PublishHouseSeed { seed });
+        Vec::new()
+    }
 }
 
 #[derive(SimpleObject)]
 pub struct PublicGame {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub bet_amount: u64,
     pub target: u8,
     pub result_roll: u8,
     pub payout: u64,
     pub multiplier: u64, 
+    pub seeds: BetSeeds,
+}
+
+impl From<Game> for PublicGame {
+    fn from(game: Game) -> Self {
+        PublicGame {
+            owner: game.owner,
+            bet_amount: game.bet_amount,
+            target: game.target,
+            result_roll: game.result_roll,
+            payout: game.payout,
+            multiplier: game.multiplier,
+            seeds: game.seeds,
+        }
+    }
 }
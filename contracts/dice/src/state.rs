@@ -1,23 +1,33 @@
 use linera_sdk::{
-    linera_base_types::ApplicationId,
-    views::{RegisterView, RootView, ViewStorageContext},
+    linera_base_types::AccountOwner,
+    views::{LogView, MapView, RegisterView, RootView, ViewStorageContext},
 };
+use dice::{DiceParams, Round};
+use pulse_common::{BetSeeds, GameSetup, PendingBet, RoundIndex, SeedBook};
 use serde::{Deserialize, Serialize};
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct DiceState {
-    pub pulse_token_id: RegisterView<Option<ApplicationId>>,
+    pub setup: GameSetup,
     pub nonce: RegisterView<u64>,
-    pub active_game: RegisterView<Option<Game>>,
+    /// Each owner's last settled roll
+    pub active_games: MapView<AccountOwner, Game>,
+    /// Rolls waiting for the house link they are drawn from
+    pub pending: MapView<AccountOwner, PendingBet<DiceParams>>,
+    pub seeds: SeedBook,
+    /// Every settled round; a round's id is its index
+    pub rounds: LogView<Round>,
+    pub round_index: RoundIndex,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Game {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub bet_amount: u64,
     pub target: u8,
     pub result_roll: u8,
     pub payout: u64,
     pub multiplier: u64, // x100
+    pub seeds: BetSeeds,
 }
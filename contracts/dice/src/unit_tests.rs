@@ -0,0 +1,83 @@
+use super::*;
+use linera_sdk::{linera_base_types::Amount, util::BlockingWait};
+use pulse_common::{
+    fairness,
+    testing::{self, GameUnderTest, ALICE},
+    GameSetup,
+};
+
+impl GameUnderTest for DiceContract {
+    fn deploy(runtime: ContractRuntime<Self>) -> Self {
+        let state = DiceState::load(runtime.root_view_storage_context())
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        DiceContract { state, runtime }
+    }
+
+    fn parts(&mut self) -> (&mut GameSetup, &mut ContractRuntime<Self>) {
+        (&mut self.state.setup, &mut self.runtime)
+    }
+
+    fn bet(owner: AccountOwner) -> Operation {
+        Operation::RollDice { amount: 10, target: 50, roll_type: RollType::Over, owner, client_seed: "seed".into() }
+    }
+
+    fn refund(owner: AccountOwner) -> Operation {
+        Operation::Refund { owner }
+    }
+
+    fn update_config(config: GameConfig) -> Operation {
+        Operation::UpdateConfig { config }
+    }
+
+    fn commit_house_seeds(hash: String) -> Operation {
+        Operation::CommitHouseSeeds { hash }
+    }
+
+    fn publish_house_seed(seed: String) -> Operation {
+        Operation::PublishHouseSeed { seed }
+    }
+}
+
+#[test]
+fn test_shared_game_rules() {
+    testing::check_shared_rules::<DiceContract>();
+}
+
+/// Settles a roll of Alice's that wins or loses as asked, returning its result and the payout
+/// of its hold.
+fn settled_roll(won: bool) -> (DiceContract, GameResult, Amount) {
+    let params = DiceParams { amount: 10, target: 50, roll_type: RollType::Under };
+    let link = &testing::house_links()[0];
+    let client_seed = testing::client_seed_where(link, 0, |rng| dice::roll_dice(rng, &params, 100).won == won);
+    let (_, mut rng) = fairness::replay(link, &client_seed, 0).expect("test seeds are hex-encoded");
+    let result = dice::roll_dice(&mut rng, &params, 100);
+
+    let owner = bet::parse_owner(ALICE);
+    let roll = Operation::RollDice { amount: 10, target: 50, roll_type: RollType::Under, owner, client_seed };
+    let (contract, payout) = testing::settle_bet::<DiceContract>(roll, 10);
+    (contract, result, payout)
+}
+
+#[test]
+fn test_winning_roll_pays_out() {
+    let (contract, result, payout) = settled_roll(true);
+    let alice = bet::parse_owner(ALICE);
+
+    assert!(result.payout > 10);
+    assert_eq!(payout, bet::tokens(result.payout));
+    let game = contract.state.active_games.get(&alice).blocking_wait().expect("Failed to read games").expect("the roll is saved");
+    assert_eq!((game.result_roll, game.payout), (result.roll, result.payout));
+    assert!(!contract.state.pending.contains_key(&alice).blocking_wait().expect("Failed to read pending rolls"));
+}
+
+#[test]
+fn test_losing_roll_pays_nothing() {
+    let (contract, result, payout) = settled_roll(false);
+    let alice = bet::parse_owner(ALICE);
+
+    assert_eq!(payout, Amount::ZERO);
+    let game = contract.state.active_games.get(&alice).blocking_wait().expect("Failed to read games").expect("the roll is saved");
+    assert_eq!((game.result_roll, game.payout), (result.roll, 0));
+    assert!(!contract.state.pending.contains_key(&alice).blocking_wait().expect("Failed to read pending rolls"));
+}
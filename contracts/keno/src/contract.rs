@@ -1,24 +1,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
 
 use linera_sdk::{
     abi::WithContractAbi,
     views::{RootView, View},
+    linera_base_types::AccountOwner,
     Contract, ContractRuntime,
-    linera_base_types::{AccountOwner, Amount, ApplicationId},
 };
-use linera_base::crypto::{CryptoHash, BcsHashable};
-use keno::{Operation, KenoAbi, InstantiationArgument};
-use state::{KenoState, Game, PayoutTable};
-use serde::{Serialize, Deserialize};
-use std::str::FromStr;
-use std::collections::HashSet;
-
-#[derive(Serialize, Deserialize)]
-struct SeedWrapper(Vec<u8>);
-
-impl<'de> BcsHashable<'de> for SeedWrapper {}
+use keno::{Operation, KenoAbi, KenoOutcome, KenoParams, InstantiationArgument};
+use pulse_common::{bet, pending, GameConfig, GameKind, GameSettled, PendingBet, SettledBet};
+use state::{KenoState, Game};
 
 pub struct KenoContract {
     state: KenoState,
@@ -31,13 +26,11 @@ impl WithContractAbi for KenoContract {
     type Abi = KenoAbi;
 }
 
-const PULSE_TOKEN_APP_ID: &str = "8e7498a4564d33c50bc4a3053eba7b51a4f5e7085111dbcc7cd3efe6072a7961";
-
 impl Contract for KenoContract {
     type Message = ();
-    type Parameters = ();
+    type Parameters = GameConfig;
     type InstantiationArgument = InstantiationArgument;
-    type EventValue = ();
+    type EventValue = GameSettled<KenoOutcome>;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = KenoState::load(runtime.root_view_storage_context())
@@ -47,13 +40,28 @@ impl Contract for KenoContract {
     }
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
-        self.state.pulse_token_id.set(Some(argument.pulse_token_id));
+        self.state.setup.instantiate(&mut self.runtime, argument.pulse_token_id, argument.leaderboard_id);
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
         match operation {
-            Operation::Play { bet_amount, picks, owner } => {
-                self.execute_play(bet_amount, picks, owner).await
+            Operation::Play { bet_amount, picks, owner, client_seed } => {
+                self.execute_play(bet_amount, picks, owner, client_seed).await
+            }
+            Operation::Refund { owner } => {
+                self.state.setup.refund_bet(&mut self.runtime, &mut self.state.pending, owner).await;
+            }
+            Operation::UpdateConfig { config } => self.state.setup.update_config(&mut self.runtime, config),
+            Operation::CommitHouseSeeds { hash } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                self.state.seeds.commit_house_chain(hash);
+            }
+            Operation::PublishHouseSeed { seed } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                let revealed = self.state.seeds.publish_house_seed(&mut self.runtime, seed);
+                for draw in pending::take_due(&mut self.state.pending, &revealed.server_seed_hash).await {
+                    self.settle(draw).await;
+                }
             }
         }
     }
@@ -66,108 +74,52 @@ impl Contract for KenoContract {
 }
 
 impl KenoContract {
-    async fn execute_play(&mut self, bet_amount: u64, picks: Vec<u8>, owner: String) {
-        // 1. Basic Validation
-        assert!(bet_amount > 0, "Bet amount must be positive");
-        let pick_count = picks.len();
-        assert!(pick_count >= 1 && pick_count <= 10, "Must pick between 1 and 10 numbers");
-
-        // Validate Picks (Unique, 1-40)
-        let mut unique_picks = HashSet::new();
-        for &num in &picks {
-            assert!(num >= 1 && num <= 40, "Numbers must be between 1 and 40");
-            assert!(unique_picks.insert(num), "Duplicate numbers not allowed");
-        }
-
-        // 2. Auth Check (CRITICAL: Fixes MetaMask Popup flow)
-        let account_owner = AccountOwner::from_str(&owner).expect("Invalid owner address");
-        let signer = self.runtime.authenticated_signer();
-        assert_eq!(signer, Some(account_owner), "Operation must be signed by the owner");
-
-        // 3. Resolve Token App ID
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID)
-                        .expect("Invalid hardcoded PulseToken App ID"),
-        };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-
-        // 4. Debit Tokens
-        let debit_op = pulse_token::Operation::GameDebit {
-            owner: account_owner,
-            amount: Amount::from_tokens(bet_amount.into()),
-        };
-        self.runtime.call_application(true, token_app_id, &debit_op);
-
-        // 5. Generate Randomness (Draw 10 numbers)
-        let nonce = *self.state.nonce.get();
-        self.state.nonce.set(nonce + 1);
-
-        // Seed: Chain + Time + User + Nonce
-        let seed_data = (self.runtime.chain_id(), self.runtime.system_time(), &owner, nonce);
-        let bytes = bcs::to_bytes(&seed_data).expect("Serialization failed");
-        let seed = SeedWrapper(bytes);
-        let hash = CryptoHash::new(&seed);
-        let hash_bytes = hash.as_bytes();
-
-        // Fisher-Yates Shuffle on [1..40]
-        let mut deck: Vec<u8> = (1..=40).collect();
-        // Use hash bytes for entropy. We need enough randomness.
-        // We have 32 bytes of hash. We can use a pseudo-RNG or simple indexing.
-        // For simplicity and determinism, we'll use a simple index swap.
-        // A standard 32-byte hash might not be enough for perfect F-Y of 40 items, 
-        // but for a game it's "fair enough" compared to complexity. 
-        // Better: Use the hash as a seed for a small LCG within the contract 
-        // or just accept the bias of simple modulo on bytes. 
-        
-        // Simple Shuffle: match each byte to a swap
-        for i in 0..10 { // We only need proper randomness for the first 10 slots
-             // Use successive bytes. If we run out, wrap around.
-             let byte_val = hash_bytes[i % 32] as usize;
-             // Swap index i with index j (where j >= i)
-             let range = 40 - i;
-             let offset = byte_val % range;
-             let j = i + offset;
-             
-             deck.swap(i, j);
-        }
-
-        // Take first 10
-        let mut drawn_numbers: Vec<u8> = deck.into_iter().take(10).collect();
-        drawn_numbers.sort(); // Sort for display niceness logic, though UI can handle it
-
-        // 6. Calculate Hits
-        let hits = picks.iter().filter(|&p| drawn_numbers.contains(p)).count();
-        let hits_u8 = hits as u8;
-
-        // 7. Calculate Payout
-        let multiplier = PayoutTable::get_multiplier(pick_count, hits);
-        let payout = (bet_amount as u128 * multiplier as u128 / 100) as u64;
+    async fn execute_play(&mut self, bet_amount: u64, picks: Vec<u8>, owner: AccountOwner, client_seed: String) {
+        let config = self.state.setup.config();
+        config.check_stake(bet_amount);
+        let params = KenoParams { bet_amount, picks };
+        params.check().unwrap_or_else(|error| panic!("{error}"));
+        config.check_payout(bet::apply_multiplier(bet_amount, keno::max_multiplier(params.picks.len(), config.house_edge_bps)));
+        bet::check_signer(&mut self.runtime, owner);
+        let waiting = self.state.pending.contains_key(&owner).await.expect("Failed to read pending draws");
+        assert!(!waiting, "A draw is already waiting for the house");
+
+        let draw = self.state.setup.lock_bet(&mut self.runtime, &mut self.state.seeds, owner, bet_amount, params, client_seed);
+        self.state.pending.insert(&owner, draw).expect("Failed to save draw");
+    }
 
-        // 8. Credit Tokens (if win)
-        if payout > 0 {
-            let credit_op = pulse_token::Operation::GameCredit {
-                owner: account_owner,
-                amount: Amount::from_tokens(payout.into()),
-            };
-            self.runtime.call_application(true, token_app_id, &credit_op);
-        }
+    /// Draws 10 numbers for a bet whose link is out and pays it out
+    async fn settle(&mut self, draw: PendingBet<KenoParams>) {
+        let mut rng = self.state.seeds.rng_for(&draw.seeds).await.expect("the draw's link is published");
+        let outcome = keno::play_keno(&mut rng, &draw.params, draw.house_edge_bps);
+        let KenoOutcome { drawn_numbers, hits, multiplier, payout } = outcome.clone();
+        self.state.setup.token().settle(&mut self.runtime, draw.hold_reference, bet::tokens(payout));
 
-        // 9. Save Game State
         let game = Game {
-            owner,
-            bet_amount,
-            picks: picks.clone(), // Keep original order or sorted? UI handles it.
+            owner: draw.owner,
+            bet_amount: draw.stake,
+            picks: draw.params.picks.clone(),
             drawn_numbers,
-            hits: hits_u8,
+            hits,
             payout,
             multiplier,
             timestamp: self.runtime.system_time().micros(),
+            seeds: draw.seeds.clone(),
+        };
+        let settled = SettledBet {
+            game: GameKind::Keno,
+            owner: draw.owner,
+            stake: draw.stake,
+            params: draw.params,
+            outcome,
+            payout,
+            house_edge_bps: draw.house_edge_bps,
+            seeds: draw.seeds,
         };
-        // Save to active_games map keyed by owner address string
-        // We need 'owner' string variable available, which moved into Game. 
-        // Copy 'owner' string before moving.
-        let owner_key = game.owner.clone();
-        self.state.active_games.insert(&owner_key, game).expect("Failed to save game state");
+        self.state
+            .setup
+            .record_round(&mut self.runtime, &mut self.state.rounds, &mut self.state.round_index, settled)
+            .await;
+        self.state.active_games.insert(&game.owner, game).expect("Failed to save game");
     }
 }
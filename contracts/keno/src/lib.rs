@@ -1,7 +1,9 @@
 use linera_sdk::{
     abi::{ContractAbi, ServiceAbi},
-    linera_base_types::ApplicationId,
+    linera_base_types::{AccountOwner, ApplicationId},
 };
+use async_graphql::{InputObject, SimpleObject};
+use pulse_common::{bet, config, history, GameConfig, GameRng};
 use serde::{Deserialize, Serialize};
 
 pub struct KenoAbi;
@@ -19,13 +21,186 @@ impl ServiceAbi for KenoAbi {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct InstantiationArgument {
     pub pulse_token_id: ApplicationId,
+    /// Leaderboard the game reports its settled bets to, if any
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Operation {
+    /// Holds the stake; the numbers are drawn when the house publishes its next link
     Play {
         bet_amount: u64,
         picks: Vec<u8>,
-        owner: String,
+        owner: AccountOwner,
+        client_seed: String,
     },
+    /// Returns the stake of a draw the house has left waiting too long
+    Refund { owner: AccountOwner },
+    /// Admin only
+    UpdateConfig { config: GameConfig },
+    /// Admin only: starts a house seed chain from the hash of its first link
+    CommitHouseSeeds { hash: String },
+    /// Admin only: publishes the next link of the house seed chain and settles the draws
+    /// waiting for it
+    PublishHouseSeed { seed: String },
 }
+
+/// The bet a draw is verified against
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "KenoParamsInput")]
+pub struct KenoParams {
+    #[serde(alias = "betAmount")]
+    pub bet_amount: u64,
+    pub picks: Vec<u8>,
+}
+
+impl KenoParams {
+    pub fn check(&self) -> Result<(), &'static str> {
+        if !(1..=10).contains(&self.picks.len()) {
+            return Err("Must pick between 1 and 10 numbers");
+        }
+        for (index, pick) in self.picks.iter().enumerate() {
+            if !(1..=40).contains(pick) {
+                return Err("Numbers must be between 1 and 40");
+            }
+            if self.picks[..index].contains(pick) {
+                return Err("Duplicate numbers not allowed");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject)]
+pub struct KenoOutcome {
+    pub drawn_numbers: Vec<u8>,
+    pub hits: u8,
+    pub multiplier: u64,
+    pub payout: u64,
+}
+
+/// The top prize for `picks` numbers after the house edge, in percent.
+pub fn max_multiplier(picks: usize, house_edge_bps: u16) -> u64 {
+    config::apply_house_edge(PayoutTable::get_max_multiplier(picks), house_edge_bps)
+}
+
+/// Draws 10 of the numbers 1-40 and scores the picks; shared by the contract, the service
+/// and pulsebet-verify.
+pub fn play_keno(rng: &mut GameRng, params: &KenoParams, house_edge_bps: u16) -> KenoOutcome {
+    // Fisher-Yates Shuffle on [1..40]; we only need the first 10 slots
+    let mut deck: Vec<u8> = (1..=40).collect();
+    rng.partial_shuffle(&mut deck, 10);
+
+    // Take first 10
+    let mut drawn_numbers: Vec<u8> = deck.into_iter().take(10).collect();
+    drawn_numbers.sort(); // Sort for display niceness logic, though UI can handle it
+
+    let hits = params.picks.iter().filter(|&p| drawn_numbers.contains(p)).count();
+    let multiplier = config::apply_house_edge(PayoutTable::get_multiplier(params.picks.len(), hits), house_edge_bps);
+    let payout = bet::apply_multiplier(params.bet_amount, multiplier);
+
+    KenoOutcome {
+        drawn_numbers,
+        hits: hits as u8,
+        multiplier,
+        payout,
+    }
+}
+
+pub struct PayoutTable;
+
+impl PayoutTable {
+    // Multipliers are scaled by 100 (e.g., 200 = 2.0x, 50 = 0.5x, 0 = 0x)
+    // Indexes: [picks_count][hits_count]
+    // Note: picks_count 0 is impossible, so row 0 is dummy.
+    pub fn get_multiplier(picks: usize, hits: usize) -> u64 {
+        match picks {
+            1 => match hits {
+                1 => 380, // 1/40 chance roughly x house edge? 3.8x
+                _ => 0,
+            },
+            2 => match hits {
+                2 => 1400, // 14x
+                _ => 0,
+            },
+            3 => match hits {
+                3 => 4500, // 45x
+                2 => 100,  // 1x
+                _ => 0,
+            },
+            4 => match hits {
+                4 => 8000,
+                3 => 400,
+                2 => 50, // 0.5x (money back half)
+                _ => 0,
+            },
+            5 => match hits {
+                5 => 25000, // 250x
+                4 => 1500,
+                3 => 200,
+                _ => 0,
+            },
+            6 => match hits {
+                6 => 50000,
+                5 => 3000,
+                4 => 300,
+                3 => 50,
+                _ => 0,
+            },
+            7 => match hits {
+                7 => 100000,
+                6 => 10000,
+                5 => 1200,
+                4 => 100,
+                _ => 0,
+            },
+            8 => match hits {
+                8 => 200000, // 2000x
+                7 => 25000,
+                6 => 5000,
+                5 => 400,
+                _ => 0,
+            },
+            9 => match hits {
+                9 => 500000, // 5000x
+                8 => 100000,
+                7 => 8000,
+                6 => 600,
+                4 => 50,
+                _ => 0,
+            },
+            10 => match hits {
+                10 => 1000000, // 10,000x Jackpot
+                9 => 200000,
+                8 => 20000,
+                7 => 2000,
+                6 => 200,
+                5 => 50,
+                0 => 100, // Fun feature: 10 picks, 0 hits = money back
+                _ => 0,
+            },
+            _ => 0,
+        }
+    }
+
+    pub fn get_max_multiplier(picks: usize) -> u64 {
+        // Just return the top prize for that pick count
+        match picks {
+            1 => 380,
+            2 => 1400,
+            3 => 4500,
+            4 => 8000,
+            5 => 25000,
+            6 => 50000,
+            7 => 100000,
+            8 => 200000,
+            9 => 500000,
+            10 => 1000000,
+            _ => 0,
+        }
+    }
+}
+
+/// A settled draw, as kept in its owner's history
+pub type Round = history::Round<KenoParams, KenoOutcome>;
@@ -5,12 +5,14 @@ mod state;
 use async_graphql::{EmptySubscription, Object, Schema, Request, Response, SimpleObject};
 use linera_sdk::{
     abi::WithServiceAbi,
+    linera_base_types::AccountOwner,
     views::View,
     Service, ServiceRuntime,
 };
 use std::sync::Arc;
-use keno::{Operation, KenoAbi};
-use state::KenoState;
+use keno::{Operation, KenoAbi, KenoOutcome, KenoParams, Round};
+use pulse_common::{fairness, history, BetSeeds, GameConfig, HouseChain, PendingBet, RevealedSeed, SeedCommitment, Verification};
+use state::{Game, KenoState};
 
 pub struct KenoService {
     state: Arc<KenoState>,
@@ -24,7 +26,7 @@ impl WithServiceAbi for KenoService {
 }
 
 impl Service for KenoService {
-    type Parameters = ();
+    type Parameters = GameConfig;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = KenoState::load(runtime.root_view_storage_context())
@@ -53,18 +55,59 @@ struct QueryRoot {
 
 #[Object]
 impl QueryRoot {
-    async fn last_game(&self, owner: String) -> Option<PublicGame> {
-        let game = self.state.active_games.get(&owner).await.ok().flatten()?;
-        Some(PublicGame {
-            owner: game.owner,
-            bet_amount: game.bet_amount,
-            picks: game.picks,
-            drawn_numbers: game.drawn_numbers,
-            hits: game.hits,
-            payout: game.payout,
-            multiplier: game.multiplier,
-            timestamp: game.timestamp,
-        })
+    /// The owner's last draw
+    async fn last_game(&self, owner: AccountOwner) -> Option<PublicGame> {
+        let game = self.state.active_games.get(&owner).await.expect("Failed to read games");
+        game.map(PublicGame::from)
+    }
+
+    /// The owner's draw waiting for the house to publish its link
+    async fn pending_draw(&self, owner: AccountOwner) -> Option<PendingBet<KenoParams>> {
+        self.state.pending.get(&owner).await.expect("Failed to read pending draws")
+    }
+
+    /// Hash of the house link the next draw's numbers will come from
+    async fn seed_commitment(&self) -> Option<SeedCommitment> {
+        self.state.seeds.commitment()
+    }
+
+    async fn revealed_seed(&self, server_seed_hash: String) -> Option<RevealedSeed> {
+        self.state.seeds.revealed(&server_seed_hash).await
+    }
+
+    /// Latest published link of the house seed chain
+    async fn house_chain(&self) -> Option<HouseChain> {
+        self.state.seeds.house_chain()
+    }
+
+    /// The owner's settled rounds, newest first. Pass the last `roundId` of a page as `after`
+    /// to get the next one.
+    async fn history(&self, owner: AccountOwner, first: Option<u32>, after: Option<u64>) -> Vec<Round> {
+        self.state.round_index.rounds(&self.state.rounds, &owner, first, after).await
+    }
+
+    async fn round(&self, round_id: u64) -> Option<Round> {
+        history::round(&self.state.rounds, round_id).await
+    }
+
+    async fn config(&self) -> Option<GameConfig> {
+        self.state.setup.config.get().clone()
+    }
+
+    /// Recomputes a draw from its revealed server seed
+    async fn verify(
+        &self,
+        server_seed: String,
+        client_seed: String,
+        nonce: u64,
+        params: KenoParams,
+        /// Edge of the config the bet was placed under; defaults to the current one
+        house_edge_bps: Option<u16>,
+    ) -> async_graphql::Result<Verification<KenoOutcome>> {
+        params.check()?;
+        let (server_seed_hash, mut rng) = fairness::replay(&server_seed, &client_seed, nonce)?;
+        let outcome = keno::play_keno(&mut rng, &params, self.state.setup.house_edge_bps(house_edge_bps));
+        Ok(Verification { server_seed_hash, outcome })
     }
 }
 
@@ -74,20 +117,41 @@ struct MutationRoot {
 
 #[Object]
 impl MutationRoot {
-    async fn play_keno(&self, bet_amount: u64, picks: Vec<u8>, owner: String) -> Vec<u8> {
+    async fn play_keno(&self, bet_amount: u64, picks: Vec<u8>, owner: AccountOwner, client_seed: String) -> Vec<u8> {
         let op = Operation::Play {
             bet_amount,
             picks,
             owner,
+            client_seed,
         };
         self.runtime.schedule_operation(&op);
         Vec::new()
     }
+
+    async fn refund(&self, owner: AccountOwner) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::Refund { owner });
+        Vec::new()
+    }
+
+    async fn update_config(&self, config: GameConfig) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::UpdateConfig { config });
+        Vec::new()
+    }
+
+    async fn commit_house_seeds(&self, hash: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::CommitHouseSeeds { hash });
+        Vec::new()
+    }
+
+    async fn publish_house_seed(&self, seed: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::PublishHouseSeed { seed });
+        Vec::new()
+    }
 }
 
 #[derive(SimpleObject)]
 pub struct PublicGame {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub bet_amount: u64,
     pub picks: Vec<u8>,
     pub drawn_numbers: Vec<u8>,
@@ -95,4 +159,21 @@ pub struct PublicGame {
     pub payout: u64,
     pub multiplier: u64,
     pub timestamp: u64,
+    pub seeds: BetSeeds,
+}
+
+impl From<Game> for PublicGame {
+    fn from(game: Game) -> Self {
+        PublicGame {
+            owner: game.owner,
+            bet_amount: game.bet_amount,
+            picks: game.picks,
+            drawn_numbers: game.drawn_numbers,
+            hits: game.hits,
+            payout: game.payout,
+            multiplier: game.multiplier,
+            timestamp: game.timestamp,
+            seeds: game.seeds,
+        }
+    }
 }
@@ -0,0 +1,90 @@
+use super::*;
+use linera_sdk::{linera_base_types::Amount, util::BlockingWait};
+use pulse_common::{
+    fairness,
+    testing::{self, GameUnderTest, ALICE},
+    GameSetup,
+};
+
+impl GameUnderTest for KenoContract {
+    fn deploy(runtime: ContractRuntime<Self>) -> Self {
+        let state = KenoState::load(runtime.root_view_storage_context())
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        KenoContract { state, runtime }
+    }
+
+    fn parts(&mut self) -> (&mut GameSetup, &mut ContractRuntime<Self>) {
+        (&mut self.state.setup, &mut self.runtime)
+    }
+
+    fn bet(owner: AccountOwner) -> Operation {
+        Operation::Play { bet_amount: 10, picks: vec![1, 2, 3], owner, client_seed: "seed".into() }
+    }
+
+    fn refund(owner: AccountOwner) -> Operation {
+        Operation::Refund { owner }
+    }
+
+    fn update_config(config: GameConfig) -> Operation {
+        Operation::UpdateConfig { config }
+    }
+
+    fn commit_house_seeds(hash: String) -> Operation {
+        Operation::CommitHouseSeeds { hash }
+    }
+
+    fn publish_house_seed(seed: String) -> Operation {
+        Operation::PublishHouseSeed { seed }
+    }
+}
+
+#[test]
+fn test_shared_game_rules() {
+    testing::check_shared_rules::<KenoContract>();
+}
+
+/// Settles a draw of Alice's on 1, 2 and 3 that pays out or not as asked, returning its outcome
+/// and the payout of its hold.
+fn settled_draw(pays: bool) -> (KenoContract, KenoOutcome, Amount) {
+    let params = KenoParams { bet_amount: 10, picks: vec![1, 2, 3] };
+    let link = &testing::house_links()[0];
+    let client_seed = testing::client_seed_where(link, 0, |rng| {
+        (keno::play_keno(rng, &params, 100).payout > 0) == pays
+    });
+    let (_, mut rng) = fairness::replay(link, &client_seed, 0).expect("test seeds are hex-encoded");
+    let outcome = keno::play_keno(&mut rng, &params, 100);
+
+    let owner = bet::parse_owner(ALICE);
+    let play = Operation::Play { bet_amount: 10, picks: params.picks, owner, client_seed };
+    let (contract, payout) = testing::settle_bet::<KenoContract>(play, 10);
+    (contract, outcome, payout)
+}
+
+/// Alice's settled draw, checking it no longer waits for the house.
+fn saved_draw(contract: &KenoContract) -> Game {
+    let alice = bet::parse_owner(ALICE);
+    let waiting = contract.state.pending.contains_key(&alice).blocking_wait();
+    assert!(!waiting.expect("Failed to read pending draws"));
+    let game = contract.state.active_games.get(&alice).blocking_wait();
+    game.expect("Failed to read games").expect("the draw is saved")
+}
+
+#[test]
+fn test_draw_with_enough_hits_pays_out() {
+    let (contract, outcome, payout) = settled_draw(true);
+
+    assert!(outcome.payout > 0);
+    assert_eq!(payout, bet::tokens(outcome.payout));
+    let game = saved_draw(&contract);
+    assert_eq!((game.drawn_numbers, game.hits, game.payout), (outcome.drawn_numbers, outcome.hits, outcome.payout));
+}
+
+#[test]
+fn test_draw_with_too_few_hits_pays_nothing() {
+    let (contract, outcome, payout) = settled_draw(false);
+
+    assert_eq!(payout, Amount::ZERO);
+    let game = saved_draw(&contract);
+    assert_eq!((game.drawn_numbers, game.hits, game.payout), (outcome.drawn_numbers, outcome.hits, 0));
+}
@@ -0,0 +1,106 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use leaderboard::{InstantiationArgument, LeaderboardAbi, Message, Operation};
+use linera_sdk::{
+    linera_base_types::{AccountOwner, Amount, ApplicationId, WithContractAbi},
+    views::{RootView, View},
+    Contract, ContractRuntime,
+};
+
+use self::state::LeaderboardState;
+
+pub struct LeaderboardContract {
+    state: LeaderboardState,
+    runtime: ContractRuntime<Self>,
+}
+
+linera_sdk::contract!(LeaderboardContract);
+
+impl WithContractAbi for LeaderboardContract {
+    type Abi = LeaderboardAbi;
+}
+
+impl Contract for LeaderboardContract {
+    type Message = Message;
+    type Parameters = ();
+    type InstantiationArgument = InstantiationArgument;
+    type EventValue = ();
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = LeaderboardState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        LeaderboardContract { state, runtime }
+    }
+
+    async fn instantiate(&mut self, argument: InstantiationArgument) {
+        self.state.admin.set(Some(argument.admin));
+    }
+
+    async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
+        match operation {
+            Operation::Record { owner, bet, payout } => {
+                let game_id = self
+                    .runtime
+                    .authenticated_caller_id()
+                    .expect("Only game applications can record bets");
+                let creator_chain_id = self.runtime.application_creator_chain_id();
+                if creator_chain_id == self.runtime.chain_id() {
+                    self.record(game_id, owner, bet, payout).await;
+                } else {
+                    let message = Message::Settled { game_id, owner, bet, payout };
+                    self.runtime.prepare_message(message).send_to(creator_chain_id);
+                }
+            }
+            Operation::AuthorizeGame { application_id, game } => {
+                self.check_admin();
+                self.state.games.insert(&application_id, game).expect("failed to update game registry");
+            }
+            Operation::RevokeGame { application_id } => {
+                self.check_admin();
+                self.state.games.remove(&application_id).expect("failed to update game registry");
+            }
+        }
+    }
+
+    async fn execute_message(&mut self, message: Message) {
+        match message {
+            Message::Settled { game_id, owner, bet, payout } => {
+                // Only this application sends the message, so `game_id` is the authenticated caller
+                self.record(game_id, owner, bet, payout).await;
+            }
+        }
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
+
+impl LeaderboardContract {
+    /// Counts the bet if `game_id` is in the registry; reports of other applications are dropped
+    /// rather than failing the game that sent them. The bet counts in the current period of this
+    /// chain's clock.
+    async fn record(&mut self, game_id: ApplicationId, owner: AccountOwner, bet: Amount, payout: Amount) {
+        let settled_at = self.runtime.system_time();
+        let game = self.state.games.get(&game_id).await.expect("failed to read game registry");
+        if let Some(game) = game {
+            self.state.record(game, owner, bet, payout, settled_at).await;
+        }
+    }
+
+    fn check_admin(&mut self) {
+        assert_eq!(
+            self.runtime.chain_id(),
+            self.runtime.application_creator_chain_id(),
+            "The game registry lives on the creator chain"
+        );
+        assert_eq!(
+            self.runtime.authenticated_signer(),
+            *self.state.admin.get(),
+            "Only the admin can manage the game registry"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Cross-game player statistics. Authorized games report every settled bet; the creator chain
+//! keeps each player's totals for every day, every week and all time.
+
+use async_graphql::{ComplexObject, Enum, SimpleObject};
+use linera_sdk::linera_base_types::{AccountOwner, Amount, ApplicationId, Timestamp};
+use serde::{Deserialize, Serialize};
+
+pub use pulse_common::{
+    leaderboard::{LeaderboardAbi, Operation},
+    GameKind,
+};
+
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InstantiationArgument {
+    /// Owner allowed to manage the registry of game applications
+    pub admin: AccountOwner,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum Message {
+    /// Sent to the creator chain, which keeps the boards. `game_id` is the application that
+    /// called `Record` on the sending chain. The creator chain counts the bet in the period of its
+    /// own clock, so a sender cannot place a bet in a past or future board.
+    Settled {
+        game_id: ApplicationId,
+        owner: AccountOwner,
+        bet: Amount,
+        payout: Amount,
+    },
+}
+
+const DAY_MICROS: u64 = 24 * 60 * 60 * 1_000_000;
+
+/// Period a board covers. Days and weeks are counted in UTC, weeks start on Monday.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Enum)]
+pub enum Window {
+    Daily,
+    Weekly,
+    AllTime,
+}
+
+impl Window {
+    pub const ALL: [Window; 3] = [Window::Daily, Window::Weekly, Window::AllTime];
+
+    /// Index of the period containing `time`: days or weeks since the Unix epoch, 0 for all time
+    pub fn period(self, time: Timestamp) -> u64 {
+        let day = time.micros() / DAY_MICROS;
+        match self {
+            Window::Daily => day,
+            // 1970-01-01 was a Thursday
+            Window::Weekly => (day + 3) / 7,
+            Window::AllTime => 0,
+        }
+    }
+}
+
+/// What a board is sorted by, highest first
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, Enum)]
+pub enum Ranking {
+    #[default]
+    Net,
+    Wagered,
+    BiggestMultiplier,
+}
+
+impl Ranking {
+    pub const ALL: [Ranking; 3] = [Ranking::Net, Ranking::Wagered, Ranking::BiggestMultiplier];
+
+    fn score(self, stats: &PlayerStats) -> i128 {
+        match self {
+            Ranking::Net => stats.net_attos(),
+            Ranking::Wagered => u128::from(stats.wagered) as i128,
+            Ranking::BiggestMultiplier => i128::from(stats.biggest_multiplier),
+        }
+    }
+
+    /// Where `owner` stands on a board ordered by this ranking
+    pub fn rank_key(self, owner: AccountOwner, stats: &PlayerStats) -> RankKey {
+        // Flipping the sign bit orders the scores as unsigned numbers; inverting the bits puts
+        // the highest first.
+        let score = !((self.score(stats) as u128) ^ (1 << 127));
+        RankKey { score: score.to_be_bytes(), owner }
+    }
+}
+
+/// Entry of a board's ranking index. Keys sort best first, both as values and once serialized,
+/// so the index is read in rank order; ties go to the lower owner so that pages are stable.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RankKey {
+    score: [u8; 16],
+    pub owner: AccountOwner,
+}
+
+/// A player's totals over one period
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
+#[graphql(complex)]
+pub struct PlayerStats {
+    pub wagered: Amount,
+    pub won: Amount,
+    pub rounds: u64,
+    /// Highest payout of a single bet, in percent of its stake
+    pub biggest_multiplier: u64,
+    pub rounds_by_game: Vec<GameRounds>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct GameRounds {
+    pub game: GameKind,
+    pub rounds: u64,
+}
+
+impl PlayerStats {
+    pub fn record(&mut self, game: GameKind, bet: Amount, payout: Amount) {
+        self.wagered.saturating_add_assign(bet);
+        self.won.saturating_add_assign(payout);
+        self.rounds += 1;
+        self.biggest_multiplier = self.biggest_multiplier.max(multiplier_percent(bet, payout));
+        match self.rounds_by_game.iter_mut().find(|entry| entry.game == game) {
+            Some(entry) => entry.rounds += 1,
+            None => {
+                self.rounds_by_game.push(GameRounds { game, rounds: 1 });
+                self.rounds_by_game.sort_by_key(|entry| entry.game);
+            }
+        }
+    }
+
+    /// Won minus wagered, in attos
+    pub fn net_attos(&self) -> i128 {
+        u128::from(self.won) as i128 - u128::from(self.wagered) as i128
+    }
+}
+
+#[ComplexObject]
+impl PlayerStats {
+    /// Won minus wagered, starting with `-` when the player is down
+    async fn net(&self) -> String {
+        if self.won >= self.wagered {
+            self.won.saturating_sub(self.wagered).to_string()
+        } else {
+            format!("-{}", self.wagered.saturating_sub(self.won))
+        }
+    }
+}
+
+/// `payout` in percent of `bet`, rounded down; 0 for a free bet.
+pub fn multiplier_percent(bet: Amount, payout: Amount) -> u64 {
+    if bet == Amount::ZERO {
+        return 0;
+    }
+    u64::try_from(u128::from(payout) * 100 / u128::from(bet)).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,151 @@
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use std::sync::Arc;
+
+use async_graphql::{EmptySubscription, Object, Request, Response, Schema, SimpleObject};
+use leaderboard::{GameKind, LeaderboardAbi, Operation, PlayerStats, Ranking, Window};
+use linera_sdk::{
+    graphql::GraphQLMutationRoot,
+    linera_base_types::{AccountOwner, ApplicationId, WithServiceAbi},
+    views::View,
+    Service, ServiceRuntime,
+};
+
+use self::state::LeaderboardState;
+
+#[derive(Clone)]
+pub struct LeaderboardService {
+    state: Arc<LeaderboardState>,
+    runtime: Arc<ServiceRuntime<Self>>,
+}
+
+linera_sdk::service!(LeaderboardService);
+
+impl WithServiceAbi for LeaderboardService {
+    type Abi = LeaderboardAbi;
+}
+
+impl Service for LeaderboardService {
+    type Parameters = ();
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = LeaderboardState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        LeaderboardService {
+            state: Arc::new(state),
+            runtime: Arc::new(runtime),
+        }
+    }
+
+    async fn handle_query(&self, request: Request) -> Response {
+        Schema::build(
+            self.clone(),
+            Operation::mutation_root(self.runtime.clone()),
+            EmptySubscription,
+        )
+        .finish()
+        .execute(request)
+        .await
+    }
+}
+
+#[Object]
+impl LeaderboardService {
+    /// Index of the current day or week, to pass as `period`
+    async fn current_period(&self, window: Window) -> u64 {
+        window.period(self.runtime.system_time())
+    }
+
+    /// `owner`'s totals, all time unless `window` is given, in which case `period` defaults to the
+    /// current one. Boards are kept on the creator chain, so query the application there.
+    async fn player(&self, owner: AccountOwner, window: Option<Window>, period: Option<u64>) -> Option<PlayerStats> {
+        let window = window.unwrap_or(Window::AllTime);
+        let period = period.unwrap_or_else(|| window.period(self.runtime.system_time()));
+        self.state.stats(window, period, &owner).await
+    }
+
+    /// Players of a period, best first, `first` at a time, starting after the `after` rank
+    async fn leaderboard(
+        &self,
+        window: Window,
+        period: Option<u64>,
+        order_by: Option<Ranking>,
+        first: Option<usize>,
+        after: Option<usize>,
+    ) -> LeaderboardPage {
+        let first = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        let period = period.unwrap_or_else(|| window.period(self.runtime.system_time()));
+        let start = after.unwrap_or(0);
+        let ranking = order_by.unwrap_or_default();
+        let mut keys = Vec::new();
+        if let Some(ranks) = self.state.ranks.try_load_entry(&(window, period, ranking)).await.expect("Failed to load ranking") {
+            // One key past the page tells whether there is a next one
+            let mut position = 0;
+            ranks
+                .for_each_index_while(|key| {
+                    if position >= start {
+                        keys.push(key);
+                    }
+                    position += 1;
+                    Ok(keys.len() <= first)
+                })
+                .await
+                .expect("Failed to read ranking");
+        }
+        let has_next_page = keys.len() > first;
+        keys.truncate(first);
+        let mut entries = Vec::with_capacity(keys.len());
+        if let Some(board) = self.state.boards.try_load_entry(&(window, period)).await.expect("Failed to load board") {
+            for (key, rank) in keys.into_iter().zip(start + 1..) {
+                let stats = board.get(&key.owner).await.expect("Failed to read board").unwrap_or_default();
+                entries.push(LeaderboardEntry { rank, owner: key.owner, stats });
+            }
+        }
+        LeaderboardPage {
+            end_cursor: entries.last().map(|entry| entry.rank),
+            has_next_page,
+            entries,
+        }
+    }
+
+    /// Game applications whose bets are counted
+    async fn games(&self) -> Vec<RegisteredGame> {
+        let games = self.state.games.index_values().await.expect("Failed to read game registry");
+        games
+            .into_iter()
+            .map(|(application_id, game)| RegisteredGame { application_id, game })
+            .collect()
+    }
+
+    async fn admin(&self) -> Option<AccountOwner> {
+        *self.state.admin.get()
+    }
+}
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(SimpleObject)]
+pub struct LeaderboardEntry {
+    /// Position on the board, starting at 1
+    pub rank: usize,
+    pub owner: AccountOwner,
+    pub stats: PlayerStats,
+}
+
+#[derive(SimpleObject)]
+pub struct LeaderboardPage {
+    pub entries: Vec<LeaderboardEntry>,
+    /// Pass as `after` to get the next page
+    pub end_cursor: Option<usize>,
+    pub has_next_page: bool,
+}
+
+#[derive(SimpleObject)]
+pub struct RegisteredGame {
+    pub application_id: ApplicationId,
+    pub game: GameKind,
+}
@@ -0,0 +1,48 @@
+use leaderboard::{GameKind, PlayerStats, RankKey, Ranking, Window};
+use linera_sdk::{
+    linera_base_types::{AccountOwner, Amount, ApplicationId, Timestamp},
+    views::{linera_views, CollectionView, MapView, RegisterView, RootView, SetView, ViewStorageContext},
+};
+
+#[derive(RootView)]
+#[view(context = ViewStorageContext)]
+pub struct LeaderboardState {
+    pub admin: RegisterView<Option<AccountOwner>>,
+    /// Applications allowed to report bets, and the game each one is
+    pub games: MapView<ApplicationId, GameKind>,
+    /// Player totals keyed by window and period; only the creator chain fills them
+    pub boards: CollectionView<(Window, u64), MapView<AccountOwner, PlayerStats>>,
+    /// Players of each board in rank order, one index per ranking, so that pages are read without
+    /// sorting the board
+    pub ranks: CollectionView<(Window, u64, Ranking), SetView<RankKey>>,
+}
+
+impl LeaderboardState {
+    /// Adds a settled bet to the owner's totals for its day, its week and all time.
+    pub async fn record(&mut self, game: GameKind, owner: AccountOwner, bet: Amount, payout: Amount, settled_at: Timestamp) {
+        for window in Window::ALL {
+            let period = window.period(settled_at);
+            let board = self.boards.load_entry_mut(&(window, period)).await.expect("failed to load board");
+            let previous = board.get(&owner).await.expect("failed to read board");
+            let mut stats = previous.clone().unwrap_or_default();
+            stats.record(game, bet, payout);
+            board.insert(&owner, stats.clone()).expect("failed to update board");
+            for ranking in Ranking::ALL {
+                let ranks = self
+                    .ranks
+                    .load_entry_mut(&(window, period, ranking))
+                    .await
+                    .expect("failed to load ranking");
+                if let Some(previous) = &previous {
+                    ranks.remove(&ranking.rank_key(owner, previous)).expect("failed to update ranking");
+                }
+                ranks.insert(&ranking.rank_key(owner, &stats)).expect("failed to update ranking");
+            }
+        }
+    }
+
+    pub async fn stats(&self, window: Window, period: u64, owner: &AccountOwner) -> Option<PlayerStats> {
+        let board = self.boards.try_load_entry(&(window, period)).await.expect("failed to load board")?;
+        board.get(owner).await.expect("failed to read board")
+    }
+}
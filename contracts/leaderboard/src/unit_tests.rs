@@ -0,0 +1,88 @@
+use super::*;
+use std::str::FromStr;
+
+fn tokens(amount: u128) -> Amount {
+    Amount::from_tokens(amount)
+}
+
+fn owner(byte: char) -> AccountOwner {
+    AccountOwner::from_str(&format!("0x{}", byte.to_string().repeat(64))).expect("valid owner")
+}
+
+#[test]
+fn test_record_adds_up_bets() {
+    let mut stats = PlayerStats::default();
+    stats.record(GameKind::Mines, tokens(10), tokens(25));
+    stats.record(GameKind::Dice, tokens(10), Amount::ZERO);
+    stats.record(GameKind::Mines, tokens(4), tokens(2));
+
+    assert_eq!(stats.wagered, tokens(24));
+    assert_eq!(stats.won, tokens(27));
+    assert_eq!(stats.rounds, 3);
+    assert_eq!(stats.biggest_multiplier, 250);
+    assert_eq!(stats.net_attos(), u128::from(tokens(3)) as i128);
+    let rounds: Vec<_> = stats.rounds_by_game.iter().map(|entry| (entry.game, entry.rounds)).collect();
+    assert_eq!(rounds, vec![(GameKind::Dice, 1), (GameKind::Mines, 2)]);
+}
+
+#[test]
+fn test_net_is_negative_for_a_losing_player() {
+    let mut stats = PlayerStats::default();
+    stats.record(GameKind::Wheel, tokens(10), tokens(4));
+    assert_eq!(stats.net_attos(), -(u128::from(tokens(6)) as i128));
+}
+
+#[test]
+fn test_multiplier_of_a_free_bet_is_zero() {
+    assert_eq!(multiplier_percent(Amount::ZERO, tokens(5)), 0);
+    assert_eq!(multiplier_percent(tokens(3), tokens(1)), 33);
+}
+
+#[test]
+fn test_periods() {
+    let day = DAY_MICROS;
+    // Thursday 1970-01-01 to Sunday 1970-01-04 are week 0, Monday 1970-01-05 starts week 1
+    assert_eq!(Window::Weekly.period(Timestamp::from(3 * day + day - 1)), 0);
+    assert_eq!(Window::Weekly.period(Timestamp::from(4 * day)), 1);
+    assert_eq!(Window::Weekly.period(Timestamp::from(10 * day + day - 1)), 1);
+    assert_eq!(Window::Weekly.period(Timestamp::from(11 * day)), 2);
+    assert_eq!(Window::Daily.period(Timestamp::from(4 * day + 1)), 4);
+    assert_eq!(Window::AllTime.period(Timestamp::from(100 * day)), 0);
+}
+
+#[test]
+fn test_ranking_orders_best_first_with_stable_ties() {
+    let mut winner = PlayerStats::default();
+    winner.record(GameKind::Keno, tokens(10), tokens(30));
+    let mut loser = PlayerStats::default();
+    loser.record(GameKind::Keno, tokens(50), Amount::ZERO);
+    let mut even = PlayerStats::default();
+    even.record(GameKind::Plinko, tokens(5), tokens(5));
+
+    let entries = vec![(owner('3'), even.clone()), (owner('1'), loser), (owner('2'), winner), (owner('0'), even)];
+    let ranked = |ranking: Ranking| {
+        let mut keys: Vec<_> = entries.iter().map(|(owner, stats)| ranking.rank_key(*owner, stats)).collect();
+        keys.sort();
+        keys.into_iter().map(|key| key.owner).collect::<Vec<_>>()
+    };
+    assert_eq!(ranked(Ranking::Net), vec![owner('2'), owner('0'), owner('3'), owner('1')]);
+    assert_eq!(ranked(Ranking::Wagered)[0], owner('1'));
+    assert_eq!(ranked(Ranking::BiggestMultiplier)[0], owner('2'));
+}
+
+#[test]
+fn test_rank_keys_serialize_in_rank_order() {
+    let mut keys = Vec::new();
+    for (byte, won) in [('1', 0), ('2', 3), ('3', 10), ('4', 25), ('5', 10)] {
+        let mut stats = PlayerStats::default();
+        stats.record(GameKind::Dice, tokens(10), tokens(won));
+        keys.push(Ranking::Net.rank_key(owner(byte), &stats));
+    }
+    let mut by_value = keys.clone();
+    by_value.sort();
+    let mut by_bytes = keys;
+    by_bytes.sort_by_key(|key| bcs::to_bytes(key).expect("serializable key"));
+    assert_eq!(by_value, by_bytes);
+    let owners: Vec<_> = by_value.iter().map(|key| key.owner).collect();
+    assert_eq!(owners, vec![owner('4'), owner('3'), owner('5'), owner('2'), owner('1')]);
+}
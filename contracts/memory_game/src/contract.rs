@@ -1,23 +1,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
 
 use linera_sdk::{
     abi::WithContractAbi,
     views::{RootView, View},
+    linera_base_types::AccountOwner,
     Contract, ContractRuntime,
-    linera_base_types::{AccountOwner, Amount, ApplicationId},
 };
-use linera_base::crypto::{BcsHashable, CryptoHash};
-use memory_game::{InstantiationArgument, MemoryGameAbi, Operation, OperationResponse, GameState};
-use state::{Card, Game, MemoryGameState};
-use serde::{Deserialize, Serialize};
-use std::str::FromStr;
-
-#[derive(Serialize, Deserialize)]
-struct SeedWrapper(Vec<u8>);
-
-impl<'de> BcsHashable<'de> for SeedWrapper {}
+use memory_game::{InstantiationArgument, MemoryGameAbi, Operation, OperationResponse, GameState, CardDraw, MemoryOutcome, MemoryParams, CARDS};
+use pulse_common::{bet, fairness, BetSeeds, GameConfig, GameKind, GameSettled, SettledBet};
+use state::{Game, MemoryGameState, PendingFlip};
 
 pub struct MemoryGameContract {
     state: MemoryGameState,
@@ -32,9 +28,9 @@ impl WithContractAbi for MemoryGameContract {
 
 impl Contract for MemoryGameContract {
     type Message = ();
-    type Parameters = ();
+    type Parameters = GameConfig;
     type InstantiationArgument = InstantiationArgument;
-    type EventValue = ();
+    type EventValue = GameSettled<MemoryOutcome>;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = MemoryGameState::load(runtime.root_view_storage_context())
@@ -44,19 +40,41 @@ impl Contract for MemoryGameContract {
     }
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
-        self.state.pulse_token_id.set(Some(argument.pulse_token_id));
+        self.state.setup.instantiate(&mut self.runtime, argument.pulse_token_id, argument.leaderboard_id);
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
         match operation {
-            Operation::CreateGame { stake_amount, owner } => {
-                self.execute_create_game(stake_amount, owner).await
+            Operation::CreateGame { stake_amount, owner, client_seed } => {
+                self.execute_create_game(stake_amount, owner, client_seed).await
             }
             Operation::RevealCard { card_id } => {
                 self.execute_reveal_card(card_id).await
             }
             Operation::ClaimPayout => {
-                self.execute_claim_payout().await
+                let owner = bet::signer(&mut self.runtime);
+                self.execute_claim_payout(owner).await
+            }
+            Operation::Forfeit { owner } => {
+                self.execute_forfeit(owner).await
+            }
+            Operation::Refund { owner } => {
+                self.execute_refund(owner).await
+            }
+            Operation::UpdateConfig { config } => {
+                self.state.setup.update_config(&mut self.runtime, config);
+                OperationResponse::ConfigUpdated
+            }
+            Operation::CommitHouseSeeds { hash } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                self.state.seeds.commit_house_chain(hash);
+                OperationResponse::HouseSeedsUpdated
+            }
+            Operation::PublishHouseSeed { seed } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                let revealed = self.state.seeds.publish_house_seed(&mut self.runtime, seed);
+                self.draw_waiting_cards(&revealed.server_seed_hash).await;
+                OperationResponse::HouseSeedsUpdated
             }
         }
     }
@@ -69,64 +87,104 @@ impl Contract for MemoryGameContract {
 }
 
 impl MemoryGameContract {
-    async fn execute_create_game(&mut self, stake_amount: u64, owner: String) -> OperationResponse {
-        assert!(stake_amount > 0, "Stake amount must be positive");
-
-        let account_owner = AccountOwner::from_str(&owner).expect("Invalid owner address");
-        let existing_game = self.state.active_game.get();
-        if let Some(game) = existing_game {
-             assert!(game.state != GameState::Playing, "Game already active. Finish or forfeit current game first.");
+    async fn execute_create_game(&mut self, stake_amount: u64, owner: AccountOwner, client_seed: String) -> OperationResponse {
+        let config = self.state.setup.config();
+        config.check_stake(stake_amount);
+        config.check_payout(memory_game::max_payout(stake_amount, config.house_edge_bps));
+        fairness::check_client_seed(&client_seed);
+
+        bet::check_signer(&mut self.runtime, owner);
+        if let Some(game) = self.game(&owner).await {
+             assert!(game.state != GameState::Playing, "Game already active. Finish or forfeit the current game first.");
+             // Pay out a finished game that was never claimed, so its stake is not left on hold
+             if game.state == GameState::Finished {
+                 self.execute_claim_payout(owner).await;
+             }
         }
 
-        const PULSE_TOKEN_APP_ID: &str = "8e7498a4564d33c50bc4a3053eba7b51a4f5e7085111dbcc7cd3efe6072a7961";
-        
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID).expect("Invalid hardcoded PulseToken App ID"),
-        };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-        
-        // Debit tokens (Cross-App Call)
-        let debit_op = pulse_token::Operation::GameDebit {
-            owner: account_owner,
-            amount: Amount::from_tokens(stake_amount.into()),
-        };
-        
-        self.runtime.call_application(true, token_app_id, &debit_op);
+        // Hold the stake until the payout is claimed (Cross-App Call)
+        let hold_reference = *self.state.nonce.get();
+        self.state.nonce.set(hold_reference + 1);
 
-        let cards = self.generate_shuffled_cards();
-        let cards_count = cards.len();
+        self.state.setup.token().lock(&mut self.runtime, owner, bet::tokens(stake_amount), hold_reference);
 
+        // No card is drawn yet: each image is drawn from the house link after the one it is
+        // first turned up under, so nobody knows the board before it is played
         let game = Game {
-            owner: owner.clone(),
-            cards,
+            owner,
             stake_amount,
+            images: vec![None; CARDS as usize],
             turn_count: 0,
             matched_cards: Vec::new(),
             first_revealed_card: None,
             state: GameState::Playing,
+            house_edge_bps: config.house_edge_bps,
+            hold_reference,
+            client_seed,
+            draws: Vec::new(),
+            pending_flip: None,
         };
 
-        self.state.active_game.set(Some(game));
+        self.save_game(owner, game);
 
         OperationResponse::GameCreated {
-            cards_count,
+            cards_count: CARDS as usize,
         }
     }
 
     async fn execute_reveal_card(&mut self, card_id: u8) -> OperationResponse {
-        let mut game = self.state.active_game.get().clone().expect("No active game found");
+        let owner = bet::signer(&mut self.runtime);
+        let mut game = self.game(&owner).await.expect("No active game found");
         
         // Validation
         assert!(game.state == GameState::Playing, "Game is not playing");
-        assert!(card_id < 12, "Invalid card ID");
+        assert!(game.pending_flip.is_none(), "A card is already waiting for the house");
+        assert!(card_id < CARDS, "Invalid card ID");
         assert!(!game.matched_cards.contains(&card_id), "Card already matched");
         
         if let Some(first_card) = game.first_revealed_card {
              assert!(first_card != card_id, "Card already revealed");
         }
 
-        let card_image_id = game.cards[card_id as usize].image_id;
+        if game.images[card_id as usize].is_none() {
+            let seeds = self.state.seeds.commit(game.client_seed.clone());
+            game.pending_flip = Some(PendingFlip { card: card_id, seeds, flipped_at: self.runtime.system_time() });
+            self.state.awaiting.insert(&owner).expect("Failed to save waiting game");
+            self.save_game(owner, game);
+            return OperationResponse::RevealPending { card_id };
+        }
+
+        let response = Self::turn_up(&mut game, card_id);
+        self.save_game(owner, game);
+        response
+    }
+
+    /// Draws the images of the cards waiting for the link that hashes to `server_seed_hash` and
+    /// plays their moves.
+    async fn draw_waiting_cards(&mut self, server_seed_hash: &str) {
+        let owners = self.state.awaiting.indices().await.expect("Failed to read waiting games");
+        for owner in owners {
+            let mut game = self.game(&owner).await.expect("Waiting games exist");
+            let flip = game.pending_flip.take().expect("Waiting games have a card face down");
+            if flip.seeds.server_seed_hash != server_seed_hash {
+                continue;
+            }
+            let mut rng = self.state.seeds.rng_for(&flip.seeds).await.expect("the card's link is published");
+            game.images[flip.card as usize] = Some(memory_game::draw_image(&mut rng, &game.images));
+            game.draws.push(CardDraw {
+                card: flip.card,
+                server_seed_hash: flip.seeds.server_seed_hash,
+                nonce: flip.seeds.nonce,
+            });
+            Self::turn_up(&mut game, flip.card);
+            self.state.awaiting.remove(&owner).expect("Failed to save waiting game");
+            self.save_game(owner, game);
+        }
+    }
+
+    /// Plays turning up `card_id`, whose image is drawn.
+    fn turn_up(game: &mut Game, card_id: u8) -> OperationResponse {
+        let card_image_id = game.images[card_id as usize].expect("the card is drawn");
         let mut is_match_result = None;
 
         match game.first_revealed_card {
@@ -136,14 +194,12 @@ impl MemoryGameContract {
             }
             Some(prev_card_id) => {
                 // Second card of the turn
-                assert!(card_id != prev_card_id, "Cannot click the same card twice");
-
-                let prev_card_image_id = game.cards[prev_card_id as usize].image_id;
+                let prev_card_image_id = game.images[prev_card_id as usize];
 
                 // Increment turn count
                 game.turn_count += 1;
 
-                let is_match = card_image_id == prev_card_image_id;
+                let is_match = Some(card_image_id) == prev_card_image_id;
                 is_match_result = Some(is_match);
 
                 if is_match {
@@ -154,14 +210,12 @@ impl MemoryGameContract {
                 game.first_revealed_card = None;
 
                 // Check win/loss conditions
-                if game.matched_cards.len() == 12 {
+                if game.matched_cards.len() == CARDS as usize {
                     game.state = GameState::Finished;
                 }
             }
         }
 
-        self.state.active_game.set(Some(game.clone()));
-
         OperationResponse::CardRevealed {
             image_id: card_image_id,
             is_match: is_match_result,
@@ -171,79 +225,101 @@ impl MemoryGameContract {
         }
     }
 
-    async fn execute_claim_payout(&mut self) -> OperationResponse {
-        let mut game = self.state.active_game.get().clone().expect("No active game to claim");
+    async fn execute_claim_payout(&mut self, owner: AccountOwner) -> OperationResponse {
+        let mut game = self.game(&owner).await.expect("No active game to claim");
         
         assert!(game.state == GameState::Finished, "Game not finished");
         
-        let payout = Self::calculate_payout_amount(game.stake_amount, game.turn_count);
-
-        if payout > 0 {
-            const PULSE_TOKEN_APP_ID: &str = "8e7498a4564d33c50bc4a3053eba7b51a4f5e7085111dbcc7cd3efe6072a7961";
-            let token_app_id = match self.state.pulse_token_id.get() {
-                Some(id) => *id,
-                None => ApplicationId::from_str(PULSE_TOKEN_APP_ID).expect("Invalid hardcoded PulseToken App ID"),
-            }.with_abi::<pulse_token::PulseTokenAbi>();
-            
-            let account_owner = AccountOwner::from_str(&game.owner).expect("Invalid owner address");
-
-            let credit_op = pulse_token::Operation::GameCredit {
-                owner: account_owner,
-                amount: Amount::from_tokens(payout.into()),
-            };
-
-            self.runtime.call_application(true, token_app_id, &credit_op);
-        }
+        let payout = memory_game::payout_for_turns(game.stake_amount, game.turn_count, game.house_edge_bps);
+
+        // Settle the held stake, paying out the winnings
+        self.state.setup.token().settle(&mut self.runtime, game.hold_reference, bet::tokens(payout));
+
+        self.record_round(&game, payout).await;
 
         game.state = GameState::Claimed;
-        self.state.active_game.set(Some(game));
+        self.save_game(owner, game);
 
         OperationResponse::PayoutClaimed {
             payout_amount: payout,
         }
     }
 
-    fn calculate_payout_amount(stake_amount: u64, turn_count: u8) -> u64 {
-        match turn_count {
-            6 => stake_amount * 20,
-            7..=8 => stake_amount * 5,
-            9..=10 => stake_amount * 3,
-            11..=12 => stake_amount * 3 / 2,
-            _ => 0,
+    async fn execute_forfeit(&mut self, owner: AccountOwner) -> OperationResponse {
+        bet::check_signer(&mut self.runtime, owner);
+        let mut game = self.game(&owner).await.expect("No active game found");
+        assert!(game.state == GameState::Playing, "Game is not playing");
+
+        // A card still face down is never drawn
+        if game.pending_flip.take().is_some() {
+            self.state.awaiting.remove(&owner).expect("Failed to save waiting game");
         }
+        self.state.setup.token().settle(&mut self.runtime, game.hold_reference, bet::tokens(0));
+        self.record_round(&game, 0).await;
+
+        game.state = GameState::Forfeited;
+        self.save_game(owner, game);
+        OperationResponse::Forfeited
     }
 
-    fn generate_shuffled_cards(&mut self) -> Vec<Card> {
-        // Create 12 cards: 6 pairs (image_id 0-5, each appears twice)
-        let mut cards = Vec::new();
-        for position in 0..12 {
-            let image_id = position / 2;  // 0,0,1,1,2,2,...,5,5
-            cards.push(Card { position, image_id });
-        }
+    async fn record_round(&mut self, game: &Game, payout: u64) {
+        // The round is verified from the link of its last draw, which every earlier link hashes from
+        let seeds = match game.draws.last() {
+            Some(last_draw) => BetSeeds {
+                server_seed_hash: last_draw.server_seed_hash.clone(),
+                client_seed: game.client_seed.clone(),
+                nonce: last_draw.nonce,
+            },
+            None => BetSeeds { client_seed: game.client_seed.clone(), ..BetSeeds::default() },
+        };
+        let params = MemoryParams {
+            stake_amount: game.stake_amount,
+            turn_count: game.turn_count,
+            draws: game.draws.clone(),
+        };
+        let outcome = MemoryOutcome {
+            image_ids: game.images.clone(),
+            payout,
+        };
+        let settled = SettledBet {
+            game: GameKind::MemoryGame,
+            owner: game.owner,
+            stake: game.stake_amount,
+            params,
+            outcome,
+            payout,
+            house_edge_bps: game.house_edge_bps,
+            seeds,
+        };
+        self.state
+            .setup
+            .record_round(&mut self.runtime, &mut self.state.rounds, &mut self.state.round_index, settled)
+            .await;
+    }
 
-        // Shuffle using on-chain randomness
-        let mut shuffled = Vec::new();
-        let mut remaining: Vec<u8> = (0..12).collect();
-        let mut nonce = 0;
-
-        while !remaining.is_empty() {
-            let data = (self.runtime.chain_id(), self.runtime.system_time(), nonce);
-            let bytes = bcs::to_bytes(&data).expect("Serialization failed");
-            let seed = SeedWrapper(bytes);
-            let hash = CryptoHash::new(&seed);
-            let index = (hash.as_bytes()[0] as usize) % remaining.len();
-            
-            let position = remaining.remove(index);
-            shuffled.push(cards[position as usize].clone());
-            
-            nonce += 1;
-        }
+    async fn execute_refund(&mut self, owner: AccountOwner) -> OperationResponse {
+        bet::check_signer(&mut self.runtime, owner);
+        let mut game = self.game(&owner).await.expect("No active game found");
+        let flip = game.pending_flip.take().expect("No card is waiting for the house");
+        assert!(
+            self.runtime.system_time() >= flip.refundable_at(),
+            "The house has until {} to turn up the card",
+            flip.refundable_at()
+        );
+
+        self.state.setup.token().release(&mut self.runtime, game.hold_reference);
+
+        game.state = GameState::Refunded;
+        self.state.awaiting.remove(&owner).expect("Failed to save waiting game");
+        self.save_game(owner, game);
+        OperationResponse::Refunded
+    }
 
-        // Reassign positions to be sequential
-        for (idx, card) in shuffled.iter_mut().enumerate() {
-            card.position = idx as u8;
-        }
+    async fn game(&self, owner: &AccountOwner) -> Option<Game> {
+        self.state.active_games.get(owner).await.expect("Failed to read games")
+    }
 
-        shuffled
+    fn save_game(&mut self, owner: AccountOwner, game: Game) {
+        self.state.active_games.insert(&owner, game).expect("Failed to save game");
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,9 @@
-use async_graphql::{Request, Response};
+use async_graphql::{InputObject, Request, Response, SimpleObject};
 use linera_sdk::{
     abi::{ContractAbi, ServiceAbi},
-    linera_base_types::ApplicationId,
+    linera_base_types::{AccountOwner, ApplicationId},
 };
+use pulse_common::{bet, config, fairness, history, GameConfig, GameRng};
 use serde::{Deserialize, Serialize};
 
 pub struct MemoryGameAbi;
@@ -10,6 +11,9 @@ pub struct MemoryGameAbi;
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct InstantiationArgument {
     pub pulse_token_id: ApplicationId,
+    /// Leaderboard the game reports its settled bets to, if any
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId>,
 }
 
 impl ContractAbi for MemoryGameAbi {
@@ -26,12 +30,36 @@ impl ServiceAbi for MemoryGameAbi {
 pub enum Operation {
     CreateGame {
         stake_amount: u64, // Amount in tokens
-        owner: String,     // Player address
+        owner: AccountOwner, // Must be the signer
+        client_seed: String,
     },
+    /// Turns a card face up. The image of a card seen for the first time is drawn from the next
+    /// house link, so the move completes when the house publishes it.
     RevealCard {
         card_id: u8,
     },
     ClaimPayout,
+    /// Gives up the owner's game; the stake goes to the house
+    Forfeit {
+        owner: AccountOwner,
+    },
+    /// Ends a game whose card the house has left waiting too long, returning the stake
+    Refund {
+        owner: AccountOwner,
+    },
+    /// Admin only
+    UpdateConfig {
+        config: GameConfig,
+    },
+    /// Admin only: starts a house seed chain from the hash of its first link
+    CommitHouseSeeds {
+        hash: String,
+    },
+    /// Admin only: publishes the next link of the house seed chain and turns up the cards waiting
+    /// for it
+    PublishHouseSeed {
+        seed: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,9 +74,17 @@ pub enum OperationResponse {
         matched_cards_count: usize,
         game_state: GameState,
     },
+    /// The card's image is drawn when the house publishes its next link
+    RevealPending {
+        card_id: u8,
+    },
     PayoutClaimed {
         payout_amount: u64,
     },
+    Forfeited,
+    Refunded,
+    HouseSeedsUpdated,
+    ConfigUpdated,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Copy)]
@@ -56,4 +92,114 @@ pub enum GameState {
     Playing,
     Finished,
     Claimed,
+    /// Given up by its owner; the stake went to the house
+    Forfeited,
+    /// Ended after the house left a card waiting too long; the stake is returned
+    Refunded,
 }
+
+pub const CARDS: u8 = 12;
+
+/// A card turned face up for the first time, drawn with the game's client seed
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, SimpleObject, InputObject)]
+#[graphql(input_name = "CardDrawInput")]
+pub struct CardDraw {
+    pub card: u8,
+    /// Hash of the house link the card's image was drawn from
+    #[serde(alias = "serverSeedHash")]
+    pub server_seed_hash: String,
+    pub nonce: u64,
+}
+
+/// The game a board is verified against: the cards drawn, in order; leave the turns out to only
+/// see the board
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "MemoryParamsInput")]
+pub struct MemoryParams {
+    #[graphql(default)]
+    #[serde(alias = "stakeAmount")]
+    pub stake_amount: u64,
+    #[graphql(default)]
+    #[serde(alias = "turnCount")]
+    pub turn_count: u8,
+    #[graphql(default)]
+    #[serde(default)]
+    pub draws: Vec<CardDraw>,
+}
+
+impl MemoryParams {
+    pub fn check(&self) -> Result<(), &'static str> {
+        if self.turn_count != 0 && self.turn_count < CARDS / 2 {
+            return Err("A board takes at least one turn per pair to clear");
+        }
+        for (index, draw) in self.draws.iter().enumerate() {
+            if draw.card >= CARDS {
+                return Err("Invalid card ID");
+            }
+            if self.draws[..index].iter().any(|earlier| earlier.card == draw.card) {
+                return Err("A card is only drawn once");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject)]
+pub struct MemoryOutcome {
+    /// Image of the card at each position, for the cards that were turned up
+    pub image_ids: Vec<Option<u8>>,
+    pub payout: u64,
+}
+
+/// Draws the image of a card turned up for the first time from the copies no card shows yet:
+/// 6 pairs (image_id 0-5, each appears twice). Shared by the contract, the service and
+/// pulsebet-verify.
+pub fn draw_image(rng: &mut GameRng, images: &[Option<u8>]) -> u8 {
+    let mut copies = Vec::new();
+    for image in 0..CARDS / 2 {
+        let shown = images.iter().filter(|shown| **shown == Some(image)).count();
+        copies.extend(std::iter::repeat(image).take(2usize.saturating_sub(shown)));
+    }
+    assert!(!copies.is_empty(), "Every card is already drawn");
+    copies[rng.below(copies.len() as u64) as usize]
+}
+
+/// Winnings for clearing the board in `turn_count` turns
+pub fn payout_for_turns(stake_amount: u64, turn_count: u8, house_edge_bps: u16) -> u64 {
+    let multiplier_percent = match turn_count {
+        6 => 2000,
+        7..=8 => 500,
+        9..=10 => 300,
+        11..=12 => 150,
+        _ => 0,
+    };
+    bet::apply_multiplier(stake_amount, config::apply_house_edge(multiplier_percent, house_edge_bps))
+}
+
+/// Winnings for a perfect game, one turn per pair
+pub fn max_payout(stake_amount: u64, house_edge_bps: u16) -> u64 {
+    payout_for_turns(stake_amount, CARDS / 2, house_edge_bps)
+}
+
+/// Redraws the cards of a game in order from `server_seed`, the revealed link of its last draw:
+/// each earlier draw's link is found by hashing back from it.
+pub fn replay_board(
+    server_seed: &str,
+    client_seed: &str,
+    params: &MemoryParams,
+    house_edge_bps: u16,
+) -> Result<MemoryOutcome, String> {
+    let mut image_ids = vec![None; CARDS as usize];
+    for draw in &params.draws {
+        let link = fairness::earlier_link(server_seed, &draw.server_seed_hash)?;
+        let (_, mut rng) = fairness::replay(&link, client_seed, draw.nonce)?;
+        image_ids[draw.card as usize] = Some(draw_image(&mut rng, &image_ids));
+    }
+    Ok(MemoryOutcome {
+        image_ids,
+        payout: payout_for_turns(params.stake_amount, params.turn_count, house_edge_bps),
+    })
+}
+
+/// A settled game, as kept in its owner's history
+pub type Round = history::Round<MemoryParams, MemoryOutcome>;
@@ -5,11 +5,13 @@ mod state;
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
 use linera_sdk::{
     abi::WithServiceAbi,
+    linera_base_types::AccountOwner,
     views::{View, ViewStorageContext},
     Service, ServiceRuntime,
 };
-use memory_game::{GameState, MemoryGameAbi, Operation};
-use state::{Card, Game, MemoryGameState};
+use memory_game::{CardDraw, GameState, MemoryGameAbi, MemoryOutcome, MemoryParams, Operation, Round};
+use pulse_common::{fairness, history, BetSeeds, GameConfig, HouseChain, RevealedSeed, SeedCommitment, Verification};
+use state::{Game, MemoryGameState};
 use std::sync::Arc;
 
 
@@ -25,7 +27,7 @@ impl WithServiceAbi for MemoryGameService {
 }
 
 impl Service for MemoryGameService {
-    type Parameters = ();
+    type Parameters = GameConfig;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = MemoryGameState::load(ViewStorageContext::from(runtime.root_view_storage_context()))
@@ -38,15 +40,8 @@ impl Service for MemoryGameService {
     }
 
     async fn handle_query(&self, request: Request) -> Response {
-        let game_opt = self.state.active_game.get();
-        
-        let active_game = game_opt.as_ref().map(|game| {
-            GameResponse::from(game.clone())
-        });
-
         let schema = Schema::build(
-            QueryRoot { 
-                active_game,
+            QueryRoot {
                 state: self.state.clone(),
             },
             MutationRoot {
@@ -61,32 +56,76 @@ impl Service for MemoryGameService {
 }
 
 struct QueryRoot {
-    active_game: Option<GameResponse>,
     state: Arc<MemoryGameState>,
 }
 
 #[Object]
 impl QueryRoot {
-    async fn active_game(&self) -> &Option<GameResponse> {
-        &self.active_game
+    /// The owner's current game, or their last one once it is claimed
+    async fn active_game(&self, owner: AccountOwner) -> Option<GameResponse> {
+        self.game(&owner).await.map(GameResponse::from)
     }
 
+    /// The cards of the owner's game; cards never turned up have no image yet
+    async fn cards(&self, player: AccountOwner) -> Option<Vec<CardResponse>> {
+        let game = self.game(&player).await?;
+        let cards = (0..).zip(game.images).map(|(position, image_id)| CardResponse {
+            position,
+            image_id: image_id.map(u32::from),
+        });
+        Some(cards.collect())
+    }
 
+    /// Hash of the house link the next card turned up for the first time will be drawn from
+    async fn seed_commitment(&self) -> Option<SeedCommitment> {
+        self.state.seeds.commitment()
+    }
 
-    async fn cards(&self, player: String) -> Option<Vec<CardResponse>> {
-        let game = self.state.active_game.get().as_ref()?;
-        
-        // Only return cards if the requesting player matches the game owner
-        if game.owner == player {
-            Some(
-                game.cards
-                    .iter()
-                    .map(|c| CardResponse::from(c.clone()))
-                    .collect()
-            )
-        } else {
-            None
-        }
+    async fn revealed_seed(&self, server_seed_hash: String) -> Option<RevealedSeed> {
+        self.state.seeds.revealed(&server_seed_hash).await
+    }
+
+    /// Latest published link of the house seed chain
+    async fn house_chain(&self) -> Option<HouseChain> {
+        self.state.seeds.house_chain()
+    }
+
+    /// The owner's settled rounds, newest first. Pass the last `roundId` of a page as `after`
+    /// to get the next one.
+    async fn history(&self, owner: AccountOwner, first: Option<u32>, after: Option<u64>) -> Vec<Round> {
+        self.state.round_index.rounds(&self.state.rounds, &owner, first, after).await
+    }
+
+    async fn round(&self, round_id: u64) -> Option<Round> {
+        history::round(&self.state.rounds, round_id).await
+    }
+
+    async fn config(&self) -> Option<GameConfig> {
+        self.state.setup.config.get().clone()
+    }
+
+    /// Recomputes a board from the revealed link of its last draw
+    async fn verify(
+        &self,
+        server_seed: String,
+        client_seed: String,
+        nonce: u64,
+        params: MemoryParams,
+        /// Edge of the config the bet was placed under; defaults to the current one
+        house_edge_bps: Option<u16>,
+    ) -> async_graphql::Result<Verification<MemoryOutcome>> {
+        params.check()?;
+        let (server_seed_hash, _) = fairness::replay(&server_seed, &client_seed, nonce)?;
+        let house_edge_bps = self.state.setup.house_edge_bps(house_edge_bps);
+        let outcome = memory_game::replay_board(&server_seed, &client_seed, &params, house_edge_bps)?;
+        Ok(Verification { server_seed_hash, outcome })
+    }
+}
+
+impl QueryRoot {
+
+    async fn game(&self, owner: &AccountOwner) -> Option<Game> {
+        self.state.active_games.get(owner).await.expect("Failed to read games")
     }
 }
 
@@ -96,8 +135,8 @@ struct MutationRoot {
 
 #[Object]
 impl MutationRoot {
-    async fn create_game(&self, stake_amount: u64, owner: String) -> Vec<u8> {
-        let op = Operation::CreateGame { stake_amount, owner };
+    async fn create_game(&self, stake_amount: u64, owner: AccountOwner, client_seed: String) -> Vec<u8> {
+        let op = Operation::CreateGame { stake_amount, owner, client_seed };
         self.runtime.schedule_operation(&op);
         Vec::new()
     }
@@ -113,25 +152,57 @@ impl MutationRoot {
         self.runtime.schedule_operation(&op);
         Vec::new()
     }
+
+    async fn forfeit(&self, owner: AccountOwner) -> Vec<u8> {
+        let op = Operation::Forfeit { owner };
+        self.runtime.schedule_operation(&op);
+        Vec::new()
+    }
+
+    async fn refund(&self, owner: AccountOwner) -> Vec<u8> {
+        let op = Operation::Refund { owner };
+        self.runtime.schedule_operation(&op);
+        Vec::new()
+    }
+
+    async fn update_config(&self, config: GameConfig) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::UpdateConfig { config });
+        Vec::new()
+    }
+
+    async fn commit_house_seeds(&self, hash: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::CommitHouseSeeds { hash });
+        Vec::new()
+    }
+
+    async fn publish_house_seed(&self, seed: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::PublishHouseSeed { seed });
+        Vec::new()
+    }
 }
 
 // GraphQL Response Types
 #[derive(Clone)]
 struct GameResponse {
-    player: String,
+    player: AccountOwner,
     stake_amount: u64,
     turn_count: u32,
     matched_cards_count: u32,
     matched_cards: Vec<u32>,
     first_revealed_card: Option<u32>,
+    waiting_card: Option<u32>,
     state: String,
     potential_payout: u64,
+    house_edge_bps: u16,
+    client_seed: String,
+    draws: Vec<CardDraw>,
+    waiting_seeds: Option<BetSeeds>,
 }
 
 #[Object]
 impl GameResponse {
-    async fn player(&self) -> String {
-        self.player.clone()
+    async fn player(&self) -> AccountOwner {
+        self.player
     }
 
     async fn stake_amount(&self) -> u64 {
@@ -154,6 +225,11 @@ impl GameResponse {
         self.first_revealed_card
     }
 
+    /// Card turned up for the first time, waiting for the house link its image is drawn from
+    async fn waiting_card(&self) -> Option<u32> {
+        self.waiting_card
+    }
+
     async fn state(&self) -> String {
         self.state.clone()
     }
@@ -161,6 +237,23 @@ impl GameResponse {
     async fn potential_payout(&self) -> u64 {
         self.potential_payout
     }
+
+    async fn house_edge_bps(&self) -> u16 {
+        self.house_edge_bps
+    }
+
+    async fn client_seed(&self) -> &str {
+        &self.client_seed
+    }
+
+    /// Cards drawn so far, in order
+    async fn draws(&self) -> &[CardDraw] {
+        &self.draws
+    }
+
+    async fn waiting_seeds(&self) -> Option<&BetSeeds> {
+        self.waiting_seeds.as_ref()
+    }
 }
 
 impl From<Game> for GameResponse {
@@ -172,30 +265,27 @@ impl From<Game> for GameResponse {
             matched_cards_count: game.matched_cards.len() as u32,
             matched_cards: game.matched_cards.into_iter().map(|c| c as u32).collect(),
             first_revealed_card: game.first_revealed_card.map(|c| c as u32),
+            waiting_card: game.pending_flip.as_ref().map(|flip| flip.card as u32),
             state: match game.state {
                 GameState::Playing => "PLAYING".to_string(),
                 GameState::Finished => "FINISHED".to_string(),
                 GameState::Claimed => "CLAIMED".to_string(),
+                GameState::Forfeited => "FORFEITED".to_string(),
+                GameState::Refunded => "REFUNDED".to_string(),
             },
-            potential_payout: calculate_potential_payout(game.stake_amount, game.turn_count),
+            potential_payout: memory_game::payout_for_turns(game.stake_amount, game.turn_count, game.house_edge_bps),
+            house_edge_bps: game.house_edge_bps,
+            client_seed: game.client_seed,
+            draws: game.draws,
+            waiting_seeds: game.pending_flip.map(|flip| flip.seeds),
         }
     }
 }
 
-fn calculate_potential_payout(stake_amount: u64, turn_count: u8) -> u64 {
-    match turn_count {
-        6 => stake_amount * 20,
-        7..=8 => stake_amount * 5,
-        9..=10 => stake_amount * 3,
-        11..=12 => stake_amount * 3 / 2,
-        _ => 0,
-    }
-}
-
 #[derive(Clone)]
 struct CardResponse {
     position: u32,
-    image_id: u32,
+    image_id: Option<u32>,
 }
 
 #[Object]
@@ -204,16 +294,7 @@ impl CardResponse {
         self.position
     }
 
-    async fn image_id(&self) -> u32 {
+    async fn image_id(&self) -> Option<u32> {
         self.image_id
     }
 }
-
-impl From<Card> for CardResponse {
-    fn from(card: Card) -> Self {
-        CardResponse {
-            position: card.position as u32,
-            image_id: card.image_id as u32,
-        }
-    }
-}
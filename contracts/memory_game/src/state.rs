@@ -1,31 +1,56 @@
 use crate::GameState;
 use linera_sdk::{
-    bcs,
-    linera_base_types::ApplicationId,
-    views::{RegisterView, RootView, View, ViewStorageContext},
+    linera_base_types::{AccountOwner, TimeDelta, Timestamp},
+    views::{LogView, MapView, RegisterView, RootView, SetView, ViewStorageContext},
 };
+use memory_game::{CardDraw, Round};
+use pulse_common::{fairness::REFUND_DELAY_SECS, BetSeeds, GameSetup, RoundIndex, SeedBook};
 use serde::{Deserialize, Serialize};
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct MemoryGameState {
-    pub active_game: RegisterView<Option<Game>>,
-    pub pulse_token_id: RegisterView<Option<ApplicationId>>,
+    /// Each owner's current or last game
+    pub active_games: MapView<AccountOwner, Game>,
+    /// Owners whose game has a card waiting for the house
+    pub awaiting: SetView<AccountOwner>,
+    pub setup: GameSetup,
+    pub nonce: RegisterView<u64>,
+    pub seeds: SeedBook,
+    /// Every settled round; a round's id is its index
+    pub rounds: LogView<Round>,
+    pub round_index: RoundIndex,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Game {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub stake_amount: u64,
-    pub cards: Vec<Card>,
+    /// Image of each card once it has been turned up; the rest are not drawn yet
+    pub images: Vec<Option<u8>>,
     pub matched_cards: Vec<u8>,
     pub first_revealed_card: Option<u8>,
     pub turn_count: u8,
     pub state: GameState,
+    pub house_edge_bps: u16, // Edge when the game was created; later config updates do not apply
+    pub hold_reference: u64, // PulseToken hold holding the stake until the payout is claimed
+    pub client_seed: String,
+    /// Cards drawn so far, in order
+    pub draws: Vec<CardDraw>,
+    pub pending_flip: Option<PendingFlip>,
 }
 
+/// A card turned up for the first time, waiting for the house link its image is drawn from
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Card {
-    pub position: u8,
-    pub image_id: u8,
+pub struct PendingFlip {
+    pub card: u8,
+    pub seeds: BetSeeds,
+    pub flipped_at: Timestamp,
+}
+
+impl PendingFlip {
+    /// When the owner may end the game with their stake back if the card is still face down
+    pub fn refundable_at(&self) -> Timestamp {
+        self.flipped_at.saturating_add(TimeDelta::from_secs(REFUND_DELAY_SECS))
+    }
 }
@@ -0,0 +1,285 @@
+use super::*;
+use linera_sdk::{linera_base_types::Amount, util::BlockingWait};
+use pulse_common::{
+    testing::{self, GameUnderTest, ALICE, BOB},
+    GameSetup,
+};
+
+impl GameUnderTest for MemoryGameContract {
+    fn deploy(runtime: ContractRuntime<Self>) -> Self {
+        let state = MemoryGameState::load(runtime.root_view_storage_context())
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        MemoryGameContract { state, runtime }
+    }
+
+    fn parts(&mut self) -> (&mut GameSetup, &mut ContractRuntime<Self>) {
+        (&mut self.state.setup, &mut self.runtime)
+    }
+
+    fn bet(owner: AccountOwner) -> Operation {
+        Operation::CreateGame { stake_amount: 10, owner, client_seed: "seed".into() }
+    }
+
+    fn refund(owner: AccountOwner) -> Operation {
+        Operation::Refund { owner }
+    }
+
+    fn update_config(config: GameConfig) -> Operation {
+        Operation::UpdateConfig { config }
+    }
+
+    fn commit_house_seeds(hash: String) -> Operation {
+        Operation::CommitHouseSeeds { hash }
+    }
+
+    fn publish_house_seed(seed: String) -> Operation {
+        Operation::PublishHouseSeed { seed }
+    }
+}
+
+#[test]
+fn test_shared_game_rules() {
+    testing::check_shared_rules::<MemoryGameContract>();
+}
+
+/// A contract whose blocks are signed by `signer`, with `ALICE` holding a finished, unclaimed game.
+fn contract_signed_by(signer: Option<&str>) -> MemoryGameContract {
+    let alice = bet::parse_owner(ALICE);
+    let mut contract = testing::contract_signed_by::<MemoryGameContract>(signer);
+    let game = Game {
+        owner: alice,
+        stake_amount: 10,
+        images: (0..CARDS).map(|position| Some(position / 2)).collect(),
+        matched_cards: (0..CARDS).collect(),
+        first_revealed_card: None,
+        turn_count: 6,
+        state: GameState::Finished,
+        house_edge_bps: 100,
+        hold_reference: 0,
+        client_seed: "seed".into(),
+        draws: (0..CARDS).map(|card| CardDraw { card, server_seed_hash: "00".repeat(32), nonce: card.into() }).collect(),
+        pending_flip: None,
+    };
+    contract.save_game(alice, game);
+    contract
+}
+
+#[test]
+#[should_panic(expected = "No active game to claim")]
+fn test_claiming_another_players_payout_is_rejected() {
+    let mut contract = contract_signed_by(Some(BOB));
+    contract.execute_operation(Operation::ClaimPayout).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "No active game found")]
+fn test_revealing_a_card_in_another_players_game_is_rejected() {
+    let mut contract = contract_signed_by(Some(BOB));
+    contract.execute_operation(Operation::RevealCard { card_id: 0 }).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "Operation must be signed")]
+fn test_unsigned_claim_is_rejected() {
+    let mut contract = contract_signed_by(None);
+    contract.execute_operation(Operation::ClaimPayout).blocking_wait();
+}
+
+/// Puts Alice's game back at the start of play: no pair matched, no turn taken.
+fn restart_alices_game(contract: &mut MemoryGameContract) {
+    let alice = bet::parse_owner(ALICE);
+    let mut game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    game.matched_cards.clear();
+    game.turn_count = 0;
+    game.state = GameState::Playing;
+    contract.save_game(alice, game);
+}
+
+#[test]
+fn test_owner_matches_a_pair_in_their_own_game() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    restart_alices_game(&mut contract);
+    contract.execute_operation(Operation::RevealCard { card_id: 0 }).blocking_wait();
+    let response = contract.execute_operation(Operation::RevealCard { card_id: 1 }).blocking_wait();
+
+    assert!(matches!(response, OperationResponse::CardRevealed { is_match: Some(true), turn_count: 1, .. }));
+    let game = contract.game(&bet::parse_owner(ALICE)).blocking_wait().expect("Alice has a game");
+    assert_eq!(game.matched_cards, vec![0, 1]);
+    assert_eq!(game.first_revealed_card, None);
+    assert_eq!(game.state, GameState::Playing);
+}
+
+#[test]
+#[should_panic(expected = "Game is not playing")]
+fn test_revealing_a_card_after_the_game_is_rejected() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    contract.execute_operation(Operation::RevealCard { card_id: 0 }).blocking_wait();
+}
+
+/// Turns every card of Alice's restarted game face down, as when it was created.
+fn hide_alices_board(contract: &mut MemoryGameContract) {
+    let alice = bet::parse_owner(ALICE);
+    restart_alices_game(contract);
+    let mut game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    game.images = vec![None; CARDS as usize];
+    game.draws.clear();
+    contract.save_game(alice, game);
+}
+
+#[test]
+fn test_a_card_is_drawn_when_the_house_publishes_its_link() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    let alice = bet::parse_owner(ALICE);
+    hide_alices_board(&mut contract);
+    let links = testing::house_links();
+    contract.execute_operation(Operation::CommitHouseSeeds { hash: testing::house_commitment() }).blocking_wait();
+
+    let response = contract.execute_operation(Operation::RevealCard { card_id: 3 }).blocking_wait();
+    assert!(matches!(response, OperationResponse::RevealPending { card_id: 3 }));
+    let game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    assert_eq!(game.images[3], None);
+    assert!(game.pending_flip.is_some());
+
+    contract.execute_operation(Operation::PublishHouseSeed { seed: links[0].clone() }).blocking_wait();
+    let game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    let draw = CardDraw { card: 3, server_seed_hash: testing::house_commitment(), nonce: 0 };
+    let (_, mut rng) = fairness::replay(&links[0], "seed", 0).expect("test seeds are hex-encoded");
+    let image = memory_game::draw_image(&mut rng, &[None; CARDS as usize]);
+    assert_eq!(game.images[3], Some(image));
+    assert_eq!(game.draws, vec![draw]);
+    assert_eq!(game.first_revealed_card, Some(3));
+    assert!(game.pending_flip.is_none());
+    assert!(!contract.state.awaiting.contains(&alice).blocking_wait().expect("Failed to read waiting games"));
+}
+
+#[test]
+#[should_panic(expected = "A card is already waiting for the house")]
+fn test_moves_wait_for_the_card_face_down() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    hide_alices_board(&mut contract);
+    contract.execute_operation(Operation::CommitHouseSeeds { hash: testing::house_commitment() }).blocking_wait();
+    contract.execute_operation(Operation::RevealCard { card_id: 3 }).blocking_wait();
+    contract.execute_operation(Operation::RevealCard { card_id: 4 }).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "The house has until")]
+fn test_refund_waits_for_the_house() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    hide_alices_board(&mut contract);
+    contract.execute_operation(Operation::CommitHouseSeeds { hash: testing::house_commitment() }).blocking_wait();
+    contract.execute_operation(Operation::RevealCard { card_id: 3 }).blocking_wait();
+    contract.execute_operation(Operation::Refund { owner: bet::parse_owner(ALICE) }).blocking_wait();
+}
+
+#[test]
+fn test_refund_releases_a_game_the_house_left_waiting() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    let alice = bet::parse_owner(ALICE);
+    hide_alices_board(&mut contract);
+    contract.execute_operation(Operation::CommitHouseSeeds { hash: testing::house_commitment() }).blocking_wait();
+    contract.execute_operation(Operation::RevealCard { card_id: 3 }).blocking_wait();
+    let calls = testing::record_token_calls(&mut contract.runtime);
+    testing::wait_out_refund_delay(&mut contract.runtime);
+    contract.execute_operation(Operation::Refund { owner: alice }).blocking_wait();
+
+    assert!(matches!(calls.lock().unwrap()[..], [pulse_token::Operation::Release { reference: 0 }]));
+    let game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    assert_eq!(game.state, GameState::Refunded);
+    assert!(!contract.state.awaiting.contains(&alice).blocking_wait().expect("Failed to read waiting games"));
+}
+
+#[test]
+fn test_forfeit_gives_the_stake_to_the_house() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    let alice = bet::parse_owner(ALICE);
+    restart_alices_game(&mut contract);
+    let calls = testing::record_token_calls(&mut contract.runtime);
+    contract.execute_operation(Operation::Forfeit { owner: alice }).blocking_wait();
+
+    assert!(matches!(
+        calls.lock().unwrap()[..],
+        [pulse_token::Operation::Settle { reference: 0, payout }] if payout == Amount::ZERO
+    ));
+    let game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    assert_eq!(game.state, GameState::Forfeited);
+    let round = contract.state.rounds.get(0).blocking_wait().expect("Failed to read rounds").expect("The forfeit is recorded");
+    assert_eq!(round.payout, 0);
+}
+
+#[test]
+#[should_panic(expected = "Finish or forfeit the current game first")]
+fn test_new_game_waits_for_the_current_one() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    restart_alices_game(&mut contract);
+    let create = Operation::CreateGame { stake_amount: 10, owner: bet::parse_owner(ALICE), client_seed: "seed".into() };
+    contract.execute_operation(create).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "Operation must be signed by the owner")]
+fn test_forfeiting_another_owners_game_is_rejected() {
+    let mut contract = contract_signed_by(Some(BOB));
+    contract.execute_operation(Operation::Forfeit { owner: bet::parse_owner(ALICE) }).blocking_wait();
+}
+
+#[test]
+fn test_board_replays_from_the_link_of_its_last_draw() {
+    let links = testing::house_links();
+    let mut images = vec![None; CARDS as usize];
+    let mut draws = Vec::new();
+    for (nonce, card) in [0u8, 7].into_iter().enumerate() {
+        let (_, mut rng) = fairness::replay(&links[nonce], "seed", nonce as u64).expect("test seeds are hex-encoded");
+        images[card as usize] = Some(memory_game::draw_image(&mut rng, &images));
+        let server_seed_hash = fairness::hash_hex_seed(&links[nonce]).expect("test seeds are hex-encoded");
+        draws.push(CardDraw { card, server_seed_hash, nonce: nonce as u64 });
+    }
+    let params = MemoryParams { stake_amount: 10, turn_count: 0, draws };
+    let outcome = memory_game::replay_board(&links[1], "seed", &params, 100).expect("board replays");
+    assert_eq!(outcome.image_ids, images);
+}
+
+/// Creates a game for Alice, finishes it in `turn_count` turns and claims it. Returns the payout
+/// its hold settled with.
+fn claimed_after(turn_count: u8) -> (MemoryGameContract, Amount) {
+    let alice = bet::parse_owner(ALICE);
+    let mut contract = testing::contract_signed_by::<MemoryGameContract>(Some(ALICE));
+    let calls = testing::record_token_calls(&mut contract.runtime);
+    contract.execute_operation(MemoryGameContract::bet(alice)).blocking_wait();
+    let mut game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    game.matched_cards = (0..CARDS).collect();
+    game.turn_count = turn_count;
+    game.state = GameState::Finished;
+    contract.save_game(alice, game);
+    contract.execute_operation(Operation::ClaimPayout).blocking_wait();
+
+    let payout = testing::settled_payout(&calls, alice, 10, 0);
+    (contract, payout)
+}
+
+#[test]
+fn test_board_cleared_in_few_turns_pays_out() {
+    let (contract, payout) = claimed_after(6);
+    let alice = bet::parse_owner(ALICE);
+
+    let expected = memory_game::payout_for_turns(10, 6, 100);
+    assert!(expected > 10);
+    assert_eq!(payout, bet::tokens(expected));
+    let game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    assert_eq!(game.state, GameState::Claimed);
+    let round = contract.state.rounds.get(0).blocking_wait().expect("Failed to read rounds").expect("The claim is recorded");
+    assert_eq!(round.payout, expected);
+}
+
+#[test]
+fn test_board_cleared_in_too_many_turns_pays_nothing() {
+    let (contract, payout) = claimed_after(13);
+    let alice = bet::parse_owner(ALICE);
+
+    assert_eq!(payout, Amount::ZERO);
+    let game = contract.game(&alice).blocking_wait().expect("Alice has a game");
+    assert_eq!(game.state, GameState::Claimed);
+    let round = contract.state.rounds.get(0).blocking_wait().expect("Failed to read rounds").expect("The claim is recorded");
+    assert_eq!(round.payout, 0);
+}
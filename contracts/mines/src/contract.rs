@@ -1,23 +1,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
 
 use linera_sdk::{
     abi::{WithContractAbi, ContractAbi},
     views::{RootView, View},
+    linera_base_types::AccountOwner,
     Contract, ContractRuntime,
-    linera_base_types::{AccountOwner, Amount, ApplicationId},
 };
-use linera_base::crypto::{CryptoHash, BcsHashable};
-use mines::{Operation, MinesAbi, GameResult, InstantiationArgument};
+use mines::{Operation, MinesAbi, InstantiationArgument, MinesOutcome, MinesParams};
+use pulse_common::{bet, pending, GameConfig, GameKind, GameSettled, PendingBet, SettledBet};
 use state::{MinesState, Game};
-use serde::{Serialize, Deserialize};
-use std::str::FromStr;
-
-#[derive(Serialize, Deserialize)]
-struct SeedWrapper(Vec<u8>);
-
-impl<'de> BcsHashable<'de> for SeedWrapper {}
 
 pub struct MinesContract {
     state: MinesState,
@@ -32,9 +28,9 @@ impl WithContractAbi for MinesContract {
 
 impl Contract for MinesContract {
     type Message = ();
-    type Parameters = ();
+    type Parameters = GameConfig;
     type InstantiationArgument = InstantiationArgument;
-    type EventValue = ();
+    type EventValue = GameSettled<MinesOutcome>;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = MinesState::load(runtime.root_view_storage_context())
@@ -44,14 +40,29 @@ impl Contract for MinesContract {
     }
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
-        self.state.pulse_token_id.set(Some(argument.pulse_token_id));
+        self.state.setup.instantiate(&mut self.runtime, argument.pulse_token_id, argument.leaderboard_id);
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
         match operation {
-            Operation::Bet { amount, mines_count, owner } => self.execute_bet(amount, mines_count, owner).await,
-            Operation::Reveal { tile_id } => self.execute_reveal(tile_id).await,
-            Operation::CashOut => self.execute_cashout().await,
+            Operation::Bet { amount, mines_count, tiles, owner, client_seed } => {
+                self.execute_bet(amount, mines_count, tiles, owner, client_seed).await
+            }
+            Operation::Refund { owner } => {
+                self.state.setup.refund_bet(&mut self.runtime, &mut self.state.pending, owner).await;
+            }
+            Operation::UpdateConfig { config } => self.state.setup.update_config(&mut self.runtime, config),
+            Operation::CommitHouseSeeds { hash } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                self.state.seeds.commit_house_chain(hash);
+            }
+            Operation::PublishHouseSeed { seed } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                let revealed = self.state.seeds.publish_house_seed(&mut self.runtime, seed);
+                for game in pending::take_due(&mut self.state.pending, &revealed.server_seed_hash).await {
+                    self.settle(game).await;
+                }
+            }
         }
     }
 
@@ -62,148 +73,57 @@ impl Contract for MinesContract {
     }
 }
 
-const PULSE_TOKEN_APP_ID: &str = "8e7498a4564d33c50bc4a3053eba7b51a4f5e7085111dbcc7cd3efe6072a7961";
-
 impl MinesContract {
-    async fn execute_bet(&mut self, amount: u64, mines_count: u8, owner: String) {
-        assert!(mines_count >= 1 && mines_count <= 24, "Invalid mines count");
-        assert!(amount > 0, "Bet amount must be positive");
-
-        let active_game = self.state.active_game.get();
-        if let Some(game) = active_game {
-            assert!(game.result != GameResult::Active, "Game already active");
-        }
-
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID).expect("Invalid hardcoded PulseToken App ID"),
-        };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-        
-        let account_owner = AccountOwner::from_str(&owner).expect("Invalid owner address");
-        
-        // Debit tokens (Cross-App Call)
-        let debit_op = pulse_token::Operation::GameDebit {
-            owner: account_owner,
-            amount: Amount::from_tokens(amount.into()),
-        };
-        
-        self.runtime
-            .call_application(true, token_app_id, &debit_op);
-
-        let mine_indices = self.generate_mines(mines_count);
-
-let new_game = Game {
-            owner: owner.clone(),
-            mines_count,
-            bet_amount: amount,
-            revealed_tiles: Vec::new(),
-            mine_indices,
-            result: GameResult::Active,
-            current_multiplier: 100, 
-        };
-
-        self.state.active_game.set(Some(new_game));
+    async fn execute_bet(&mut self, amount: u64, mines_count: u8, tiles: Vec<u8>, owner: AccountOwner, client_seed: String) {
+        assert!(!tiles.is_empty(), "Pick at least one tile");
+        let params = MinesParams { amount, mines_count, revealed_tiles: tiles };
+        params.check().unwrap_or_else(|error| panic!("{error}"));
+        let config = self.state.setup.config();
+        config.check_stake(amount);
+        let best_multiplier = mines::multiplier_percent(mines_count, params.revealed_tiles.len() as u64, config.house_edge_bps);
+        config.check_payout(bet::apply_multiplier(amount, best_multiplier));
+
+        bet::check_signer(&mut self.runtime, owner);
+        let waiting = self.state.pending.contains_key(&owner).await.expect("Failed to read pending games");
+        assert!(!waiting, "Game already active");
+
+        // Nobody knows the layout until the house publishes the link it is drawn from, and by
+        // then the tiles are picked and the stake is held
+        let game = self.state.setup.lock_bet(&mut self.runtime, &mut self.state.seeds, owner, amount, params, client_seed);
+        self.state.pending.insert(&owner, game).expect("Failed to save game");
     }
 
-    async fn execute_reveal(&mut self, tile_id: u8) {
-        assert!(tile_id < 25, "Invalid tile ID");
-
-        let mut game = self.state.active_game.get().clone().expect("No active game");
-        assert!(matches!(game.result, GameResult::Active), "Game is over");
-        assert!(!game.revealed_tiles.contains(&tile_id), "Tile already revealed");
-
-        if game.mine_indices.contains(&tile_id) {
-            game.result = GameResult::Lost;
-            game.revealed_tiles.push(tile_id);
-            self.state.active_game.set(Some(game));
-        } else {
-            game.revealed_tiles.push(tile_id);
-            
-            let hits = game.revealed_tiles.len() as u64;
-            let (base, step) = match game.mines_count {
-                3 => (110, 20),   
-                5 => (140, 30),   
-                7 => (140, 50),   
-                _ => (100, 10),   
-            };
-            
-            let new_mult = if hits == 0 { 
-                100  
-            } else {
-                base + ((hits - 1) * step)
-            };
-            
-            game.current_multiplier = new_mult;
-
-            let safe_tiles = 25 - game.mines_count;
-            if game.revealed_tiles.len() as u8 == safe_tiles {
-                game.result = GameResult::Won;
-                
-                let payout = (game.bet_amount * game.current_multiplier) / 100;
-                
-                let token_app_id = match self.state.pulse_token_id.get() {
-                    Some(id) => *id,
-                    None => ApplicationId::from_str(PULSE_TOKEN_APP_ID).expect("Invalid hardcoded PulseToken App ID"),
-                };
-                let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-                
-                let account_owner = AccountOwner::from_str(&game.owner).expect("Invalid owner address");
-
-                let credit_op = pulse_token::Operation::GameCredit {
-                    owner: account_owner,
-                    amount: Amount::from_tokens(payout.into()),
-                };
-
-                self.runtime
-                    .call_application(true, token_app_id, &credit_op);
-            }
-            
-            self.state.active_game.set(Some(game));
-        }
-    }
-
-    async fn execute_cashout(&mut self) {
-        let mut game = self.state.active_game.get().clone().expect("No active game");
-        assert!(matches!(game.result, GameResult::Active), "Game is over");
-
-        let payout = (game.bet_amount * game.current_multiplier) / 100;
-        
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID).expect("Invalid hardcoded PulseToken App ID"),
+    /// Draws the layout of a game whose link is out, plays its tiles and pays it out
+    async fn settle(&mut self, game: PendingBet<MinesParams>) {
+        let mut rng = self.state.seeds.rng_for(&game.seeds).await.expect("the game's link is published");
+        let outcome = mines::replay_mines(&mut rng, &game.params, game.house_edge_bps);
+        self.state.setup.token().settle(&mut self.runtime, game.hold_reference, bet::tokens(outcome.payout));
+
+        let last_game = Game {
+            owner: game.owner,
+            mines_count: game.params.mines_count,
+            bet_amount: game.stake,
+            revealed_tiles: game.params.revealed_tiles.clone(),
+            mine_indices: outcome.mine_indices.clone(),
+            result: outcome.result,
+            current_multiplier: outcome.multiplier,
+            house_edge_bps: game.house_edge_bps,
+            seeds: game.seeds.clone(),
         };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-        
-        let account_owner = AccountOwner::from_str(&game.owner).expect("Invalid owner address");
-
-        let credit_op = pulse_token::Operation::GameCredit {
-            owner: account_owner,
-            amount: Amount::from_tokens(payout.into()),
+        let settled = SettledBet {
+            game: GameKind::Mines,
+            owner: game.owner,
+            stake: game.stake,
+            params: game.params,
+            payout: outcome.payout,
+            outcome,
+            house_edge_bps: game.house_edge_bps,
+            seeds: game.seeds,
         };
-
-        self.runtime
-            .call_application(true, token_app_id, &credit_op);
-
-        game.result = GameResult::CashedOut;
-        self.state.active_game.set(Some(game));
-    }
-
-    fn generate_mines(&mut self, count: u8) -> Vec<u8> {
-        let mut mines = Vec::new();
-        let mut nonce = 0;
-        while mines.len() < count as usize {
-            let data = (self.runtime.chain_id(), self.runtime.system_time(), nonce);
-            let bytes = bcs::to_bytes(&data).expect("Serialization failed");
-            let seed = SeedWrapper(bytes);
-            let hash = CryptoHash::new(&seed);
-            let byte = hash.as_bytes()[0];
-            let tile = byte % 25;
-            if !mines.contains(&tile) {
-                mines.push(tile);
-            }
-            nonce += 1;
-        }
-        mines
+        self.state
+            .setup
+            .record_round(&mut self.runtime, &mut self.state.rounds, &mut self.state.round_index, settled)
+            .await;
+        self.state.active_games.insert(&last_game.owner, last_game).expect("Failed to save game");
     }
 }
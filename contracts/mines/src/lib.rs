@@ -1,14 +1,18 @@
-use async_graphql::{Enum, Request, Response};
+use async_graphql::{Enum, InputObject, Request, Response, SimpleObject};
 use linera_sdk::{
     abi::{ContractAbi, ServiceAbi},
     graphql::GraphQLMutationRoot,
-    linera_base_types::ApplicationId,
+    linera_base_types::{AccountOwner, ApplicationId},
 };
+use pulse_common::{bet, config, history, GameConfig, GameRng};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InstantiationArgument {
     pub pulse_token_id: ApplicationId,
+    /// Leaderboard the game reports its settled bets to, if any
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId>,
 }
 
 pub struct MinesAbi;
@@ -25,15 +29,25 @@ impl ServiceAbi for MinesAbi {
 
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
+    /// Holds the stake for a game that opens `tiles` in order and cashes out after the last
+    /// one. The layout is drawn from the house's next link, so the game is played out when the
+    /// house publishes it.
     Bet {
         amount: u64,
         mines_count: u8,
-        owner: String,
+        tiles: Vec<u8>,
+        owner: AccountOwner,
+        client_seed: String,
     },
-    Reveal {
-        tile_id: u8,
-    },
-    CashOut,
+    /// Returns the stake of a game the house has left waiting too long
+    Refund { owner: AccountOwner },
+    /// Admin only
+    UpdateConfig { config: GameConfig },
+    /// Admin only: starts a house seed chain from the hash of its first link
+    CommitHouseSeeds { hash: String },
+    /// Admin only: publishes the next link of the house seed chain and plays out the games
+    /// waiting for it
+    PublishHouseSeed { seed: String },
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Enum, Copy, PartialEq, Eq)]
@@ -41,5 +55,96 @@ pub enum GameResult {
     Won,
     Lost,
     CashedOut,
+    /// Waiting for the house link the layout is drawn from
     Active,
 }
+
+pub const TILES: u8 = 25;
+
+/// The bet a layout is verified against. `revealed_tiles` are the tiles the player opened, in
+/// order; the game is scored as if they cashed out after the last one.
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "MinesParamsInput")]
+pub struct MinesParams {
+    pub amount: u64,
+    #[serde(alias = "minesCount")]
+    pub mines_count: u8,
+    #[graphql(default)]
+    #[serde(alias = "revealedTiles")]
+    pub revealed_tiles: Vec<u8>,
+}
+
+impl MinesParams {
+    pub fn check(&self) -> Result<(), &'static str> {
+        if !(1..=24).contains(&self.mines_count) {
+            return Err("Invalid mines count");
+        }
+        if self.revealed_tiles.len() > usize::from(TILES - self.mines_count) {
+            return Err("Cannot open more tiles than there are safe ones");
+        }
+        for (index, tile) in self.revealed_tiles.iter().enumerate() {
+            if *tile >= TILES {
+                return Err("Invalid tile ID");
+            }
+            if self.revealed_tiles[..index].contains(tile) {
+                return Err("Tile already revealed");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject)]
+pub struct MinesOutcome {
+    pub mine_indices: Vec<u8>,
+    pub result: GameResult,
+    pub multiplier: u64,
+    pub payout: u64,
+}
+
+/// Places the mines of a game; shared by the contract, the service and pulsebet-verify.
+pub fn mine_layout(rng: &mut GameRng, mines_count: u8) -> Vec<u8> {
+    let mut tiles: Vec<u8> = (0..TILES).collect();
+    rng.partial_shuffle(&mut tiles, mines_count as usize);
+    tiles.truncate(mines_count as usize);
+    tiles
+}
+
+/// Multiplier in percent after `hits` safe tiles. Cashing out before any reveal returns the stake.
+pub fn multiplier_percent(mines_count: u8, hits: u64, house_edge_bps: u16) -> u64 {
+    let (base, step) = match mines_count {
+        3 => (110, 20),
+        5 => (140, 30),
+        7 => (140, 50),
+        _ => (100, 10),
+    };
+    if hits == 0 {
+        100
+    } else {
+        config::apply_house_edge(base + ((hits - 1) * step), house_edge_bps)
+    }
+}
+
+/// Replays a whole game: the layout, then the reveals until a mine or the last safe tile.
+pub fn replay_mines(rng: &mut GameRng, params: &MinesParams, house_edge_bps: u16) -> MinesOutcome {
+    let mine_indices = mine_layout(rng, params.mines_count);
+    let mut result = GameResult::CashedOut;
+    let mut hits = 0;
+    for tile in &params.revealed_tiles {
+        if mine_indices.contains(tile) {
+            result = GameResult::Lost;
+            break;
+        }
+        hits += 1;
+        if hits == u64::from(TILES - params.mines_count) {
+            result = GameResult::Won;
+            break;
+        }
+    }
+    let multiplier = multiplier_percent(params.mines_count, hits, house_edge_bps);
+    let payout = if result == GameResult::Lost { 0 } else { bet::apply_multiplier(params.amount, multiplier) };
+    MinesOutcome { mine_indices, result, multiplier, payout }
+}
+
+/// A settled game, as kept in its owner's history
+pub type Round = history::Round<MinesParams, MinesOutcome>;
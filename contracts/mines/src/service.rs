@@ -5,15 +5,17 @@ mod state;
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema, SimpleObject};
 use linera_sdk::{
     abi::WithServiceAbi,
+    linera_base_types::AccountOwner,
     views::View,
     Service, ServiceRuntime,
 };
 use std::sync::Arc;
-use mines::{Operation, MinesAbi, GameResult};
-use state::MinesState;
+use mines::{Operation, MinesAbi, MinesOutcome, MinesParams, GameResult, Round};
+use pulse_common::{fairness, history, BetSeeds, GameConfig, HouseChain, PendingBet, RevealedSeed, SeedCommitment, Verification};
+use state::{Game, MinesState};
 
 pub struct MinesService {
-    state: MinesState,
+    state: Arc<MinesState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -24,41 +26,21 @@ impl WithServiceAbi for MinesService {
 }
 
 impl Service for MinesService {
-    type Parameters = ();
+    type Parameters = GameConfig;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = MinesState::load(runtime.root_view_storage_context())
             .await
             .expect("Failed to load state");
         MinesService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, request: Request) -> Response {
-        let game_opt = self.state.active_game.get();
-        
-        let public_game = game_opt.as_ref().map(|game| {
-            let mine_indices = if matches!(game.result, GameResult::Active) {
-                Vec::new() 
-            } else {
-                game.mine_indices.clone()
-            };
-
-            PublicGame {
-                owner: game.owner.clone(),
-                mines_count: game.mines_count,
-                bet_amount: game.bet_amount,
-                revealed_tiles: game.revealed_tiles.clone(),
-                mine_indices,
-                result: game.result,
-                current_multiplier: game.current_multiplier,
-            }
-        });
-
         let schema = Schema::build(
-            QueryRoot { public_game },
+            QueryRoot { state: self.state.clone() },
             MutationRoot { runtime: self.runtime.clone() },
             EmptySubscription,
         )
@@ -68,13 +50,62 @@ impl Service for MinesService {
 }
 
 struct QueryRoot {
-    public_game: Option<PublicGame>,
+    state: Arc<MinesState>,
 }
 
 #[Object]
 impl QueryRoot {
-    async fn active_game(&self) -> &Option<PublicGame> {
-        &self.public_game
+    /// The owner's game waiting for the house, or their last one once it is over
+    async fn active_game(&self, owner: AccountOwner) -> Option<PublicGame> {
+        if let Some(game) = self.state.pending.get(&owner).await.expect("Failed to read pending games") {
+            return Some(PublicGame::from(game));
+        }
+        let game = self.state.active_games.get(&owner).await.expect("Failed to read games");
+        game.map(PublicGame::from)
+    }
+
+    /// Hash of the house link the next layout will be drawn from
+    async fn seed_commitment(&self) -> Option<SeedCommitment> {
+        self.state.seeds.commitment()
+    }
+
+    async fn revealed_seed(&self, server_seed_hash: String) -> Option<RevealedSeed> {
+        self.state.seeds.revealed(&server_seed_hash).await
+    }
+
+    /// Latest published link of the house seed chain
+    async fn house_chain(&self) -> Option<HouseChain> {
+        self.state.seeds.house_chain()
+    }
+
+    /// The owner's settled rounds, newest first. Pass the last `roundId` of a page as `after`
+    /// to get the next one.
+    async fn history(&self, owner: AccountOwner, first: Option<u32>, after: Option<u64>) -> Vec<Round> {
+        self.state.round_index.rounds(&self.state.rounds, &owner, first, after).await
+    }
+
+    async fn round(&self, round_id: u64) -> Option<Round> {
+        history::round(&self.state.rounds, round_id).await
+    }
+
+    async fn config(&self) -> Option<GameConfig> {
+        self.state.setup.config.get().clone()
+    }
+
+    /// Recomputes a mine layout, and the game played on it, from its revealed server seed
+    async fn verify(
+        &self,
+        server_seed: String,
+        client_seed: String,
+        nonce: u64,
+        params: MinesParams,
+        /// Edge of the config the bet was placed under; defaults to the current one
+        house_edge_bps: Option<u16>,
+    ) -> async_graphql::Result<Verification<MinesOutcome>> {
+        params.check()?;
+        let (server_seed_hash, mut rng) = fairness::replay(&server_seed, &client_seed, nonce)?;
+        let outcome = mines::replay_mines(&mut rng, &params, self.state.setup.house_edge_bps(house_edge_bps));
+        Ok(Verification { server_seed_hash, outcome })
     }
 }
 
@@ -84,32 +115,75 @@ struct MutationRoot {
 
 #[Object]
 impl MutationRoot {
-    async fn bet(&self, amount: u64, mines_count: u8, owner: String) -> Vec<u8> {
-        let op = Operation::Bet { amount, mines_count, owner };
+    async fn bet(&self, amount: u64, mines_count: u8, tiles: Vec<u8>, owner: AccountOwner, client_seed: String) -> Vec<u8> {
+        let op = Operation::Bet { amount, mines_count, tiles, owner, client_seed };
         self.runtime.schedule_operation(&op);
         Vec::new()
     }
 
-    async fn reveal(&self, tile_id: u8) -> Vec<u8> {
-        let op = Operation::Reveal { tile_id };
-        self.runtime.schedule_operation(&op);
+    async fn refund(&self, owner: AccountOwner) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::Refund { owner });
         Vec::new()
     }
 
-    async fn cash_out(&self) -> Vec<u8> {
-        let op = Operation::CashOut;
-        self.runtime.schedule_operation(&op);
+    async fn update_config(&self, config: GameConfig) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::UpdateConfig { config });
+        Vec::new()
+    }
+
+    async fn commit_house_seeds(&self, hash: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::CommitHouseSeeds { hash });
+        Vec::new()
+    }
+
+    async fn publish_house_seed(&self, seed: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::PublishHouseSeed { seed });
         Vec::new()
     }
 }
 
 #[derive(SimpleObject)]
 pub struct PublicGame {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub mines_count: u8,
     pub bet_amount: u64,
     pub revealed_tiles: Vec<u8>,
     pub mine_indices: Vec<u8>, 
     pub result: GameResult,
     pub current_multiplier: u64,  // Stored as percentage (100 = 1.0x)
+    pub house_edge_bps: u16,
+    pub seeds: BetSeeds,
+}
+
+impl From<PendingBet<MinesParams>> for PublicGame {
+    fn from(game: PendingBet<MinesParams>) -> Self {
+        // The layout is only drawn once the house publishes the game's link
+        PublicGame {
+            owner: game.owner,
+            mines_count: game.params.mines_count,
+            bet_amount: game.stake,
+            revealed_tiles: game.params.revealed_tiles,
+            mine_indices: Vec::new(),
+            result: GameResult::Active,
+            current_multiplier: 100,
+            house_edge_bps: game.house_edge_bps,
+            seeds: game.seeds,
+        }
+    }
+}
+
+impl From<Game> for PublicGame {
+    fn from(game: Game) -> Self {
+        PublicGame {
+            owner: game.owner,
+            mines_count: game.mines_count,
+            bet_amount: game.bet_amount,
+            revealed_tiles: game.revealed_tiles,
+            mine_indices: game.mine_indices,
+            result: game.result,
+            current_multiplier: game.current_multiplier,
+            house_edge_bps: game.house_edge_bps,
+            seeds: game.seeds,
+        }
+    }
 }
@@ -1,24 +1,34 @@
 use linera_sdk::{
-    linera_base_types::ApplicationId,
-    views::{RegisterView, RootView, ViewStorageContext},
+    linera_base_types::AccountOwner,
+    views::{LogView, MapView, RootView, ViewStorageContext},
 };
-use mines::GameResult;
+use mines::{GameResult, MinesParams, Round};
+use pulse_common::{BetSeeds, GameSetup, PendingBet, RoundIndex, SeedBook};
 use serde::{Deserialize, Serialize};
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct MinesState {
-    pub active_game: RegisterView<Option<Game>>,
-    pub pulse_token_id: RegisterView<Option<ApplicationId>>,
+    /// Each owner's last settled game
+    pub active_games: MapView<AccountOwner, Game>,
+    /// Games waiting for the house link their layout is drawn from
+    pub pending: MapView<AccountOwner, PendingBet<MinesParams>>,
+    pub setup: GameSetup,
+    pub seeds: SeedBook,
+    /// Every settled round; a round's id is its index
+    pub rounds: LogView<Round>,
+    pub round_index: RoundIndex,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Game {
-    pub owner: String, 
+    pub owner: AccountOwner,
     pub mines_count: u8,
     pub bet_amount: u64,
     pub revealed_tiles: Vec<u8>,
     pub mine_indices: Vec<u8>,
     pub result: GameResult,
     pub current_multiplier: u64, 
+    pub house_edge_bps: u16, // Edge when the bet was placed; later config updates do not apply
+    pub seeds: BetSeeds,
 }
@@ -0,0 +1,165 @@
+use super::*;
+use linera_sdk::{
+    linera_base_types::{Amount, Timestamp},
+    util::BlockingWait,
+};
+use mines::GameResult;
+use pulse_common::{
+    fairness,
+    testing::{self, GameUnderTest, ALICE, BOB},
+    BetSeeds, GameSetup,
+};
+
+impl GameUnderTest for MinesContract {
+    fn deploy(runtime: ContractRuntime<Self>) -> Self {
+        let state = MinesState::load(runtime.root_view_storage_context())
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        MinesContract { state, runtime }
+    }
+
+    fn parts(&mut self) -> (&mut GameSetup, &mut ContractRuntime<Self>) {
+        (&mut self.state.setup, &mut self.runtime)
+    }
+
+    fn bet(owner: AccountOwner) -> Operation {
+        Operation::Bet { amount: 10, mines_count: 3, tiles: vec![5], owner, client_seed: "seed".into() }
+    }
+
+    fn refund(owner: AccountOwner) -> Operation {
+        Operation::Refund { owner }
+    }
+
+    fn update_config(config: GameConfig) -> Operation {
+        Operation::UpdateConfig { config }
+    }
+
+    fn commit_house_seeds(hash: String) -> Operation {
+        Operation::CommitHouseSeeds { hash }
+    }
+
+    fn publish_house_seed(seed: String) -> Operation {
+        Operation::PublishHouseSeed { seed }
+    }
+}
+
+#[test]
+fn test_shared_game_rules() {
+    testing::check_shared_rules::<MinesContract>();
+}
+
+/// A contract whose blocks are signed by `signer`, with `ALICE`'s game waiting for the house.
+fn contract_signed_by(signer: Option<&str>) -> MinesContract {
+    let alice = bet::parse_owner(ALICE);
+    let mut contract = testing::contract_signed_by::<MinesContract>(signer);
+    let game = PendingBet {
+        owner: alice,
+        stake: 10,
+        params: MinesParams { amount: 10, mines_count: 3, revealed_tiles: vec![5] },
+        house_edge_bps: 100,
+        hold_reference: 0,
+        seeds: BetSeeds::default(),
+        locked_at: Timestamp::from(0),
+    };
+    contract.state.pending.insert(&alice, game).expect("Failed to save game");
+    contract
+}
+
+fn bet(owner: &str, tiles: Vec<u8>) -> Operation {
+    Operation::Bet { amount: 10, mines_count: 3, tiles, owner: bet::parse_owner(owner), client_seed: "seed".into() }
+}
+
+#[test]
+#[should_panic(expected = "Game already active")]
+fn test_bet_while_a_game_waits_for_the_house_is_rejected() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    contract.execute_operation(bet(ALICE, vec![5])).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "Pick at least one tile")]
+fn test_bet_without_tiles_is_rejected() {
+    let mut contract = contract_signed_by(Some(BOB));
+    contract.execute_operation(bet(BOB, Vec::new())).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "The house has not committed to a seed chain")]
+fn test_bets_wait_for_the_house_to_commit() {
+    let mut contract = contract_signed_by(Some(BOB));
+    contract.execute_operation(bet(BOB, vec![5])).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "The house has until")]
+fn test_refund_waits_for_the_house() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    contract.execute_operation(Operation::Refund { owner: bet::parse_owner(ALICE) }).blocking_wait();
+}
+
+#[test]
+fn test_refund_releases_a_game_the_house_left_waiting() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    let alice = bet::parse_owner(ALICE);
+    let calls = testing::record_token_calls(&mut contract.runtime);
+    testing::wait_out_refund_delay(&mut contract.runtime);
+    contract.execute_operation(Operation::Refund { owner: alice }).blocking_wait();
+
+    assert!(matches!(calls.lock().unwrap()[..], [pulse_token::Operation::Release { reference: 0 }]));
+    assert!(!contract.state.pending.contains_key(&alice).blocking_wait().expect("Failed to read pending games"));
+}
+
+/// Settles a game of Alice's opening tiles 5 and 6 among 3 mines, with a layout that spares both
+/// tiles or not as asked. Returns its outcome and the payout of its hold.
+fn settled_game(spared: bool) -> (MinesContract, MinesOutcome, Amount) {
+    let params = MinesParams { amount: 10, mines_count: 3, revealed_tiles: vec![5, 6] };
+    let link = &testing::house_links()[0];
+    let client_seed = testing::client_seed_where(link, 0, |rng| {
+        (mines::replay_mines(rng, &params, 100).result != GameResult::Lost) == spared
+    });
+    let (_, mut rng) = fairness::replay(link, &client_seed, 0).expect("test seeds are hex-encoded");
+    let outcome = mines::replay_mines(&mut rng, &params, 100);
+
+    let owner = bet::parse_owner(ALICE);
+    let game = Operation::Bet { amount: 10, mines_count: 3, tiles: params.revealed_tiles, owner, client_seed };
+    let (contract, payout) = testing::settle_bet::<MinesContract>(game, 10);
+    (contract, outcome, payout)
+}
+
+/// Alice's settled game, checking it no longer waits for the house.
+fn saved_game(contract: &MinesContract) -> Game {
+    let alice = bet::parse_owner(ALICE);
+    let waiting = contract.state.pending.contains_key(&alice).blocking_wait();
+    assert!(!waiting.expect("Failed to read pending games"));
+    let game = contract.state.active_games.get(&alice).blocking_wait();
+    game.expect("Failed to read games").expect("the game is saved")
+}
+
+#[test]
+fn test_game_that_misses_the_mines_cashes_out() {
+    let (contract, outcome, payout) = settled_game(true);
+
+    assert_eq!(outcome.result, GameResult::CashedOut);
+    assert!(outcome.payout > 10);
+    assert_eq!(payout, bet::tokens(outcome.payout));
+    let game = saved_game(&contract);
+    assert_eq!((game.result, game.mine_indices), (GameResult::CashedOut, outcome.mine_indices));
+}
+
+#[test]
+fn test_game_that_hits_a_mine_pays_nothing() {
+    let (contract, outcome, payout) = settled_game(false);
+
+    assert_eq!(payout, Amount::ZERO);
+    let game = saved_game(&contract);
+    assert_eq!((game.result, game.mine_indices), (GameResult::Lost, outcome.mine_indices));
+}
+
+#[test]
+fn test_params_reject_bad_revealed_tiles() {
+    let params = |revealed_tiles: Vec<u8>| MinesParams { amount: 10, mines_count: 3, revealed_tiles };
+    assert_eq!(params(vec![4, 24, 0]).check(), Ok(()));
+    assert_eq!(params(vec![4, 25]).check(), Err("Invalid tile ID"));
+    assert_eq!(params(vec![4, 7, 4]).check(), Err("Tile already revealed"));
+    assert_eq!(params((0..23).collect()).check(), Err("Cannot open more tiles than there are safe ones"));
+}
@@ -1,14 +1,18 @@
-use async_graphql::{Enum, Request, Response};
+use async_graphql::{Enum, InputObject, Request, Response, SimpleObject};
 use linera_sdk::{
     abi::{ContractAbi, ServiceAbi},
     graphql::GraphQLMutationRoot,
-    linera_base_types::ApplicationId,
+    linera_base_types::{AccountOwner, ApplicationId},
 };
+use pulse_common::{bet, config, history, GameConfig, GameRng};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InstantiationArgument {
     pub pulse_token_id: ApplicationId,
+    /// Leaderboard the game reports its settled bets to, if any
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId>,
 }
 
 pub struct PlinkoAbi;
@@ -25,14 +29,24 @@ impl ServiceAbi for PlinkoAbi {
 
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
-    StartGame { amount: u64, owner: String },
-    AdvanceBatch { target_row: u8 },
+    /// Holds the stake; the ball is dropped when the house publishes its next link
+    StartGame { amount: u64, owner: AccountOwner, client_seed: String },
+    /// Returns the stake of a drop the house has left waiting too long
+    Refund { owner: AccountOwner },
+    /// Admin only
+    UpdateConfig { config: GameConfig },
+    /// Admin only: starts a house seed chain from the hash of its first link
+    CommitHouseSeeds { hash: String },
+    /// Admin only: publishes the next link of the house seed chain and drops the balls waiting
+    /// for it
+    PublishHouseSeed { seed: String },
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Enum, Copy, PartialEq, Eq)]
 pub enum GameResult {
     Won,  // Payout happened
     Lost, // Should strictly use Won/Active for Plinko since you always win *something* usually, but we keep Lost for coherence
+    /// Waiting for the house link the path is drawn from
     Active,
 }
 
@@ -55,3 +69,71 @@ pub const MULTIPLIERS: [u64; 9] = [
     25,   // Slot 7
     1000, // Slot 8: JACKPOT
 ];
+
+pub const ROWS: u8 = 8;
+
+/// The bet a drop is verified against
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "PlinkoParamsInput")]
+pub struct PlinkoParams {
+    pub amount: u64,
+}
+
+impl PlinkoParams {
+    pub fn check(&self) -> Result<(), &'static str> {
+        if self.amount > 0 {
+            Ok(())
+        } else {
+            Err("Bet amount must be positive")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject)]
+pub struct PlinkoOutcome {
+    pub path: Vec<Direction>,
+    pub slot: u8,
+    pub multiplier: u64,
+    pub payout: u64,
+}
+
+/// The ball's next bounce. A game draws one per row, in order, from the bet's stream.
+pub fn next_direction(rng: &mut GameRng) -> Direction {
+    // 50/50 Chance
+    if rng.below(2) == 0 { Direction::Right } else { Direction::Left }
+}
+
+/// Slot the ball lands in from its final column.
+pub fn landing_slot(col: i8) -> u8 {
+    // Map Final Column (Col 8, ranges from -8 to +8 in steps of 2)
+    // Range: -8, -6, -4, -2, 0, 2, 4, 6, 8. (9 positions)
+    // Map: (-8 -> 0), (-6 -> 1) ... (8 -> 8).
+    // Formula: (col + 8) / 2
+    let slot_index = (col + 8) / 2;
+    assert!(slot_index >= 0 && slot_index <= 8, "Logic error in slot calculation");
+    slot_index as u8
+}
+
+/// Multiplier of a slot after the house edge, in percent.
+pub fn slot_multiplier(slot: u8, house_edge_bps: u16) -> u64 {
+    config::apply_house_edge(MULTIPLIERS[slot as usize], house_edge_bps)
+}
+
+/// The jackpot multiplier after the house edge, in percent.
+pub fn max_multiplier(house_edge_bps: u16) -> u64 {
+    let jackpot = MULTIPLIERS.iter().copied().max().unwrap_or_default();
+    config::apply_house_edge(jackpot, house_edge_bps)
+}
+
+/// Drops the ball through every row; shared by the contract, the service and pulsebet-verify.
+pub fn drop_ball(rng: &mut GameRng, params: &PlinkoParams, house_edge_bps: u16) -> PlinkoOutcome {
+    let path: Vec<Direction> = (0..ROWS).map(|_| next_direction(rng)).collect();
+    let col: i8 = path.iter().map(|direction| if *direction == Direction::Right { 1 } else { -1 }).sum();
+    let slot = landing_slot(col);
+    let multiplier = slot_multiplier(slot, house_edge_bps);
+    let payout = bet::apply_multiplier(params.amount, multiplier);
+    PlinkoOutcome { path, slot, multiplier, payout }
+}
+
+/// A settled drop, as kept in its owner's history
+pub type Round = history::Round<PlinkoParams, PlinkoOutcome>;
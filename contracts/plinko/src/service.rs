@@ -5,15 +5,17 @@ mod state;
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema, SimpleObject};
 use linera_sdk::{
     abi::WithServiceAbi,
+    linera_base_types::AccountOwner,
     views::View,
     Service, ServiceRuntime,
 };
 use std::sync::Arc;
-use plinko::{Operation, PlinkoAbi, GameResult, Direction};
-use state::PlinkoState;
+use plinko::{Operation, PlinkoAbi, PlinkoOutcome, PlinkoParams, GameResult, Direction, Round};
+use pulse_common::{fairness, history, BetSeeds, GameConfig, HouseChain, PendingBet, RevealedSeed, SeedCommitment, Verification};
+use state::{Game, PlinkoState};
 
 pub struct PlinkoService {
-    state: PlinkoState,
+    state: Arc<PlinkoState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -24,35 +26,21 @@ impl WithServiceAbi for PlinkoService {
 }
 
 impl Service for PlinkoService {
-    type Parameters = ();
+    type Parameters = GameConfig;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = PlinkoState::load(runtime.root_view_storage_context())
             .await
             .expect("Failed to load state");
         PlinkoService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, request: Request) -> Response {
-        let game_opt = self.state.active_game.get();
-        
-        let public_game = game_opt.as_ref().map(|game| {
-            PublicGame {
-                owner: game.owner.clone(),
-                bet_amount: game.bet_amount,
-                current_row: game.current_row,
-                current_col: game.current_col,
-                path: game.path.clone(),
-                result: game.result,
-                final_multiplier: game.final_multiplier,
-            }
-        });
-
         let schema = Schema::build(
-            QueryRoot { public_game },
+            QueryRoot { state: self.state.clone() },
             MutationRoot { runtime: self.runtime.clone() },
             EmptySubscription,
         )
@@ -62,13 +50,62 @@ impl Service for PlinkoService {
 }
 
 struct QueryRoot {
-    public_game: Option<PublicGame>,
+    state: Arc<PlinkoState>,
 }
 
 #[Object]
 impl QueryRoot {
-    async fn active_game(&self) -> &Option<PublicGame> {
-        &self.public_game
+    /// The owner's drop waiting for the house, or their last one once it has landed
+    async fn active_game(&self, owner: AccountOwner) -> Option<PublicGame> {
+        if let Some(game) = self.state.pending.get(&owner).await.expect("Failed to read pending games") {
+            return Some(PublicGame::from(game));
+        }
+        let game = self.state.active_games.get(&owner).await.expect("Failed to read games");
+        game.map(PublicGame::from)
+    }
+
+    /// Hash of the house link the next drop's path will be drawn from
+    async fn seed_commitment(&self) -> Option<SeedCommitment> {
+        self.state.seeds.commitment()
+    }
+
+    async fn revealed_seed(&self, server_seed_hash: String) -> Option<RevealedSeed> {
+        self.state.seeds.revealed(&server_seed_hash).await
+    }
+
+    /// Latest published link of the house seed chain
+    async fn house_chain(&self) -> Option<HouseChain> {
+        self.state.seeds.house_chain()
+    }
+
+    /// The owner's settled rounds, newest first. Pass the last `roundId` of a page as `after`
+    /// to get the next one.
+    async fn history(&self, owner: AccountOwner, first: Option<u32>, after: Option<u64>) -> Vec<Round> {
+        self.state.round_index.rounds(&self.state.rounds, &owner, first, after).await
+    }
+
+    async fn round(&self, round_id: u64) -> Option<Round> {
+        history::round(&self.state.rounds, round_id).await
+    }
+
+    async fn config(&self) -> Option<GameConfig> {
+        self.state.setup.config.get().clone()
+    }
+
+    /// Recomputes a ball's path from its revealed server seed
+    async fn verify(
+        &self,
+        server_seed: String,
+        client_seed: String,
+        nonce: u64,
+        params: PlinkoParams,
+        /// Edge of the config the bet was placed under; defaults to the current one
+        house_edge_bps: Option<u16>,
+    ) -> async_graphql::Result<Verification<PlinkoOutcome>> {
+        params.check()?;
+        let (server_seed_hash, mut rng) = fairness::replay(&server_seed, &client_seed, nonce)?;
+        let outcome = plinko::drop_ball(&mut rng, &params, self.state.setup.house_edge_bps(house_edge_bps));
+        Ok(Verification { server_seed_hash, outcome })
     }
 }
 
@@ -78,26 +115,75 @@ struct MutationRoot {
 
 #[Object]
 impl MutationRoot {
-    async fn start_game(&self, amount: u64, owner: String) -> Vec<u8> {
-        let op = Operation::StartGame { amount, owner };
+    async fn start_game(&self, amount: u64, owner: AccountOwner, client_seed: String) -> Vec<u8> {
+        let op = Operation::StartGame { amount, owner, client_seed };
         self.runtime.schedule_operation(&op);
         Vec::new()
     }
 
-    async fn advance_batch(&self, target_row: u8) -> Vec<u8> {
-        let op = Operation::AdvanceBatch { target_row };
-        self.runtime.schedule_operation(&op);
+    async fn refund(&self, owner: AccountOwner) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::Refund { owner });
+        Vec::new()
+    }
+
+    async fn update_config(&self, config: GameConfig) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::UpdateConfig { config });
+        Vec::new()
+    }
+
+    async fn commit_house_seeds(&self, hash: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::CommitHouseSeeds { hash });
+        Vec::new()
+    }
+
+    async fn publish_house_seed(&self, seed: String) -> Vec<u8> {
+        self.runtime.schedule_operation(&Operation::PublishHouseSeed { seed });
         Vec::new()
     }
 }
 
 #[derive(SimpleObject)]
 pub struct PublicGame {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub bet_amount: u64,
     pub current_row: u8,
     pub current_col: i8,
     pub path: Vec<Direction>,
     pub result: GameResult,
     pub final_multiplier: u64,
+    pub house_edge_bps: u16,
+    pub seeds: BetSeeds,
+}
+
+impl From<PendingBet<PlinkoParams>> for PublicGame {
+    fn from(game: PendingBet<PlinkoParams>) -> Self {
+        // The path is only drawn once the house publishes the drop's link
+        PublicGame {
+            owner: game.owner,
+            bet_amount: game.stake,
+            current_row: 0,
+            current_col: 0,
+            path: Vec::new(),
+            result: GameResult::Active,
+            final_multiplier: 0,
+            house_edge_bps: game.house_edge_bps,
+            seeds: game.seeds,
+        }
+    }
+}
+
+impl From<Game> for PublicGame {
+    fn from(game: Game) -> Self {
+        PublicGame {
+            owner: game.owner,
+            bet_amount: game.bet_amount,
+            current_row: game.current_row,
+            current_col: game.current_col,
+            path: game.path,
+            result: game.result,
+            final_multiplier: game.final_multiplier,
+            house_edge_bps: game.house_edge_bps,
+            seeds: game.seeds,
+        }
+    }
 }
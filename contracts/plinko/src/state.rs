@@ -1,24 +1,34 @@
 use linera_sdk::{
-    linera_base_types::ApplicationId,
-    views::{RegisterView, RootView, ViewStorageContext},
+    linera_base_types::AccountOwner,
+    views::{LogView, MapView, RootView, ViewStorageContext},
 };
-use plinko::{Direction, GameResult};
+use plinko::{Direction, GameResult, PlinkoParams, Round};
+use pulse_common::{BetSeeds, GameSetup, PendingBet, RoundIndex, SeedBook};
 use serde::{Deserialize, Serialize};
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct PlinkoState {
-    pub active_game: RegisterView<Option<Game>>,
-    pub pulse_token_id: RegisterView<Option<ApplicationId>>,
+    /// Each owner's current or last game
+    pub active_games: MapView<AccountOwner, Game>,
+    /// Drops waiting for the house link their path is drawn from
+    pub pending: MapView<AccountOwner, PendingBet<PlinkoParams>>,
+    pub setup: GameSetup,
+    pub seeds: SeedBook,
+    /// Every settled round; a round's id is its index
+    pub rounds: LogView<Round>,
+    pub round_index: RoundIndex,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Game {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub bet_amount: u64,
     pub current_row: u8, // 0 to 8
     pub current_col: i8, // 0 start. -1 Left, +1 Right.
     pub path: Vec<Direction>,
     pub result: GameResult,
     pub final_multiplier: u64,
+    pub house_edge_bps: u16, // Edge when the bet was placed; later config updates do not apply
+    pub seeds: BetSeeds,
 }
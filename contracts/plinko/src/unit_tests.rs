@@ -0,0 +1,148 @@
+use super::*;
+use linera_sdk::{
+    linera_base_types::{Amount, Timestamp},
+    util::BlockingWait,
+};
+use pulse_common::{
+    fairness,
+    testing::{self, GameUnderTest, ALICE, BOB},
+    BetSeeds, GameSetup,
+};
+
+impl GameUnderTest for PlinkoContract {
+    fn deploy(runtime: ContractRuntime<Self>) -> Self {
+        let state = PlinkoState::load(runtime.root_view_storage_context())
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        PlinkoContract { state, runtime }
+    }
+
+    fn parts(&mut self) -> (&mut GameSetup, &mut ContractRuntime<Self>) {
+        (&mut self.state.setup, &mut self.runtime)
+    }
+
+    fn bet(owner: AccountOwner) -> Operation {
+        Operation::StartGame { amount: 10, owner, client_seed: "seed".into() }
+    }
+
+    fn refund(owner: AccountOwner) -> Operation {
+        Operation::Refund { owner }
+    }
+
+    fn update_config(config: GameConfig) -> Operation {
+        Operation::UpdateConfig { config }
+    }
+
+    fn commit_house_seeds(hash: String) -> Operation {
+        Operation::CommitHouseSeeds { hash }
+    }
+
+    fn publish_house_seed(seed: String) -> Operation {
+        Operation::PublishHouseSeed { seed }
+    }
+}
+
+#[test]
+fn test_shared_game_rules() {
+    testing::check_shared_rules::<PlinkoContract>();
+}
+
+/// A contract whose blocks are signed by `signer`, with `ALICE`'s drop waiting for the house.
+fn contract_signed_by(signer: Option<&str>) -> PlinkoContract {
+    let alice = bet::parse_owner(ALICE);
+    let mut contract = testing::contract_signed_by::<PlinkoContract>(signer);
+    let drop = PendingBet {
+        owner: alice,
+        stake: 10,
+        params: PlinkoParams { amount: 10 },
+        house_edge_bps: 100,
+        hold_reference: 0,
+        seeds: BetSeeds::default(),
+        locked_at: Timestamp::from(0),
+    };
+    contract.state.pending.insert(&alice, drop).expect("Failed to save game");
+    contract
+}
+
+fn start(owner: &str) -> Operation {
+    Operation::StartGame { amount: 10, owner: bet::parse_owner(owner), client_seed: "seed".into() }
+}
+
+#[test]
+#[should_panic(expected = "Game already active")]
+fn test_start_while_a_drop_waits_for_the_house_is_rejected() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    contract.execute_operation(start(ALICE)).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "The house has not committed to a seed chain")]
+fn test_drops_wait_for_the_house_to_commit() {
+    let mut contract = contract_signed_by(Some(BOB));
+    contract.execute_operation(start(BOB)).blocking_wait();
+}
+
+#[test]
+#[should_panic(expected = "The house has until")]
+fn test_refund_waits_for_the_house() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    contract.execute_operation(Operation::Refund { owner: bet::parse_owner(ALICE) }).blocking_wait();
+}
+
+#[test]
+fn test_refund_releases_a_drop_the_house_left_waiting() {
+    let mut contract = contract_signed_by(Some(ALICE));
+    let alice = bet::parse_owner(ALICE);
+    let calls = testing::record_token_calls(&mut contract.runtime);
+    testing::wait_out_refund_delay(&mut contract.runtime);
+    contract.execute_operation(Operation::Refund { owner: alice }).blocking_wait();
+
+    assert!(matches!(calls.lock().unwrap()[..], [pulse_token::Operation::Release { reference: 0 }]));
+    assert!(!contract.state.pending.contains_key(&alice).blocking_wait().expect("Failed to read pending drops"));
+}
+
+/// Settles a drop of Alice's whose slot pays more than the stake or less, as asked. Returns its
+/// outcome and the payout of its hold.
+fn settled_drop(gains: bool) -> (PlinkoContract, PlinkoOutcome, Amount) {
+    let params = PlinkoParams { amount: 10 };
+    let link = &testing::house_links()[0];
+    let client_seed = testing::client_seed_where(link, 0, |rng| {
+        (plinko::drop_ball(rng, &params, 100).payout > params.amount) == gains
+    });
+    let (_, mut rng) = fairness::replay(link, &client_seed, 0).expect("test seeds are hex-encoded");
+    let outcome = plinko::drop_ball(&mut rng, &params, 100);
+
+    let drop = Operation::StartGame { amount: 10, owner: bet::parse_owner(ALICE), client_seed };
+    let (contract, payout) = testing::settle_bet::<PlinkoContract>(drop, 10);
+    (contract, outcome, payout)
+}
+
+/// Alice's settled drop, checking it no longer waits for the house.
+fn saved_drop(contract: &PlinkoContract) -> Game {
+    let alice = bet::parse_owner(ALICE);
+    let waiting = contract.state.pending.contains_key(&alice).blocking_wait();
+    assert!(!waiting.expect("Failed to read pending drops"));
+    let game = contract.state.active_games.get(&alice).blocking_wait();
+    game.expect("Failed to read games").expect("the drop is saved")
+}
+
+#[test]
+fn test_drop_into_a_rich_slot_pays_more_than_the_stake() {
+    let (contract, outcome, payout) = settled_drop(true);
+
+    assert!(outcome.payout > 10);
+    assert_eq!(payout, bet::tokens(outcome.payout));
+    let game = saved_drop(&contract);
+    assert_eq!((game.path, game.final_multiplier), (outcome.path, outcome.multiplier));
+    assert_eq!(game.current_row, ROWS);
+}
+
+#[test]
+fn test_drop_into_a_poor_slot_pays_part_of_the_stake() {
+    let (contract, outcome, payout) = settled_drop(false);
+
+    assert!(outcome.payout < 10);
+    assert_eq!(payout, bet::tokens(outcome.payout));
+    let game = saved_drop(&contract);
+    assert_eq!((game.path, game.final_multiplier), (outcome.path, outcome.multiplier));
+}
@@ -0,0 +1,30 @@
+use std::str::FromStr;
+
+use linera_sdk::{
+    linera_base_types::{AccountOwner, Amount},
+    Contract, ContractRuntime,
+};
+
+/// Games take bets in whole tokens.
+pub fn tokens(amount: u64) -> Amount {
+    Amount::from_tokens(amount.into())
+}
+
+pub fn parse_owner(owner: &str) -> AccountOwner {
+    AccountOwner::from_str(owner).expect("Invalid owner address")
+}
+
+/// The owner who signed the block; follow-up moves of a game act on their session.
+pub fn signer<C: Contract>(runtime: &mut ContractRuntime<C>) -> AccountOwner {
+    runtime.authenticated_signer().expect("Operation must be signed")
+}
+
+/// Checks that `owner` signed the block, so nobody can bet or act on another player's behalf.
+pub fn check_signer<C: Contract>(runtime: &mut ContractRuntime<C>, owner: AccountOwner) {
+    assert_eq!(runtime.authenticated_signer(), Some(owner), "Operation must be signed by the owner");
+}
+
+/// `amount` times a multiplier given in percent, rounded down.
+pub fn apply_multiplier(amount: u64, multiplier_percent: u64) -> u64 {
+    (u128::from(amount) * u128::from(multiplier_percent) / 100) as u64
+}
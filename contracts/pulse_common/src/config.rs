@@ -0,0 +1,66 @@
+use async_graphql::{InputObject, SimpleObject};
+use linera_sdk::{linera_base_types::AccountOwner, Contract, ContractRuntime};
+use serde::{Deserialize, Serialize};
+
+/// House edges are given in basis points of the fair payout.
+pub const BASIS_POINTS: u64 = 10_000;
+
+/// Limits and house edge of a game.
+///
+/// A game is deployed with its initial config as application parameters; the admin changes it
+/// later with the game's `UpdateConfig` operation, without redeploying.
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject, InputObject)]
+#[graphql(input_name = "GameConfigInput")]
+pub struct GameConfig {
+    pub admin: AccountOwner,
+    pub house_edge_bps: u16,
+    /// Smallest stake, in whole tokens
+    pub min_bet: u64,
+    /// Largest stake, in whole tokens
+    pub max_bet: u64,
+    /// Largest amount a single bet may pay out, in whole tokens. Bets that could win more are
+    /// refused up front.
+    pub max_payout: u64,
+}
+
+impl GameConfig {
+    pub fn check(&self) {
+        assert!(u64::from(self.house_edge_bps) < BASIS_POINTS, "House edge must be below 100%");
+        assert!(self.min_bet > 0, "Minimum bet must be positive");
+        assert!(self.min_bet <= self.max_bet, "Minimum bet must not exceed the maximum bet");
+    }
+
+    pub fn check_stake(&self, amount: u64) {
+        assert!(
+            amount >= self.min_bet && amount <= self.max_bet,
+            "Bet must be between {} and {} tokens",
+            self.min_bet,
+            self.max_bet
+        );
+    }
+
+    /// Refuses a bet whose best outcome would pay more than `max_payout`.
+    pub fn check_payout(&self, max_possible_payout: u64) {
+        assert!(
+            max_possible_payout <= self.max_payout,
+            "Bet could pay out {max_possible_payout} tokens, more than the maximum of {}",
+            self.max_payout
+        );
+    }
+
+    /// Checks that the block is signed by the admin, who alone may replace the config and feed
+    /// the house seed chain.
+    pub fn check_admin<C: Contract>(&self, runtime: &mut ContractRuntime<C>) {
+        assert_eq!(
+            runtime.authenticated_signer(),
+            Some(self.admin),
+            "Only the game admin can do this"
+        );
+    }
+}
+
+/// `multiplier_percent` with the house edge taken off, rounded down.
+pub fn apply_house_edge(multiplier_percent: u64, house_edge_bps: u16) -> u64 {
+    let kept = BASIS_POINTS.saturating_sub(u64::from(house_edge_bps));
+    (u128::from(multiplier_percent) * u128::from(kept) / u128::from(BASIS_POINTS)) as u64
+}
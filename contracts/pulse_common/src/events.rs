@@ -0,0 +1,35 @@
+use async_graphql::Enum;
+use linera_sdk::linera_base_types::{AccountOwner, Amount};
+use serde::{Deserialize, Serialize};
+
+/// The games of PulseBet, as named in events and on the leaderboard
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Enum)]
+pub enum GameKind {
+    Dice,
+    CoinToss,
+    Wheel,
+    Keno,
+    Mines,
+    Plinko,
+    MemoryGame,
+    ColorTrading,
+}
+
+/// Stream every game emits its [`GameSettled`] events on, one event per settled bet.
+///
+/// Subscribe with the game's application id to follow its results instead of polling.
+pub const SETTLED_STREAM: &str = "game_settled";
+
+/// A bet that has been paid out (or lost), generic over the game's own outcome type
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GameSettled<Outcome> {
+    pub game: GameKind,
+    pub owner: AccountOwner,
+    /// Stake of the bet
+    pub bet: Amount,
+    pub outcome: Outcome,
+    /// What the bet paid, zero for a loss
+    pub payout: Amount,
+    /// Id of the round in the game's history; color_trading uses the id of the shared round
+    pub round_id: u64,
+}
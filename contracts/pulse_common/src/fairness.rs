@@ -0,0 +1,196 @@
+use async_graphql::SimpleObject;
+use linera_sdk::{
+    linera_base_types::Timestamp,
+    views::{linera_views, MapView, RegisterView, View, ViewStorageContext},
+    Contract, ContractRuntime,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::GameRng;
+
+/// Longest client seed a bet accepts
+pub const MAX_CLIENT_SEED_LEN: usize = 64;
+/// How long a bet waits for its house link before its owner may take the stake back
+pub const REFUND_DELAY_SECS: u64 = 3_600;
+/// Most hashes [`earlier_link`] takes to walk back from one link to another
+pub const MAX_LINK_DISTANCE: u64 = 1_000_000;
+
+/// What a player sees before betting: the link their bet will be drawn from, by its hash
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct SeedCommitment {
+    pub server_seed_hash: String,
+    pub next_nonce: u64,
+}
+
+/// A published link of the house chain, the server seed of every bet locked against its hash
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct RevealedSeed {
+    pub server_seed: String,
+    pub server_seed_hash: String,
+    pub revealed_at: Timestamp,
+}
+
+/// The seeds a bet is drawn from, fixed when its stake is locked
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct BetSeeds {
+    /// Hash of the house link the bet waits for
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub nonce: u64,
+}
+
+/// The house's seed chain.
+///
+/// The admin picks a secret off-chain and hashes it `n` times, then commits to the last hash
+/// with `CommitHouseSeeds`. `PublishHouseSeed` reveals the links in reverse order: each one must
+/// hash to the previous, so there is exactly one link the house can publish next.
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct HouseChain {
+    /// Hex-encoded latest published link; the commitment until the first link is out. The next
+    /// link hashes to it.
+    pub head: String,
+    /// Links published since the commitment
+    pub published: u64,
+}
+
+/// Hex-encoded SHA-256 of the server seed bytes, the value a player is shown up front.
+pub fn hash_server_seed(seed: &[u8]) -> String {
+    hex::encode(Sha256::digest(seed))
+}
+
+/// Hash of a hex-encoded seed, the form house links are published and committed in.
+pub fn hash_hex_seed(seed: &str) -> Result<String, String> {
+    let seed_bytes = hex::decode(seed).map_err(|_| "Seed must be hex-encoded".to_string())?;
+    Ok(hash_server_seed(&seed_bytes))
+}
+
+/// Rebuilds the stream of a bet from its revealed server seed, returning the seed's hash to
+/// compare with the commitment the player was shown.
+pub fn replay(server_seed: &str, client_seed: &str, nonce: u64) -> Result<(String, GameRng), String> {
+    let seed_bytes = hex::decode(server_seed).map_err(|_| "Server seed must be hex-encoded".to_string())?;
+    let rng = GameRng::from_seeds(&seed_bytes, client_seed, nonce);
+    Ok((hash_server_seed(&seed_bytes), rng))
+}
+
+/// The link that hashes to `server_seed_hash`, found from `link` or any link published after it:
+/// every earlier link is a hash of a later one.
+pub fn earlier_link(link: &str, server_seed_hash: &str) -> Result<String, String> {
+    let mut link = link.to_ascii_lowercase();
+    let server_seed_hash = server_seed_hash.to_ascii_lowercase();
+    for _ in 0..MAX_LINK_DISTANCE {
+        let hash = hash_hex_seed(&link)?;
+        if hash == server_seed_hash {
+            return Ok(link);
+        }
+        link = hash;
+    }
+    Err(format!("No link within {MAX_LINK_DISTANCE} hashes of the server seed hashes to {server_seed_hash}"))
+}
+
+pub fn check_client_seed(client_seed: &str) {
+    assert!(!client_seed.is_empty(), "Client seed must not be empty");
+    assert!(
+        client_seed.len() <= MAX_CLIENT_SEED_LEN,
+        "Client seed must be at most {MAX_CLIENT_SEED_LEN} bytes"
+    );
+}
+
+/// The house chain a game draws its bets from and the links published so far.
+///
+/// A bet is drawn from the next link of the chain, the one not published yet, with the player's
+/// client seed and a nonce of its own. Its stake is locked first, while that link is known to the
+/// house alone; the bet settles when `PublishHouseSeed` reveals it. So:
+///
+/// - The house cannot pick the outcome: the link was fixed by the commitment before the bet, and
+///   the client seed was chosen after.
+/// - The player cannot compute the outcome before betting: nothing on chain, in the state or
+///   elsewhere, holds the link until it is published, and then the bet is already locked.
+/// - The house can compute the outcome as soon as the bet is locked, and could withhold the link
+///   of a bet it would lose. It cannot void that bet alone, since every bet locked against the
+///   link waits with it, and after [`REFUND_DELAY_SECS`] owners take their stakes back.
+/// - Anyone who learns the house's secret can compute every bet, so it must stay off chain.
+/// - A link can be read from the block that publishes it before that block is committed. A
+///   player who proposes a competing block in the meantime could lock a bet against a link they
+///   have read, so the house should publish in rounds where it is the only owner allowed to
+///   propose.
+///
+/// Each chain the game runs on keeps its own chain of links and its own nonces.
+#[derive(View)]
+#[view(context = ViewStorageContext)]
+pub struct SeedBook {
+    pub house: RegisterView<Option<HouseChain>>,
+    /// Published links keyed by their hash, the `server_seed_hash` of the bets drawn from them
+    pub revealed: MapView<String, RevealedSeed>,
+    /// Bets drawn so far, which is also the nonce of the next one
+    pub nonce: RegisterView<u64>,
+}
+
+impl SeedBook {
+    /// The link and nonce the next bet will be drawn from.
+    pub fn commitment(&self) -> Option<SeedCommitment> {
+        self.house_chain().map(|chain| SeedCommitment {
+            server_seed_hash: chain.head,
+            next_nonce: *self.nonce.get(),
+        })
+    }
+
+    pub async fn revealed(&self, server_seed_hash: &str) -> Option<RevealedSeed> {
+        self.revealed
+            .get(&server_seed_hash.to_string())
+            .await
+            .expect("failed to read revealed seeds")
+    }
+
+    pub fn house_chain(&self) -> Option<HouseChain> {
+        self.house.get().clone()
+    }
+
+    /// Starts a new house chain from `hash`, the hex-encoded SHA-256 of its first link. Bets still
+    /// waiting for a link of the old chain can only be refunded. The caller checks that the admin
+    /// signed.
+    pub fn commit_house_chain(&mut self, hash: String) {
+        let bytes = hex::decode(&hash).expect("House seed hash must be hex-encoded");
+        assert_eq!(bytes.len(), 32, "House seed hash must be a SHA-256 hash");
+        self.house.set(Some(HouseChain { head: hash, published: 0 }));
+    }
+
+    /// Publishes the next link of the house chain, so that the bets waiting for it can settle.
+    /// The caller checks that the admin signed.
+    pub fn publish_house_seed<C: Contract>(&mut self, runtime: &mut ContractRuntime<C>, seed: String) -> RevealedSeed {
+        let mut chain = self.house_chain().expect("The house has not committed to a seed chain");
+        assert_eq!(
+            hash_hex_seed(&seed).expect("House seed must be hex-encoded"),
+            chain.head,
+            "House seed does not hash to the last link of the chain"
+        );
+        let revealed = RevealedSeed {
+            server_seed: seed.clone(),
+            server_seed_hash: chain.head,
+            revealed_at: runtime.system_time(),
+        };
+        self.revealed
+            .insert(&revealed.server_seed_hash, revealed.clone())
+            .expect("failed to record revealed seed");
+        chain.head = seed;
+        chain.published += 1;
+        self.house.set(Some(chain));
+        revealed
+    }
+
+    /// Draws a bet with `client_seed` from the next link, which is not published yet.
+    pub fn commit(&mut self, client_seed: String) -> BetSeeds {
+        check_client_seed(&client_seed);
+        let chain = self.house_chain().expect("The house has not committed to a seed chain");
+        let nonce = *self.nonce.get();
+        self.nonce.set(nonce + 1);
+        BetSeeds { server_seed_hash: chain.head, client_seed, nonce }
+    }
+
+    /// The stream of a bet, once the link it waits for is published.
+    pub async fn rng_for(&self, seeds: &BetSeeds) -> Option<GameRng> {
+        let revealed = self.revealed(&seeds.server_seed_hash).await?;
+        let seed_bytes = hex::decode(&revealed.server_seed).expect("house seeds are hex-encoded");
+        Some(GameRng::from_seeds(&seed_bytes, &seeds.client_seed, seeds.nonce))
+    }
+}
@@ -0,0 +1,136 @@
+use async_graphql::{OutputType, SimpleObject};
+use linera_sdk::{
+    linera_base_types::{AccountOwner, ApplicationId, StreamName},
+    views::{linera_views, LogView, RegisterView, View, ViewStorageContext},
+    Contract, ContractRuntime,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    bet,
+    events::SETTLED_STREAM,
+    history::Round,
+    BetSeeds, GameConfig, GameKind, GameSettled, LeaderboardClient, RoundIndex, TokenClient,
+};
+
+/// What every game keeps in its state besides its own games: the applications it calls and the
+/// config it currently runs with.
+#[derive(View)]
+#[view(context = ViewStorageContext)]
+pub struct GameSetup {
+    pub pulse_token_id: RegisterView<Option<ApplicationId>>,
+    /// Leaderboard the game reports its settled bets to, if any
+    pub leaderboard_id: RegisterView<Option<ApplicationId>>,
+    pub config: RegisterView<Option<GameConfig>>,
+}
+
+impl GameSetup {
+    /// Stores the applications the game calls and the config it was deployed with.
+    pub fn instantiate<C: Contract<Parameters = GameConfig>>(
+        &mut self,
+        runtime: &mut ContractRuntime<C>,
+        pulse_token_id: ApplicationId,
+        leaderboard_id: Option<ApplicationId>,
+    ) {
+        self.pulse_token_id.set(Some(pulse_token_id));
+        self.leaderboard_id.set(leaderboard_id);
+        let config = runtime.application_parameters();
+        config.check();
+        self.config.set(Some(config));
+    }
+
+    pub fn config(&self) -> GameConfig {
+        self.config.get().clone().expect("Game config is not set")
+    }
+
+    /// Checks that the block is signed by the current admin.
+    pub fn check_admin<C: Contract>(&self, runtime: &mut ContractRuntime<C>) {
+        self.config().check_admin(runtime);
+    }
+
+    /// Replaces the limits and house edge; only the current admin may do this.
+    pub fn update_config<C: Contract>(&mut self, runtime: &mut ContractRuntime<C>, config: GameConfig) {
+        self.check_admin(runtime);
+        config.check();
+        self.config.set(Some(config));
+    }
+
+    pub fn token(&self) -> TokenClient {
+        TokenClient::new(self.pulse_token_id.get().expect("PulseToken application is not configured"))
+    }
+
+    pub fn leaderboard(&self) -> Option<LeaderboardClient> {
+        self.leaderboard_id.get().map(LeaderboardClient::new)
+    }
+
+    /// The edge a bet was placed under when the caller knows it, the current one otherwise.
+    pub fn house_edge_bps(&self, requested: Option<u16>) -> u16 {
+        requested
+            .or_else(|| self.config.get().as_ref().map(|config| config.house_edge_bps))
+            .unwrap_or_default()
+    }
+
+    /// Appends a settled bet to the history, emits it on the settled stream and reports it to
+    /// the leaderboard. Returns the id of its round.
+    pub async fn record_round<C, Params, Outcome>(
+        &self,
+        runtime: &mut ContractRuntime<C>,
+        rounds: &mut LogView<Round<Params, Outcome>>,
+        round_index: &mut RoundIndex,
+        settled: SettledBet<Params, Outcome>,
+    ) -> u64
+    where
+        C: Contract<EventValue = GameSettled<Outcome>>,
+        Params: OutputType + Clone + Serialize + DeserializeOwned,
+        Outcome: OutputType + Clone + Serialize + DeserializeOwned,
+    {
+        let round_id = rounds.count() as u64;
+        let event = GameSettled {
+            game: settled.game,
+            owner: settled.owner,
+            bet: bet::tokens(settled.stake),
+            outcome: settled.outcome.clone(),
+            payout: bet::tokens(settled.payout),
+            round_id,
+        };
+        runtime.emit(StreamName::from(SETTLED_STREAM), &event);
+        if let Some(leaderboard) = self.leaderboard() {
+            leaderboard.record(runtime, settled.owner, event.bet, event.payout);
+        }
+        rounds.push(Round {
+            round_id,
+            owner: settled.owner,
+            params: settled.params,
+            outcome: settled.outcome,
+            payout: settled.payout,
+            house_edge_bps: settled.house_edge_bps,
+            timestamp: runtime.system_time(),
+            seeds: settled.seeds,
+        });
+        round_index.push(settled.owner, round_id).await;
+        round_id
+    }
+}
+
+/// A bet that has just been paid out (or lost), before it gets its round id
+#[derive(Clone, Debug)]
+pub struct SettledBet<Params, Outcome> {
+    pub game: GameKind,
+    pub owner: AccountOwner,
+    /// Stake, in whole tokens
+    pub stake: u64,
+    pub params: Params,
+    pub outcome: Outcome,
+    /// What the bet paid, in whole tokens
+    pub payout: u64,
+    pub house_edge_bps: u16,
+    pub seeds: BetSeeds,
+}
+
+/// Result of a game's `verify` query
+#[derive(SimpleObject)]
+pub struct Verification<Outcome: OutputType> {
+    /// Compare with the commitment shown before the bet
+    pub server_seed_hash: String,
+    pub outcome: Outcome,
+}
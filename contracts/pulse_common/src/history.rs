@@ -0,0 +1,95 @@
+use async_graphql::{OutputType, SimpleObject};
+use linera_sdk::{
+    linera_base_types::{AccountOwner, Timestamp},
+    views::{linera_views, CollectionView, LogView, View, ViewStorageContext},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::BetSeeds;
+
+/// Rounds returned by a history query when the caller does not ask for a page size
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+/// Largest page a history query returns
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// A settled bet, as kept in its owner's history. Each game names its own with its bet
+/// parameters and outcome.
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Round<Params: OutputType, Outcome: OutputType> {
+    pub round_id: u64,
+    pub owner: AccountOwner,
+    pub params: Params,
+    pub outcome: Outcome,
+    pub payout: u64,
+    pub house_edge_bps: u16,
+    pub timestamp: Timestamp,
+    pub seeds: BetSeeds,
+}
+
+/// Ids of every owner's settled rounds, oldest first.
+///
+/// A game keeps the rounds themselves in a `LogView` whose indices are the round ids; this
+/// index is what lets the service page through one player's rounds without scanning them all.
+#[derive(View)]
+#[view(context = ViewStorageContext)]
+pub struct RoundIndex {
+    pub by_owner: CollectionView<AccountOwner, LogView<u64>>,
+}
+
+impl RoundIndex {
+    pub async fn push(&mut self, owner: AccountOwner, round_id: u64) {
+        self.by_owner
+            .load_entry_mut(&owner)
+            .await
+            .expect("failed to load round index")
+            .push(round_id);
+    }
+
+    /// Up to `first` of `owner`'s round ids, newest first. With `after`, the page starts at
+    /// the round before that one, so the last id of a page is the cursor of the next.
+    pub async fn page(&self, owner: &AccountOwner, first: Option<u32>, after: Option<u64>) -> Vec<u64> {
+        let Some(rounds) = self.by_owner.try_load_entry(owner).await.expect("failed to load round index") else {
+            return Vec::new();
+        };
+        let page_size = first.map_or(DEFAULT_PAGE_SIZE, |first| first as usize).min(MAX_PAGE_SIZE);
+        let mut end = rounds.count();
+        if let Some(after) = after {
+            // Ids are pushed in increasing order, so the cursor is found by bisection
+            let mut low = 0;
+            while low < end {
+                let middle = (low + end) / 2;
+                let id = rounds.get(middle).await.expect("failed to read round index");
+                if id.is_some_and(|id| id < after) {
+                    low = middle + 1;
+                } else {
+                    end = middle;
+                }
+            }
+        }
+        let mut ids = rounds
+            .read(end.saturating_sub(page_size)..end)
+            .await
+            .expect("failed to read round index");
+        ids.reverse();
+        ids
+    }
+
+    /// Up to `first` of `owner`'s rounds from `rounds`, newest first, paged as in [`Self::page`].
+    pub async fn rounds<R>(&self, rounds: &LogView<R>, owner: &AccountOwner, first: Option<u32>, after: Option<u64>) -> Vec<R>
+    where
+        R: Clone + Serialize + DeserializeOwned + Send + Sync,
+    {
+        let round_ids = self.page(owner, first, after).await;
+        let indices = round_ids.into_iter().map(|round_id| round_id as usize).collect();
+        let rounds = rounds.multi_get(indices).await.expect("failed to read rounds");
+        rounds.into_iter().flatten().collect()
+    }
+}
+
+/// The round with id `round_id`, if it has settled.
+pub async fn round<R>(rounds: &LogView<R>, round_id: u64) -> Option<R>
+where
+    R: Clone + Serialize + DeserializeOwned + Send + Sync,
+{
+    rounds.get(round_id as usize).await.expect("failed to read rounds")
+}
@@ -0,0 +1,63 @@
+use async_graphql::{Request, Response};
+use linera_sdk::{
+    abi::{ContractAbi, ServiceAbi},
+    graphql::GraphQLMutationRoot,
+    linera_base_types::{AccountOwner, Amount, ApplicationId},
+    Contract, ContractRuntime,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::GameKind;
+
+/// ABI of the leaderboard application. It lives here so that games can report to the leaderboard
+/// without depending on it; the leaderboard crate re-exports it.
+pub struct LeaderboardAbi;
+
+impl ContractAbi for LeaderboardAbi {
+    type Operation = Operation;
+    type Response = ();
+}
+
+impl ServiceAbi for LeaderboardAbi {
+    type Query = Request;
+    type QueryResponse = Response;
+}
+
+#[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
+pub enum Operation {
+    /// Called by an authorized game application when one of `owner`'s bets settles
+    Record {
+        owner: AccountOwner,
+        bet: Amount,
+        payout: Amount,
+    },
+    /// Lets a game application report its bets (admin only, on the creator chain)
+    AuthorizeGame {
+        application_id: ApplicationId,
+        game: GameKind,
+    },
+    /// Stops counting the bets of a game application (admin only, on the creator chain)
+    RevokeGame {
+        application_id: ApplicationId,
+    },
+}
+
+/// Typed calls from a game contract into the leaderboard application.
+#[derive(Clone, Copy, Debug)]
+pub struct LeaderboardClient {
+    application_id: ApplicationId<LeaderboardAbi>,
+}
+
+impl LeaderboardClient {
+    pub fn new(application_id: ApplicationId) -> Self {
+        LeaderboardClient {
+            application_id: application_id.with_abi::<LeaderboardAbi>(),
+        }
+    }
+
+    /// Reports a settled bet of `owner`. The leaderboard only counts it once the calling game is
+    /// in its registry.
+    pub fn record<C: Contract>(&self, runtime: &mut ContractRuntime<C>, owner: AccountOwner, bet: Amount, payout: Amount) {
+        runtime.call_application(true, self.application_id, &Operation::Record { owner, bet, payout });
+    }
+}
@@ -0,0 +1,29 @@
+//! Code shared by the PulseBet game contracts: calls into pulse_token and the leaderboard,
+//! provably fair randomness, bet validation and the events games emit.
+
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
+
+pub mod bet;
+pub mod config;
+pub mod events;
+pub mod fairness;
+pub mod game;
+pub mod history;
+pub mod leaderboard;
+pub mod pending;
+pub mod rng;
+#[cfg(feature = "test")]
+pub mod testing;
+pub mod token;
+
+pub use config::GameConfig;
+pub use events::{GameKind, GameSettled};
+pub use fairness::{BetSeeds, HouseChain, RevealedSeed, SeedBook, SeedCommitment};
+pub use game::{GameSetup, SettledBet, Verification};
+pub use history::{Round, RoundIndex};
+pub use leaderboard::LeaderboardClient;
+pub use pending::PendingBet;
+pub use rng::GameRng;
+pub use token::TokenClient;
@@ -0,0 +1,107 @@
+use async_graphql::{OutputType, SimpleObject};
+use linera_sdk::{
+    linera_base_types::{AccountOwner, TimeDelta, Timestamp},
+    views::MapView,
+    Contract, ContractRuntime,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{bet, fairness::REFUND_DELAY_SECS, BetSeeds, GameSetup, SeedBook};
+
+/// A bet whose stake is held until the house publishes the link its seeds wait for. Each game
+/// keeps at most one per owner.
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct PendingBet<Params: OutputType> {
+    pub owner: AccountOwner,
+    /// Stake, in whole tokens
+    pub stake: u64,
+    pub params: Params,
+    /// Edge when the bet was placed; later config updates do not apply
+    pub house_edge_bps: u16,
+    /// PulseToken hold holding the stake until the bet settles
+    pub hold_reference: u64,
+    pub seeds: BetSeeds,
+    pub locked_at: Timestamp,
+}
+
+impl<Params: OutputType> PendingBet<Params> {
+    /// When the owner may take the stake back if the house has not published the link by then
+    pub fn refundable_at(&self) -> Timestamp {
+        self.locked_at.saturating_add(TimeDelta::from_secs(REFUND_DELAY_SECS))
+    }
+}
+
+/// Removes and returns the bets waiting for the link that hashes to `server_seed_hash`.
+pub async fn take_due<Params>(
+    bets: &mut MapView<AccountOwner, PendingBet<Params>>,
+    server_seed_hash: &str,
+) -> Vec<PendingBet<Params>>
+where
+    Params: OutputType + Clone + Serialize + DeserializeOwned + Send + Sync,
+{
+    let due: Vec<PendingBet<Params>> = bets
+        .index_values()
+        .await
+        .expect("failed to read pending bets")
+        .into_iter()
+        .map(|(_, bet)| bet)
+        .filter(|bet| bet.seeds.server_seed_hash == server_seed_hash)
+        .collect();
+    for bet in &due {
+        bets.remove(&bet.owner).expect("failed to remove pending bet");
+    }
+    due
+}
+
+impl GameSetup {
+    /// Holds `stake` tokens of `owner` for a bet drawn from the next house link with
+    /// `client_seed`. The hold is referenced by the bet's nonce.
+    pub fn lock_bet<C: Contract, Params: OutputType>(
+        &self,
+        runtime: &mut ContractRuntime<C>,
+        seeds: &mut SeedBook,
+        owner: AccountOwner,
+        stake: u64,
+        params: Params,
+        client_seed: String,
+    ) -> PendingBet<Params> {
+        let seeds = seeds.commit(client_seed);
+        let hold_reference = seeds.nonce;
+        self.token().lock(runtime, owner, bet::tokens(stake), hold_reference);
+        PendingBet {
+            owner,
+            stake,
+            params,
+            house_edge_bps: self.config().house_edge_bps,
+            hold_reference,
+            seeds,
+            locked_at: runtime.system_time(),
+        }
+    }
+
+    /// Hands `owner` back the stake of their pending bet once the house has left it waiting for
+    /// [`REFUND_DELAY_SECS`].
+    pub async fn refund_bet<C, Params>(
+        &self,
+        runtime: &mut ContractRuntime<C>,
+        bets: &mut MapView<AccountOwner, PendingBet<Params>>,
+        owner: AccountOwner,
+    ) where
+        C: Contract,
+        Params: OutputType + Clone + Serialize + DeserializeOwned + Send + Sync,
+    {
+        bet::check_signer(runtime, owner);
+        let pending = bets
+            .get(&owner)
+            .await
+            .expect("failed to read pending bets")
+            .expect("No bet is waiting for the house");
+        assert!(
+            runtime.system_time() >= pending.refundable_at(),
+            "The house has until {} to settle the bet",
+            pending.refundable_at()
+        );
+        self.token().release(runtime, pending.hold_reference);
+        bets.remove(&owner).expect("failed to remove pending bet");
+    }
+}
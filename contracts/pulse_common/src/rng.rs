@@ -0,0 +1,115 @@
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+/// Deterministic randomness for game outcomes.
+///
+/// Bytes come in blocks of `HMAC-SHA256(key, "{message}:{counter}")` for counters 0, 1, 2...
+/// so a game can draw as much as it needs. For provably fair bets the key is the server seed
+/// and the message is `"{client_seed}:{nonce}"`.
+pub struct GameRng {
+    key: Vec<u8>,
+    message: String,
+    counter: u64,
+    block: Vec<u8>,
+    position: usize,
+}
+
+impl GameRng {
+    /// Seeds the generator with the BCS bytes of `seed`, for outcomes that are not tied to a
+    /// player's seeds.
+    pub fn new<T: Serialize>(seed: &T) -> Self {
+        let key = bcs::to_bytes(seed).expect("Serialization failed");
+        Self::with_key(key, String::new())
+    }
+
+    /// The stream of a bet placed with `client_seed` as the `nonce`-th bet on `server_seed`.
+    pub fn from_seeds(server_seed: &[u8], client_seed: &str, nonce: u64) -> Self {
+        Self::with_key(server_seed.to_vec(), format!("{client_seed}:{nonce}"))
+    }
+
+    fn with_key(key: Vec<u8>, message: String) -> Self {
+        GameRng {
+            key,
+            message,
+            counter: 0,
+            block: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        for byte in &mut bytes {
+            *byte = self.next_byte();
+        }
+        u64::from_le_bytes(bytes)
+    }
+
+    /// A uniform integer in `0..bound`.
+    ///
+    /// Taking a draw modulo `bound` favours the low values whenever `bound` does not divide the
+    /// range of the draw, so draws from the last, incomplete run of `bound` values are rejected.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        below(bound, || self.next_u64())
+    }
+
+    /// Moves a uniform sample of `amount` items to the front of `items`, in random order: the
+    /// first `amount` steps of a Fisher–Yates shuffle.
+    pub fn partial_shuffle<T>(&mut self, items: &mut [T], amount: usize) {
+        assert!(amount <= items.len(), "Cannot sample more items than there are");
+        for i in 0..amount {
+            let j = i + self.below((items.len() - i) as u64) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Puts `items` in a uniformly random order.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        let len = items.len();
+        self.partial_shuffle(items, len);
+    }
+
+    /// Picks an index with probability proportional to its weight.
+    pub fn weighted(&mut self, weights: &[u64]) -> usize {
+        let total = weights
+            .iter()
+            .try_fold(0u64, |total, weight| total.checked_add(*weight))
+            .expect("Weights overflow");
+        assert!(total > 0, "Weights must not all be zero");
+        let mut draw = self.below(total);
+        for (index, weight) in weights.iter().enumerate() {
+            if draw < *weight {
+                return index;
+            }
+            draw -= weight;
+        }
+        unreachable!("draw is below the total weight")
+    }
+
+    pub fn next_byte(&mut self) -> u8 {
+        if self.position == self.block.len() {
+            let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+            mac.update(format!("{}:{}", self.message, self.counter).as_bytes());
+            self.block = mac.finalize().into_bytes().to_vec();
+            self.counter += 1;
+            self.position = 0;
+        }
+        let byte = self.block[self.position];
+        self.position += 1;
+        byte
+    }
+}
+
+/// Reduces the first draw of `next` that falls outside the last, incomplete run of `bound` values.
+pub(crate) fn below(bound: u64, mut next: impl FnMut() -> u64) -> u64 {
+    assert!(bound > 0, "Cannot draw from an empty range");
+    let span = 1u128 << 64;
+    let limit = span - span % u128::from(bound);
+    loop {
+        let draw = next();
+        if u128::from(draw) < limit {
+            return draw % bound;
+        }
+    }
+}
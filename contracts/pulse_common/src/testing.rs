@@ -0,0 +1,197 @@
+//! Fixtures for the unit tests of the game contracts, enabled by the `test` feature.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+};
+
+use linera_sdk::{
+    bcs,
+    linera_base_types::{AccountOwner, Amount, ApplicationId, BlockHeight, ChainId, CryptoHash, TimeDelta, Timestamp},
+    util::BlockingWait,
+    Contract, ContractRuntime,
+};
+use pulse_token::{Operation, TokenError};
+
+use crate::{
+    bet,
+    fairness::{self, hash_hex_seed, REFUND_DELAY_SECS},
+    GameConfig, GameRng, GameSetup,
+};
+
+pub const ALICE: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+pub const BOB: &str = "0x2222222222222222222222222222222222222222222222222222222222222222";
+/// Links in the test house chain
+pub const HOUSE_LINKS: usize = 4;
+
+/// The config games are deployed with in tests, administered by `ALICE`.
+pub fn config() -> GameConfig {
+    GameConfig {
+        admin: bet::parse_owner(ALICE),
+        house_edge_bps: 100,
+        min_bet: 1,
+        max_bet: 1_000,
+        max_payout: 1_000_000,
+    }
+}
+
+/// A runtime deployed with [`config`] whose block is signed by `signer`.
+pub fn runtime_signed_by<C: Contract<Parameters = GameConfig>>(signer: Option<&str>) -> ContractRuntime<C> {
+    ContractRuntime::new()
+        .with_application_parameters(config())
+        .with_authenticated_signer(signer.map(bet::parse_owner))
+        .with_chain_id(ChainId(CryptoHash::test_hash("chain")))
+        .with_block_height(BlockHeight(1))
+        .with_system_time(Timestamp::from(1_000_000))
+}
+
+/// Sets the game up as `instantiate` does, with a stand-in pulse_token and no leaderboard.
+pub fn instantiate<C: Contract<Parameters = GameConfig>>(setup: &mut GameSetup, runtime: &mut ContractRuntime<C>) {
+    let pulse_token_id = ApplicationId::new(CryptoHash::test_hash("pulse_token"));
+    setup.instantiate(runtime, pulse_token_id, None);
+}
+
+/// A game contract the shared tests deploy and drive through its operations.
+pub trait GameUnderTest: Contract<Parameters = GameConfig> {
+    /// The contract with its state loaded from `runtime`, before it is instantiated
+    fn deploy(runtime: ContractRuntime<Self>) -> Self;
+    fn parts(&mut self) -> (&mut GameSetup, &mut ContractRuntime<Self>);
+    /// A valid bet of 10 tokens for `owner`
+    fn bet(owner: AccountOwner) -> Self::Operation;
+    fn refund(owner: AccountOwner) -> Self::Operation;
+    fn update_config(config: GameConfig) -> Self::Operation;
+    fn commit_house_seeds(hash: String) -> Self::Operation;
+    fn publish_house_seed(seed: String) -> Self::Operation;
+}
+
+/// A freshly deployed game whose blocks are signed by `signer`.
+pub fn contract_signed_by<G: GameUnderTest>(signer: Option<&str>) -> G {
+    let mut contract = G::deploy(runtime_signed_by(signer));
+    let (setup, runtime) = contract.parts();
+    instantiate(setup, runtime);
+    contract
+}
+
+/// Checks that `operation` panics on `contract` with a message containing `expected`.
+fn assert_rejected<G: GameUnderTest>(contract: &mut G, operation: G::Operation, expected: &str) {
+    let error = panic::catch_unwind(AssertUnwindSafe(|| {
+        contract.execute_operation(operation).blocking_wait();
+    }))
+    .err()
+    .unwrap_or_else(|| panic!("the operation was accepted, expected it to fail with {expected:?}"));
+    let message = match error.downcast_ref::<String>() {
+        Some(message) => message.as_str(),
+        None => error.downcast_ref::<&str>().copied().unwrap_or_default(),
+    };
+    assert!(message.contains(expected), "the operation failed with {message:?}, expected {expected:?}");
+}
+
+/// The rules every game shares: players only act for themselves, only the admin changes the
+/// config or the house chain, and the house publishes its chain link by link.
+pub fn check_shared_rules<G: GameUnderTest>() {
+    let alice = bet::parse_owner(ALICE);
+    let links = house_links();
+    let signed_by = |signer| contract_signed_by::<G>(signer);
+    let on_behalf = "Operation must be signed by the owner";
+    assert_rejected(&mut signed_by(Some(BOB)), G::bet(alice), on_behalf);
+    assert_rejected(&mut signed_by(None), G::bet(alice), on_behalf);
+    assert_rejected(&mut signed_by(Some(BOB)), G::refund(alice), on_behalf);
+    let admin_only = "Only the game admin can do this";
+    assert_rejected(&mut signed_by(Some(BOB)), G::update_config(config()), admin_only);
+    assert_rejected(&mut signed_by(Some(BOB)), G::commit_house_seeds(house_commitment()), admin_only);
+    assert_rejected(&mut signed_by(Some(BOB)), G::publish_house_seed(links[0].clone()), admin_only);
+
+    let mut contract = signed_by(Some(ALICE));
+    let updated = GameConfig { max_bet: 500, ..config() };
+    contract.execute_operation(G::update_config(updated)).blocking_wait();
+    assert_eq!(contract.parts().0.config().max_bet, 500);
+
+    let mut contract = signed_by(Some(ALICE));
+    contract.execute_operation(G::commit_house_seeds(house_commitment())).blocking_wait();
+    assert_rejected(
+        &mut contract,
+        G::publish_house_seed(links[1].clone()),
+        "House seed does not hash to the last link of the chain",
+    );
+
+    let mut contract = signed_by(Some(ALICE));
+    contract.execute_operation(G::commit_house_seeds(house_commitment())).blocking_wait();
+    for link in &links {
+        contract.execute_operation(G::publish_house_seed(link.clone())).blocking_wait();
+    }
+}
+
+/// The first client seed, of `seed-0`, `seed-1` and so on, whose stream for the bet with `nonce`
+/// drawn from `link` satisfies `wanted`.
+pub fn client_seed_where(link: &str, nonce: u64, mut wanted: impl FnMut(&mut GameRng) -> bool) -> String {
+    (0..10_000)
+        .map(|index| format!("seed-{index}"))
+        .find(|client_seed| {
+            let (_, mut rng) = fairness::replay(link, client_seed, nonce).expect("test seeds are hex-encoded");
+            wanted(&mut rng)
+        })
+        .expect("some client seed gives the wanted outcome")
+}
+
+/// Places `operation`, a bet of `stake` tokens by `ALICE`, on a freshly deployed game, publishes
+/// the link it waits for and returns the game with the payout its hold settled with.
+pub fn settle_bet<G: GameUnderTest>(operation: G::Operation, stake: u64) -> (G, Amount) {
+    let mut contract = contract_signed_by::<G>(Some(ALICE));
+    let calls = record_token_calls(contract.parts().1);
+    contract.execute_operation(G::commit_house_seeds(house_commitment())).blocking_wait();
+    contract.execute_operation(operation).blocking_wait();
+    contract.execute_operation(G::publish_house_seed(house_links()[0].clone())).blocking_wait();
+    let payout = settled_payout(&calls, bet::parse_owner(ALICE), stake, 0);
+    (contract, payout)
+}
+
+/// The payout of the hold `reference`, checking that `calls` locked `stake` tokens of `owner` in
+/// it and then settled it, and did nothing else.
+pub fn settled_payout(calls: &TokenCalls, owner: AccountOwner, stake: u64, reference: u64) -> Amount {
+    match &calls.lock().expect("token calls are recorded on one thread")[..] {
+        [Operation::Lock { owner: locked_owner, amount, reference: locked }, Operation::Settle { reference: settled, payout }] => {
+            assert_eq!((*locked_owner, *amount, *locked), (owner, bet::tokens(stake), reference));
+            assert_eq!(*settled, reference);
+            *payout
+        }
+        calls => panic!("expected the stake to be locked and then settled, got {calls:?}"),
+    }
+}
+
+/// The pulse_token calls a contract made, in order.
+pub type TokenCalls = Arc<Mutex<Vec<Operation>>>;
+
+/// Makes every pulse_token call of `runtime` succeed, recording it.
+pub fn record_token_calls<C: Contract>(runtime: &mut ContractRuntime<C>) -> TokenCalls {
+    let calls = TokenCalls::default();
+    let recorded = calls.clone();
+    runtime.set_call_application_handler(move |_authenticated, _application_id, bytes| {
+        let operation = bcs::from_bytes(&bytes).expect("games call pulse_token with its operations");
+        recorded.lock().expect("token calls are recorded on one thread").push(operation);
+        bcs::to_bytes(&Ok::<(), TokenError>(())).expect("token responses serialize")
+    });
+    calls
+}
+
+/// Moves `runtime`'s clock past the time a bet locked at [`runtime_signed_by`]'s start waits for
+/// the house before its owner can take the stake back.
+pub fn wait_out_refund_delay<C: Contract>(runtime: &mut ContractRuntime<C>) {
+    let now = runtime.system_time();
+    runtime.set_system_time(now.saturating_add(TimeDelta::from_secs(REFUND_DELAY_SECS)));
+}
+
+/// Links of the test house chain, in the order they are published.
+pub fn house_links() -> Vec<String> {
+    let mut links = vec!["11".repeat(32)];
+    for _ in 1..HOUSE_LINKS {
+        let next = hash_hex_seed(links.last().expect("the chain has a secret")).expect("test seeds are hex-encoded");
+        links.push(next);
+    }
+    links.reverse();
+    links
+}
+
+/// What the house commits to before the first link of [`house_links`] is published.
+pub fn house_commitment() -> String {
+    hash_hex_seed(&house_links()[0]).expect("test seeds are hex-encoded")
+}
@@ -0,0 +1,65 @@
+use linera_sdk::{
+    linera_base_types::{AccountOwner, Amount, ApplicationId},
+    Contract, ContractRuntime,
+};
+use pulse_token::{CreditEntry, Operation, PulseTokenAbi, TokenError};
+
+/// Typed calls from a game contract into the pulse_token application.
+///
+/// The helpers fail the whole operation with a readable message when pulse_token refuses;
+/// use [`TokenClient::call`] to handle the [`TokenError`] yourself.
+#[derive(Clone, Copy, Debug)]
+pub struct TokenClient {
+    application_id: ApplicationId<PulseTokenAbi>,
+}
+
+impl TokenClient {
+    pub fn new(application_id: ApplicationId) -> Self {
+        TokenClient {
+            application_id: application_id.with_abi::<PulseTokenAbi>(),
+        }
+    }
+
+    pub fn call<C: Contract>(&self, runtime: &mut ContractRuntime<C>, operation: &Operation) -> Result<(), TokenError> {
+        runtime.call_application(true, self.application_id, operation)
+    }
+
+    /// Moves `owner`'s stake into the house treasury.
+    pub fn debit<C: Contract>(&self, runtime: &mut ContractRuntime<C>, owner: AccountOwner, amount: Amount) {
+        self.call(runtime, &Operation::GameDebit { owner, amount })
+            .unwrap_or_else(|error| panic!("Could not take the stake: {error}"));
+    }
+
+    /// Pays `owner` winnings from the house treasury.
+    pub fn credit<C: Contract>(&self, runtime: &mut ContractRuntime<C>, owner: AccountOwner, amount: Amount) {
+        self.call(runtime, &Operation::GameCredit { owner, amount })
+            .unwrap_or_else(|error| panic!("Could not pay out the winnings: {error}"));
+    }
+
+    /// Pays every winner of a round in a single call.
+    pub fn batch_credit<C: Contract>(&self, runtime: &mut ContractRuntime<C>, entries: Vec<CreditEntry>) {
+        if entries.is_empty() {
+            return;
+        }
+        self.call(runtime, &Operation::BatchCredit { entries })
+            .unwrap_or_else(|error| panic!("Could not pay out the winnings: {error}"));
+    }
+
+    /// Holds `owner`'s stake under `reference` until the game settles or releases it.
+    pub fn lock<C: Contract>(&self, runtime: &mut ContractRuntime<C>, owner: AccountOwner, amount: Amount, reference: u64) {
+        self.call(runtime, &Operation::Lock { owner, amount, reference })
+            .unwrap_or_else(|error| panic!("Could not hold the stake: {error}"));
+    }
+
+    /// Gives the held stake to the house and pays out `payout`, which may be zero.
+    pub fn settle<C: Contract>(&self, runtime: &mut ContractRuntime<C>, reference: u64, payout: Amount) {
+        self.call(runtime, &Operation::Settle { reference, payout })
+            .unwrap_or_else(|error| panic!("Could not settle the bet: {error}"));
+    }
+
+    /// Returns the held stake to its owner.
+    pub fn release<C: Contract>(&self, runtime: &mut ContractRuntime<C>, reference: u64) {
+        self.call(runtime, &Operation::Release { reference })
+            .unwrap_or_else(|error| panic!("Could not release the stake: {error}"));
+    }
+}
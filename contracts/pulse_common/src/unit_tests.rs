@@ -0,0 +1,198 @@
+use crate::{
+    config::apply_house_edge,
+    fairness::{earlier_link, hash_hex_seed},
+    rng, GameRng,
+};
+
+// Draws per expected hit; with deterministic seeds the checks below never flake.
+const SAMPLES_PER_OUTCOME: u64 = 4_000;
+
+/// Asserts that `counts[i]` is within tolerance of `total * probabilities[i]`.
+fn assert_frequencies(counts: &[u64], probabilities: &[f64]) {
+    let total: u64 = counts.iter().sum();
+    for (outcome, (count, probability)) in counts.iter().zip(probabilities).enumerate() {
+        let expected = total as f64 * probability;
+        let deviation = (expected * (1.0 - probability)).sqrt();
+        let difference = (*count as f64 - expected).abs();
+        assert!(
+            difference <= 5.0 * deviation,
+            "outcome {outcome} drawn {count} times, expected {expected:.0} ± {:.0}",
+            5.0 * deviation
+        );
+    }
+}
+
+fn uniform(outcomes: usize) -> Vec<f64> {
+    vec![1.0 / outcomes as f64; outcomes]
+}
+
+#[test]
+fn test_streams_are_deterministic() {
+    let mut first = GameRng::from_seeds(b"server", "client", 7);
+    let mut second = GameRng::from_seeds(b"server", "client", 7);
+    let mut next_nonce = GameRng::from_seeds(b"server", "client", 8);
+
+    let first: Vec<u8> = (0..100).map(|_| first.next_byte()).collect();
+    let second: Vec<u8> = (0..100).map(|_| second.next_byte()).collect();
+    let next_nonce: Vec<u8> = (0..100).map(|_| next_nonce.next_byte()).collect();
+
+    assert_eq!(first, second);
+    assert_ne!(first, next_nonce);
+}
+
+#[test]
+fn test_below_is_uniform() {
+    // Dice, coin toss, wheel and mines bounds; none but 2 divides 2^64
+    for bound in [2u64, 10, 25, 40, 100] {
+        let mut rng = GameRng::new(&("below", bound));
+        let mut counts = vec![0; bound as usize];
+        for _ in 0..bound * SAMPLES_PER_OUTCOME {
+            let value = rng.below(bound);
+            assert!(value < bound);
+            counts[value as usize] += 1;
+        }
+        assert_frequencies(&counts, &uniform(bound as usize));
+    }
+}
+
+#[test]
+fn test_below_rejects_draws_past_the_last_full_run() {
+    // (bound, draws, expected): each case ends with the first draw inside the acceptance zone
+    let cases: [(u64, &[u64], u64); 3] = [
+        // 2^64 = 6 (mod 10), so the top 6 values would favour 0..6 and are redrawn
+        (10, &[u64::MAX, u64::MAX - 5, u64::MAX - 6], 9),
+        // 2^64 = 1 (mod 3): only the top value is redrawn
+        (3, &[u64::MAX, u64::MAX - 1], 2),
+        // 2 divides 2^64, so no draw is rejected
+        (2, &[u64::MAX], 1),
+    ];
+    for (bound, draws, expected) in cases {
+        let mut source = draws.iter().copied();
+        assert_eq!(rng::below(bound, || source.next().expect("draw was rejected")), expected);
+        assert_eq!(source.next(), None, "a draw inside the acceptance zone was rejected");
+    }
+}
+
+#[test]
+fn test_below_one_is_zero() {
+    let mut rng = GameRng::new(&"one");
+    assert!((0..100).all(|_| rng.below(1) == 0));
+}
+
+#[test]
+#[should_panic(expected = "Cannot draw from an empty range")]
+fn test_below_zero_panics() {
+    GameRng::new(&"zero").below(0);
+}
+
+#[test]
+fn test_shuffle_orders_are_uniform() {
+    // All 24 orders of 4 items
+    let mut rng = GameRng::new(&"shuffle");
+    let mut counts = vec![0; 24];
+    for _ in 0..24 * SAMPLES_PER_OUTCOME {
+        let mut items = [0usize, 1, 2, 3];
+        rng.shuffle(&mut items);
+        let rank = items.iter().enumerate().fold(0, |rank, (position, item)| {
+            let smaller_after = items[position + 1..].iter().filter(|other| *other < item).count();
+            rank + smaller_after * [6, 2, 1, 1][position]
+        });
+        counts[rank] += 1;
+    }
+    assert_frequencies(&counts, &uniform(24));
+}
+
+#[test]
+fn test_shuffle_keeps_every_item() {
+    let mut rng = GameRng::new(&"permutation");
+    let mut cards: Vec<u8> = (0..12).map(|position| position / 2).collect();
+    rng.shuffle(&mut cards);
+    cards.sort();
+    assert_eq!(cards, (0..12).map(|position| position / 2).collect::<Vec<u8>>());
+}
+
+#[test]
+fn test_partial_shuffle_samples_uniformly() {
+    // Keno draws 10 of 40 numbers, so each one is drawn a quarter of the time
+    let mut rng = GameRng::new(&"keno");
+    let rounds = 40 * SAMPLES_PER_OUTCOME / 10;
+    let mut counts = vec![0; 40];
+    for _ in 0..rounds {
+        let mut deck: Vec<usize> = (0..40).collect();
+        rng.partial_shuffle(&mut deck, 10);
+        let mut drawn = deck[..10].to_vec();
+        drawn.sort();
+        drawn.dedup();
+        assert_eq!(drawn.len(), 10);
+        for number in drawn {
+            counts[number] += 1;
+        }
+    }
+    for count in &counts {
+        let expected = rounds as f64 / 4.0;
+        let deviation = (expected * 0.75).sqrt();
+        assert!((*count as f64 - expected).abs() <= 5.0 * deviation, "drawn {count} times");
+    }
+}
+
+#[test]
+fn test_partial_shuffle_first_pick_is_uniform() {
+    let mut rng = GameRng::new(&"first");
+    let mut counts = vec![0; 25];
+    for _ in 0..25 * SAMPLES_PER_OUTCOME {
+        let mut tiles: Vec<usize> = (0..25).collect();
+        rng.partial_shuffle(&mut tiles, 3);
+        counts[tiles[0]] += 1;
+    }
+    assert_frequencies(&counts, &uniform(25));
+}
+
+#[test]
+fn test_weighted_follows_weights() {
+    // Color trading's odds
+    let weights = [30, 30, 15, 15, 10];
+    let mut rng = GameRng::new(&"weighted");
+    let mut counts = vec![0; weights.len()];
+    for _ in 0..100 * SAMPLES_PER_OUTCOME {
+        counts[rng.weighted(&weights)] += 1;
+    }
+    let probabilities: Vec<f64> = weights.iter().map(|weight| *weight as f64 / 100.0).collect();
+    assert_frequencies(&counts, &probabilities);
+}
+
+#[test]
+fn test_weighted_skips_zero_weights() {
+    let mut rng = GameRng::new(&"zero weights");
+    assert!((0..1_000).all(|_| rng.weighted(&[0, 5, 0]) == 1));
+}
+
+#[test]
+fn test_house_edge_rounds_down() {
+    assert_eq!(apply_house_edge(200, 0), 200);
+    assert_eq!(apply_house_edge(200, 100), 198);
+    assert_eq!(apply_house_edge(9_900, 250), 9_652);
+    assert_eq!(apply_house_edge(1, 1), 0);
+}
+
+#[test]
+fn test_full_house_edge_pays_nothing() {
+    assert_eq!(apply_house_edge(1_000, 10_000), 0);
+    assert_eq!(apply_house_edge(1_000, u16::MAX), 0);
+}
+
+#[test]
+fn test_earlier_links_are_found_from_later_ones() {
+    let secret = "11".repeat(32);
+    let later = hash_hex_seed(&secret).expect("hex seed");
+    let earlier = hash_hex_seed(&later).expect("hex seed");
+    let earlier_hash = hash_hex_seed(&earlier).expect("hex seed");
+    assert_eq!(earlier_link(&later, &earlier_hash), Ok(earlier.clone()));
+    assert_eq!(earlier_link(&later, &earlier), Ok(later.clone()));
+    assert_eq!(earlier_link(&secret.to_uppercase(), &earlier_hash), Ok(earlier));
+}
+
+#[test]
+fn test_links_must_be_hex_encoded() {
+    let hash = hash_hex_seed(&"11".repeat(32)).expect("hex seed");
+    assert_eq!(earlier_link(&"zz".repeat(32), &hash), Err("Seed must be hex-encoded".to_string()));
+}
@@ -1,14 +1,18 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
+
+use std::collections::BTreeMap;
 
 use linera_sdk::{
-    linera_base_types::{WithContractAbi,AccountOwner,Amount,Account},
+    linera_base_types::{WithContractAbi,AccountOwner,Amount,Account,ApplicationId,ChainId,StreamName,TimeDelta},
     views::{RootView, View},
     Contract, ContractRuntime,
 };
-use std::str::FromStr;
-use pulse_token::{Operation,Message};
+use pulse_token::{FaucetConfig,Hold,InstantiationArgument,LedgerEntry,LedgerKind,Operation,Message,TokenError,TokenEvent,TokenMetadata,TransferEntry,CreditEntry,StakingConfig,Unbonding,Epoch,TOKEN_EVENT_STREAM};
 
 use self::state::PulseTokenState;
 
@@ -25,9 +29,9 @@ impl WithContractAbi for PulseTokenContract {
 
 impl Contract for PulseTokenContract {
     type Message = Message;
-    type Parameters = ();
-    type InstantiationArgument = ();
-    type EventValue = ();
+    type Parameters = TokenMetadata;
+    type InstantiationArgument = InstantiationArgument;
+    type EventValue = TokenEvent;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = PulseTokenState::load(runtime.root_view_storage_context())
@@ -37,39 +41,393 @@ impl Contract for PulseTokenContract {
     }
 
     async fn instantiate(&mut self, argument: Self::InstantiationArgument) {
-        let amount:Amount= Amount::from_str("1_000_000").unwrap();
-        if let Some(owner)=self.runtime.authenticated_signer(){
-            self.state.initialize_accounts(owner,amount).await;
-        }
-       
+        // Validate that the application parameters were configured correctly.
+        self.runtime.application_parameters();
+        self.state.admin.set(Some(argument.admin));
+        self.state.minter.set(Some(argument.minter));
+        self.state.treasury.set(Some(argument.treasury));
+        self.state.max_supply.set(argument.max_supply);
+        self.state.faucet.set(Some(FaucetConfig {
+            amount: argument.faucet_amount,
+            cooldown_secs: argument.faucet_cooldown_secs,
+        }));
+        self.state.staking.set(Some(StakingConfig {
+            epoch_secs: argument.staking_epoch_secs,
+            unstake_cooldown_secs: argument.unstake_cooldown_secs,
+        }));
+        self.state.epoch_started_at.set(self.runtime.system_time());
+        self.state.issue(argument.initial_supply).expect("Initial supply exceeds the max supply");
+        self.credit(argument.admin, argument.initial_supply, LedgerKind::Mint, None).await;
+        let target_account = Account { chain_id: self.runtime.chain_id(), owner: argument.admin };
+        self.emit(TokenEvent::Minted { target_account, amount: argument.initial_supply });
     }
 
     async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
         match operation {
-           Operation::Transfer { owner, amount, target_account } => {
-            self.check_account_authentication(owner);
-            self.state.debit(owner,amount).await;
-            self.finish_transfer_to_account(amount,target_account).await;
-        }
-        // Handle Minting
-            Operation::Mint { owner, amount } => {
-                self.state.credit(owner, amount).await;
+            Operation::Transfer { owner, amount, target_account } => {
+                self.check_account_authentication(owner)?;
+                self.debit(owner, amount, LedgerKind::TransferOut, Some(target_account.owner)).await?;
+                self.finish_transfer_to_account(amount, target_account, owner, LedgerKind::TransferIn).await;
+            }
+            Operation::BatchTransfer { owner, transfers } => {
+                self.check_account_authentication(owner)?;
+                let total = sum_amounts(transfers.iter().map(|transfer| transfer.amount))?;
+                self.state.ensure_balance(&owner, total).await?;
+                self.finish_batch_transfer(owner, transfers).await?;
+            }
+            Operation::Claim { source_account, amount, target_account } => {
+                self.check_account_authentication(source_account.owner)?;
+                if source_account.chain_id == self.runtime.chain_id() {
+                    self.debit(source_account.owner, amount, LedgerKind::TransferOut, Some(target_account.owner)).await?;
+                    self.finish_transfer_to_account(amount, target_account, source_account.owner, LedgerKind::TransferIn).await;
+                } else {
+                    let message = Message::Withdraw {
+                        owner: source_account.owner,
+                        amount,
+                        target_account,
+                    };
+                    self.runtime
+                        .prepare_message(message)
+                        .with_authentication()
+                        .send_to(source_account.chain_id);
+                }
+            }
+            Operation::Approve { owner, spender, allowance } => {
+                self.check_account_authentication(owner)?;
+                self.state.approve(owner, spender, allowance);
             }
-        // Handle GameDebit (called by Mines contract for betting)
+            Operation::TransferFrom { owner, spender, amount, target_account } => {
+                if self.spender()? != spender {
+                    return Err(TokenError::Unauthorized);
+                }
+                self.state.ensure_balance(&owner, amount).await?;
+                self.state.spend_allowance(owner, spender, amount).await?;
+                self.debit(owner, amount, LedgerKind::TransferOut, Some(target_account.owner)).await?;
+                self.finish_transfer_to_account(amount, target_account, owner, LedgerKind::TransferIn).await;
+            }
+        // Handle Minting (minter only, on the creator chain)
+            Operation::Mint { target_account, amount } => {
+                let minter = self.check_minter_authentication()?;
+                self.state.issue(amount)?;
+                self.finish_transfer_to_account(amount, target_account, minter, LedgerKind::Mint).await;
+            }
+            Operation::Faucet { owner } => {
+                self.check_account_authentication(owner)?;
+                let creator_chain_id = self.runtime.application_creator_chain_id();
+                if creator_chain_id == self.runtime.chain_id() {
+                    self.process_faucet_claim(owner, creator_chain_id).await?;
+                } else {
+                    self.runtime
+                        .prepare_message(Message::FaucetRequest { owner })
+                        .with_authentication()
+                        .send_to(creator_chain_id);
+                }
+            }
+        // Handle GameDebit (called by an authorized game for betting)
             Operation::GameDebit { owner, amount } => {
-                self.state.debit(owner, amount).await;
+                let game_id = self.check_game_authentication().await?;
+                let now = self.runtime.system_time();
+                self.state.ensure_balance(&owner, amount).await?;
+                self.state.check_stake(&owner, amount, now).await?;
+                self.check_game_debit_permission(owner, AccountOwner::from(game_id), amount).await?;
+                let treasury = *self.state.treasury.get();
+                self.debit(owner, amount, LedgerKind::GameDebit, treasury).await?;
+                self.state.record_activity(owner, now, amount, Amount::ZERO).await;
+                self.emit(TokenEvent::GameDebited { owner, game: game_id, amount });
+                self.settle_with_house(game_id, owner, amount, Amount::ZERO).await?;
             }
-        // Handle GameCredit (called by Mines contract for winnings)
+        // Handle GameCredit (called by an authorized game for winnings)
             Operation::GameCredit { owner, amount } => {
-                self.state.credit(owner, amount).await;
+                let game_id = self.check_game_authentication().await?;
+                self.settle_with_house(game_id, owner, Amount::ZERO, amount).await?;
+            }
+            Operation::BatchCredit { entries } => {
+                let game_id = self.check_game_authentication().await?;
+                let total = sum_amounts(entries.iter().map(|entry| entry.amount))?;
+                if self.is_creator_chain() {
+                    let available = self.state.balance(&self.treasury()?).await;
+                    if available < total {
+                        return Err(TokenError::InsufficientTreasury { available });
+                    }
+                }
+                for CreditEntry { owner, amount } in entries {
+                    self.settle_with_house(game_id, owner, Amount::ZERO, amount).await?;
+                }
+            }
+            Operation::Lock { owner, amount, reference } => {
+                let game_id = self.check_game_authentication().await?;
+                if self.state.hold(game_id, reference).await.is_some() {
+                    return Err(TokenError::DuplicateHold { reference });
+                }
+                let now = self.runtime.system_time();
+                self.state.ensure_balance(&owner, amount).await?;
+                self.state.check_stake(&owner, amount, now).await?;
+                self.check_game_debit_permission(owner, AccountOwner::from(game_id), amount).await?;
+                self.debit(owner, amount, LedgerKind::Lock, None).await?;
+                self.state.record_activity(owner, now, amount, Amount::ZERO).await;
+                let hold = Hold {
+                    owner,
+                    game: game_id,
+                    reference,
+                    amount,
+                    created_at: now,
+                };
+                self.state.open_hold(hold).await?;
+                self.emit(TokenEvent::Locked { owner, game: game_id, reference, amount });
+            }
+            Operation::Settle { reference, payout } => {
+                let game_id = self.check_game_authentication().await?;
+                let hold = self.state.hold(game_id, reference).await.ok_or(TokenError::UnknownHold { reference })?;
+                if self.is_creator_chain() {
+                    // The stake joins the treasury first, so it can go towards the payout.
+                    let available = self.state.balance(&self.treasury()?).await.saturating_add(hold.amount);
+                    if available < payout {
+                        return Err(TokenError::InsufficientTreasury { available });
+                    }
+                }
+                self.state.close_hold(game_id, reference).await?;
+                self.emit(TokenEvent::GameDebited { owner: hold.owner, game: game_id, amount: hold.amount });
+                self.settle_with_house(game_id, hold.owner, hold.amount, payout).await?;
+            }
+            Operation::Release { reference } => {
+                let game_id = self.check_game_authentication().await?;
+                let hold = self.state.close_hold(game_id, reference).await?;
+                self.credit(hold.owner, hold.amount, LedgerKind::Release, None).await;
+                // A released stake was never at risk, so it does not count as a loss.
+                let now = self.runtime.system_time();
+                self.state.record_activity(hold.owner, now, Amount::ZERO, hold.amount).await;
+                self.emit(TokenEvent::Released { owner: hold.owner, game: game_id, reference, amount: hold.amount });
+            }
+            Operation::AuthorizeGame { application_id } => {
+                self.check_admin_authentication()?;
+                self.state.authorized_games.insert(&application_id).expect("failed to update game registry");
+            }
+            Operation::RevokeGame { application_id } => {
+                self.check_admin_authentication()?;
+                self.state.authorized_games.remove(&application_id).expect("failed to update game registry");
+            }
+            Operation::SetMinter { minter } => {
+                self.check_admin_authentication()?;
+                self.state.minter.set(Some(minter));
+            }
+            Operation::SetTreasury { treasury } => {
+                self.check_admin_authentication()?;
+                self.state.treasury.set(Some(treasury));
+            }
+            Operation::SetGamingLimits { owner, limits } => {
+                self.check_account_authentication(owner)?;
+                self.state.gaming_limits.insert(&owner, limits).expect("failed to update gaming limits");
+            }
+            Operation::SelfExclude { owner, duration_secs } => {
+                self.check_account_authentication(owner)?;
+                let mut until = self.runtime.system_time().saturating_add(TimeDelta::from_secs(duration_secs));
+                if let Some(current) = self.state.excluded_until(&owner).await {
+                    until = until.max(current);
+                }
+                self.state.self_exclusions.insert(&owner, until).expect("failed to record self-exclusion");
+            }
+            Operation::Stake { owner, amount } => {
+                self.check_account_authentication(owner)?;
+                self.staking_config()?;
+                self.debit(owner, amount, LedgerKind::Stake, None).await?;
+                let mut account = self.state.stake_account(&owner).await;
+                account.activating.saturating_add_assign(amount);
+                account.activation_epoch = *self.state.epoch_count.get() + 1;
+                self.state.save_stake_account(owner, account);
+                self.state.activating_stake.get_mut().saturating_add_assign(amount);
+                self.emit(TokenEvent::Staked { owner, amount });
+            }
+            Operation::Unstake { owner, amount } => {
+                self.check_account_authentication(owner)?;
+                let cooldown_secs = self.staking_config()?.unstake_cooldown_secs;
+                let mut account = self.state.stake_account(&owner).await;
+                let available = account.staked.saturating_add(account.activating);
+                if amount > available {
+                    return Err(TokenError::InsufficientStake { available });
+                }
+                // Stake that is not earning yet goes first.
+                let from_activating = amount.min(account.activating);
+                let from_staked = amount.saturating_sub(from_activating);
+                account.activating = account.activating.saturating_sub(from_activating);
+                account.staked = account.staked.saturating_sub(from_staked);
+                let activating_stake = self.state.activating_stake.get().saturating_sub(from_activating);
+                self.state.activating_stake.set(activating_stake);
+                let total_staked = self.state.total_staked.get().saturating_sub(from_staked);
+                self.state.total_staked.set(total_staked);
+                let release_at = self.runtime.system_time().saturating_add(TimeDelta::from_secs(cooldown_secs));
+                account.unbonding.push(Unbonding { amount, release_at });
+                self.state.save_stake_account(owner, account);
+                self.emit(TokenEvent::Unstaked { owner, amount, release_at });
+            }
+            Operation::WithdrawUnstaked { owner } => {
+                self.check_account_authentication(owner)?;
+                let mut account = self.state.stake_account(&owner).await;
+                let now = self.runtime.system_time();
+                let (released, unbonding): (Vec<_>, Vec<_>) =
+                    account.unbonding.into_iter().partition(|unbonding| unbonding.release_at <= now);
+                account.unbonding = unbonding;
+                self.state.save_stake_account(owner, account);
+                let amount = released
+                    .iter()
+                    .fold(Amount::ZERO, |total, unbonding| total.saturating_add(unbonding.amount));
+                if amount > Amount::ZERO {
+                    self.credit(owner, amount, LedgerKind::Unstake, None).await;
+                }
+            }
+            Operation::ClaimRewards { owner } => {
+                self.check_account_authentication(owner)?;
+                let mut account = self.state.stake_account(&owner).await;
+                let amount = account.rewards;
+                account.rewards = Amount::ZERO;
+                self.state.save_stake_account(owner, account);
+                if amount > Amount::ZERO {
+                    let reward_pool = self.state.reward_pool.get().saturating_sub(amount);
+                    self.state.reward_pool.set(reward_pool);
+                    self.credit(owner, amount, LedgerKind::StakingReward, None).await;
+                    self.emit(TokenEvent::RewardsClaimed { owner, amount });
+                }
+            }
+            Operation::CloseEpoch => {
+                self.close_epoch().await?;
+            }
+            Operation::FundTreasury { owner, amount } => {
+                self.check_account_authentication(owner)?;
+                let treasury = Account { chain_id: self.runtime.application_creator_chain_id(), owner: self.treasury()? };
+                self.debit(owner, amount, LedgerKind::TransferOut, Some(treasury.owner)).await?;
+                self.finish_transfer_to_account(amount, treasury, owner, LedgerKind::TransferIn).await;
+            }
+            Operation::WithdrawTreasury { amount, target_account } => {
+                self.check_admin_authentication()?;
+                let treasury = self.treasury()?;
+                self.debit(treasury, amount, LedgerKind::TransferOut, Some(target_account.owner)).await?;
+                self.finish_transfer_to_account(amount, target_account, treasury, LedgerKind::TransferIn).await;
+            }
+            Operation::SyncRegistry => {
+                let creator_chain_id = self.runtime.application_creator_chain_id();
+                if creator_chain_id != self.runtime.chain_id() {
+                    self.runtime.send_message(creator_chain_id, Message::RegistryRequest);
+                }
+            }
+            Operation::ClaimWinnings { owner } => {
+                self.check_account_authentication(owner)?;
+                let creator_chain_id = self.runtime.application_creator_chain_id();
+                if creator_chain_id == self.runtime.chain_id() {
+                    self.process_winnings_claim(Account { chain_id: creator_chain_id, owner }).await?;
+                } else {
+                    self.runtime
+                        .prepare_message(Message::WinningsRequest { owner })
+                        .with_authentication()
+                        .send_to(creator_chain_id);
+                }
             }
         }
+        Ok(())
     }
 
     async fn execute_message(&mut self, _message: Self::Message) {
         match _message{
-            Message::Credit{amount, owner}=>{
-                self.state.credit(owner,amount).await;
+            Message::Credit{amount, owner, source, kind}=>{
+                let is_bouncing = self
+                    .runtime
+                    .message_is_bouncing()
+                    .expect("Delivery status is available when executing a message");
+                if is_bouncing {
+                    self.credit(source, amount, LedgerKind::Refund, Some(owner)).await;
+                    self.emit(TokenEvent::Refunded { owner: source, amount });
+                } else {
+                    self.credit(owner, amount, kind, Some(source)).await;
+                    if kind == LedgerKind::GameCredit {
+                        // Winnings paid by the treasury on the creator chain count towards the loss limits here
+                        let now = self.runtime.system_time();
+                        self.state.record_activity(owner, now, Amount::ZERO, amount).await;
+                    }
+                    self.emit(TokenEvent::Received { owner, source, amount });
+                }
+            }
+            // Messages have no caller to hand an error back to, so they still reject the block.
+            Message::BatchCredit { credits, source } => {
+                let is_bouncing = self
+                    .runtime
+                    .message_is_bouncing()
+                    .expect("Delivery status is available when executing a message");
+                if is_bouncing {
+                    let amount = credits
+                        .iter()
+                        .fold(Amount::ZERO, |total, (_, amount)| total.saturating_add(*amount));
+                    self.credit(source, amount, LedgerKind::Refund, None).await;
+                    self.emit(TokenEvent::Refunded { owner: source, amount });
+                } else {
+                    for (owner, amount) in credits {
+                        self.credit(owner, amount, LedgerKind::TransferIn, Some(source)).await;
+                        self.emit(TokenEvent::Received { owner, source, amount });
+                    }
+                }
+            }
+            Message::Withdraw { owner, amount, target_account } => {
+                self.check_account_authentication(owner).unwrap_or_else(|error| panic!("Withdraw rejected: {error}"));
+                self.debit(owner, amount, LedgerKind::TransferOut, Some(target_account.owner))
+                    .await
+                    .unwrap_or_else(|error| panic!("Withdraw rejected: {error}"));
+                self.finish_transfer_to_account(amount, target_account, owner, LedgerKind::TransferIn).await;
+            }
+            Message::FaucetRequest { owner } => {
+                self.check_account_authentication(owner).unwrap_or_else(|error| panic!("Faucet claim rejected: {error}"));
+                let origin = self.runtime.message_origin_chain_id().expect("Incoming message origin chain ID has to be available when executing a message");
+                self.process_faucet_claim(owner, origin)
+                    .await
+                    .unwrap_or_else(|error| panic!("Faucet claim rejected: {error}"));
+            }
+            Message::RegistryRequest => {
+                let origin = self.runtime.message_origin_chain_id().expect("Incoming message origin chain ID has to be available when executing a message");
+                let admin = self.state.admin.get().expect("Token admin is not set on the creator chain");
+                let treasury = self.treasury().unwrap_or_else(|error| panic!("Registry request rejected: {error}"));
+                let games = self.state.game_registry().await;
+                self.runtime.send_message(origin, Message::RegistrySync { admin, treasury, games });
+            }
+            Message::RegistrySync { admin, treasury, games } => {
+                let origin = self.runtime.message_origin_chain_id();
+                assert_eq!(
+                    origin,
+                    Some(self.runtime.application_creator_chain_id()),
+                    "Registry can only be synced from the creator chain"
+                );
+                self.state.replace_registry(admin, treasury, games).await;
+            }
+            Message::HouseRound { game, owner, stake, payout } => {
+                let is_bouncing = self
+                    .runtime
+                    .message_is_bouncing()
+                    .expect("Delivery status is available when executing a message");
+                if is_bouncing {
+                    // The creator chain never took the stake, so it goes back to the owner.
+                    if stake > Amount::ZERO {
+                        self.credit(owner, stake, LedgerKind::Refund, None).await;
+                        self.emit(TokenEvent::Refunded { owner, amount: stake });
+                    }
+                    return;
+                }
+                let origin = self.runtime.message_origin_chain_id().expect("Incoming message origin chain ID has to be available when executing a message");
+                let treasury = self.treasury().unwrap_or_else(|error| panic!("House round rejected: {error}"));
+                if stake > Amount::ZERO {
+                    self.credit(treasury, stake, LedgerKind::GameDebit, Some(owner)).await;
+                    self.state.record_wager(game, stake).await;
+                }
+                if payout > Amount::ZERO {
+                    let account = Account { chain_id: origin, owner };
+                    // The round is over on the sending chain, so a short treasury owes the winnings.
+                    if self.pay_from_treasury(game, account, payout).await.is_err() {
+                        self.state.record_payout(game, payout).await;
+                        self.state.owe_winnings(account, payout).await;
+                        self.emit(TokenEvent::WinningsOwed { account, game, amount: payout });
+                    }
+                }
+            }
+            Message::WinningsRequest { owner } => {
+                self.check_account_authentication(owner).unwrap_or_else(|error| panic!("Winnings claim rejected: {error}"));
+                let origin = self.runtime.message_origin_chain_id().expect("Incoming message origin chain ID has to be available when executing a message");
+                self.process_winnings_claim(Account { chain_id: origin, owner })
+                    .await
+                    .unwrap_or_else(|error| panic!("Winnings claim rejected: {error}"));
             }
         }
     }
@@ -80,21 +438,289 @@ impl Contract for PulseTokenContract {
 }
 
 impl PulseTokenContract{
-    fn check_account_authentication(&mut self , owner:AccountOwner){
-        assert_eq!(
-            self.runtime.authenticated_signer(),Some(owner),"Incorrect authentication"
-        )
+    fn check_account_authentication(&mut self , owner:AccountOwner)->Result<(),TokenError>{
+        if self.runtime.authenticated_signer() != Some(owner) {
+            return Err(TokenError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Only the admin, signing on the creator chain, can run admin operations.
+    fn check_admin_authentication(&mut self) -> Result<(), TokenError> {
+        let admin = *self.state.admin.get();
+        if self.runtime.chain_id() != self.runtime.application_creator_chain_id()
+            || admin.is_none()
+            || self.runtime.authenticated_signer() != admin
+        {
+            return Err(TokenError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Minting is only possible on the creator chain, where the total supply is tracked.
+    fn check_minter_authentication(&mut self) -> Result<AccountOwner, TokenError> {
+        let minter = *self.state.minter.get();
+        if self.runtime.chain_id() != self.runtime.application_creator_chain_id()
+            || minter.is_none()
+            || self.runtime.authenticated_signer() != minter
+        {
+            return Err(TokenError::Unauthorized);
+        }
+        Ok(minter.expect("minter was checked above"))
+    }
+
+    /// Pays one faucet drip to `owner` on `chain_id`, once per cooldown period per owner.
+    async fn process_faucet_claim(&mut self, owner: AccountOwner, chain_id: ChainId) -> Result<(), TokenError> {
+        let faucet = self.state.faucet.get().clone().expect("Faucet is not configured");
+        let now = self.runtime.system_time();
+        if let Some(last_claim) = self.state.faucet_claims.get(&owner).await.expect("failed to read faucet claims") {
+            let next_claim = last_claim.saturating_add(TimeDelta::from_secs(faucet.cooldown_secs));
+            if now < next_claim {
+                return Err(TokenError::FaucetCooldown { next_claim });
+            }
+        }
+        self.state.issue(faucet.amount)?;
+        self.state.faucet_claims.insert(&owner, now).expect("failed to record faucet claim");
+        self.finish_transfer_to_account(faucet.amount, Account { chain_id, owner }, owner, LedgerKind::Mint).await;
+        Ok(())
+    }
+
+    /// Game operations must come from a registered game application, never from a user directly.
+    async fn check_game_authentication(&mut self) -> Result<ApplicationId, TokenError> {
+        let caller_id: ApplicationId = self
+            .runtime
+            .authenticated_caller_id()
+            .ok_or(TokenError::Unauthorized)?;
+        if !self.state.is_authorized_game(&caller_id).await {
+            return Err(TokenError::Unauthorized);
+        }
+        Ok(caller_id)
+    }
+
+    /// A game takes from `owner` within the allowance `owner` gave it or, when there is none,
+    /// only if `owner` signed the block themselves. The signature alone suffices because the
+    /// signer is forwarded to the applications the block calls, so the game could `Transfer` the
+    /// same tokens; see `Operation::GameDebit`.
+    async fn check_game_debit_permission(&mut self, owner: AccountOwner, game: AccountOwner, amount: Amount) -> Result<(), TokenError> {
+        if self.state.allowance(owner, game).await.is_some() {
+            self.state.spend_allowance(owner, game, amount).await
+        } else {
+            self.check_account_authentication(owner)
+        }
+    }
+
+    /// The calling application if there is one, otherwise the signer of the block.
+    fn spender(&mut self) -> Result<AccountOwner, TokenError> {
+        match self.runtime.authenticated_caller_id() {
+            Some(application_id) => Ok(AccountOwner::from(application_id)),
+            None => self.runtime.authenticated_signer().ok_or(TokenError::Unauthorized),
+        }
+    }
+
+    fn is_creator_chain(&mut self) -> bool {
+        self.runtime.chain_id() == self.runtime.application_creator_chain_id()
+    }
+
+    /// Moves a settled stake of `owner` into the treasury and pays them `payout` from it. The
+    /// treasury lives on the creator chain, so elsewhere both legs go there in one message and
+    /// the winnings come back as a credit. The caller checks that the creator chain's treasury
+    /// can pay.
+    async fn settle_with_house(&mut self, game_id: ApplicationId, owner: AccountOwner, stake: Amount, payout: Amount) -> Result<(), TokenError> {
+        let creator_chain_id = self.runtime.application_creator_chain_id();
+        if creator_chain_id != self.runtime.chain_id() {
+            let message = Message::HouseRound { game: game_id, owner, stake, payout };
+            self.runtime.prepare_message(message).with_tracking().send_to(creator_chain_id);
+            return Ok(());
+        }
+        if stake > Amount::ZERO {
+            let treasury = self.treasury()?;
+            self.credit(treasury, stake, LedgerKind::GameDebit, Some(owner)).await;
+            self.state.record_wager(game_id, stake).await;
+        }
+        if payout > Amount::ZERO {
+            self.pay_from_treasury(game_id, Account { chain_id: creator_chain_id, owner }, payout).await?;
+        }
+        Ok(())
+    }
+
+    /// Pays `amount` of winnings for `game_id` to `account`, refusing to overdraw the treasury.
+    async fn pay_from_treasury(&mut self, game_id: ApplicationId, account: Account, amount: Amount) -> Result<(), TokenError> {
+        let treasury = self.treasury()?;
+        let available = self.state.balance(&treasury).await;
+        if available < amount {
+            return Err(TokenError::InsufficientTreasury { available });
+        }
+        self.transfer_winnings(treasury, account, amount).await?;
+        self.state.record_payout(game_id, amount).await;
+        self.emit(TokenEvent::GameCredited { owner: account.owner, game: game_id, amount });
+        Ok(())
+    }
+
+    /// Moves winnings out of the treasury. Winnings credited here count towards the owner's
+    /// loss limits at once; those sent to another chain count when they arrive.
+    async fn transfer_winnings(&mut self, treasury: AccountOwner, account: Account, amount: Amount) -> Result<(), TokenError> {
+        self.debit(treasury, amount, LedgerKind::GameCredit, Some(account.owner)).await?;
+        if account.chain_id == self.runtime.chain_id() {
+            self.credit(account.owner, amount, LedgerKind::GameCredit, Some(treasury)).await;
+            let now = self.runtime.system_time();
+            self.state.record_activity(account.owner, now, Amount::ZERO, amount).await;
+        } else {
+            self.finish_transfer_to_account(amount, account, treasury, LedgerKind::GameCredit).await;
+        }
+        Ok(())
+    }
+
+    /// Pays everything owed to `account` once the treasury can cover all of it.
+    async fn process_winnings_claim(&mut self, account: Account) -> Result<(), TokenError> {
+        let treasury = self.treasury()?;
+        let owed = self.state.owed_winnings(&account).await;
+        if owed == Amount::ZERO {
+            return Ok(());
+        }
+        let available = self.state.balance(&treasury).await;
+        if available < owed {
+            return Err(TokenError::InsufficientTreasury { available });
+        }
+        self.state.take_owed_winnings(&account).await;
+        self.transfer_winnings(treasury, account, owed).await?;
+        self.emit(TokenEvent::OwedWinningsPaid { account, amount: owed });
+        Ok(())
     }
 
-    async fn finish_transfer_to_account(&mut self, amount: Amount, account: Account){
+    /// Closes the current epoch, moving the house profit since the last distribution from the
+    /// treasury into the reward pool. Profit is kept by the house when nobody was staking.
+    async fn close_epoch(&mut self) -> Result<(), TokenError> {
+        let epoch_secs = self.staking_config()?.epoch_secs;
+        let now = self.runtime.system_time();
+        let started_at = *self.state.epoch_started_at.get();
+        let ends_at = started_at.saturating_add(TimeDelta::from_secs(epoch_secs));
+        if now < ends_at {
+            return Err(TokenError::EpochNotOver { ends_at });
+        }
+        let total_staked = *self.state.total_staked.get();
+        let mut profit = Amount::ZERO;
+        if let Some(undistributed) = self.state.undistributed_profit() {
+            if total_staked > Amount::ZERO {
+                let treasury = self.treasury()?;
+                // Owed winnings are still the players'
+                let solvent = self.state.balance(&treasury).await.saturating_sub(*self.state.owed_total.get());
+                profit = undistributed.min(solvent);
+                if profit > Amount::ZERO {
+                    self.debit(treasury, profit, LedgerKind::StakingReward, None).await?;
+                    self.state.reward_pool.get_mut().saturating_add_assign(profit);
+                }
+            }
+            let totals = self.state.house_totals.get().clone();
+            self.state.profit_mark.set(totals);
+        }
+        let index = *self.state.epoch_count.get();
+        let epoch = Epoch { index, profit, total_staked, started_at, closed_at: now };
+        self.state.epochs.insert(&index, epoch).expect("failed to record epoch");
+        self.state.epoch_count.set(index + 1);
+        self.state.epoch_started_at.set(now);
+        let activating_stake = *self.state.activating_stake.get();
+        self.state.total_staked.set(total_staked.saturating_add(activating_stake));
+        self.state.activating_stake.set(Amount::ZERO);
+        self.emit(TokenEvent::EpochClosed { epoch: index, profit, total_staked });
+        Ok(())
+    }
+
+    /// Only `instantiate` configures staking, so other chains have none.
+    fn staking_config(&self) -> Result<StakingConfig, TokenError> {
+        self.state.staking.get().clone().ok_or(TokenError::StakingUnavailable)
+    }
+
+    /// `instantiate` sets the treasury on the creator chain; other chains learn it from `SyncRegistry`.
+    fn treasury(&self) -> Result<AccountOwner, TokenError> {
+        self.state.treasury.get().ok_or(TokenError::RegistryNotSynced)
+    }
+
+    fn emit(&mut self, event: TokenEvent) {
+        self.runtime.emit(StreamName::from(TOKEN_EVENT_STREAM), &event);
+    }
+
+    /// Credits `owner` and appends the change to their ledger.
+    async fn credit(&mut self, owner: AccountOwner, amount: Amount, kind: LedgerKind, counterparty: Option<AccountOwner>) {
+        let balance = self.state.credit(owner, amount).await;
+        self.record_ledger_entry(owner, kind, amount, balance, counterparty).await;
+    }
+
+    /// Debits `owner` and appends the change to their ledger.
+    async fn debit(&mut self, owner: AccountOwner, amount: Amount, kind: LedgerKind, counterparty: Option<AccountOwner>) -> Result<(), TokenError> {
+        let balance = self.state.debit(owner, amount).await?;
+        self.record_ledger_entry(owner, kind, amount, balance, counterparty).await;
+        Ok(())
+    }
+
+    async fn record_ledger_entry(
+        &mut self,
+        owner: AccountOwner,
+        kind: LedgerKind,
+        amount: Amount,
+        balance: Amount,
+        counterparty: Option<AccountOwner>,
+    ) {
+        let entry = LedgerEntry {
+            index: 0,
+            kind,
+            amount,
+            balance,
+            counterparty,
+            application_id: self.runtime.authenticated_caller_id(),
+            timestamp: self.runtime.system_time(),
+        };
+        self.state.push_ledger_entry(owner, entry).await;
+    }
+
+    /// Debits `source` for every transfer and credits the targets, sending a single tracked
+    /// message per remote chain. The caller checks that `source` can cover the total.
+    async fn finish_batch_transfer(&mut self, source: AccountOwner, transfers: Vec<TransferEntry>) -> Result<(), TokenError> {
+        let chain_id = self.runtime.chain_id();
+        let mut remote_credits: BTreeMap<ChainId, Vec<(AccountOwner, Amount)>> = BTreeMap::new();
+        for TransferEntry { target_account, amount } in transfers {
+            self.debit(source, amount, LedgerKind::TransferOut, Some(target_account.owner)).await?;
+            self.emit(TokenEvent::Transferred { source, target_account, amount });
+            if target_account.chain_id == chain_id {
+                self.credit(target_account.owner, amount, LedgerKind::TransferIn, Some(source)).await;
+            } else {
+                remote_credits
+                    .entry(target_account.chain_id)
+                    .or_default()
+                    .push((target_account.owner, amount));
+            }
+        }
+        for (chain_id, credits) in remote_credits {
+            self.runtime
+                .prepare_message(Message::BatchCredit { credits, source })
+                .with_authentication()
+                .with_tracking()
+                .send_to(chain_id);
+        }
+        Ok(())
+    }
+
+    /// Credits `account`, sending a tracked message when it lives on another chain so that
+    /// a rejected credit bounces back to `source` instead of being lost.
+    async fn finish_transfer_to_account(&mut self, amount: Amount, account: Account, source: AccountOwner, kind: LedgerKind){
+        let event = match kind {
+            LedgerKind::Mint => TokenEvent::Minted { target_account: account, amount },
+            _ => TokenEvent::Transferred { source, target_account: account, amount },
+        };
+        self.emit(event);
         if account.chain_id==self.runtime.chain_id(){
-            self.state.credit(account.owner,amount).await;
+            self.credit(account.owner, amount, kind, Some(source)).await;
         }else{
             let message=Message::Credit{
                 owner:account.owner,
-                amount
+                amount,
+                source,
+                kind,
             };
-            self.runtime.prepare_message(message).with_authentication().send_to(account.chain_id);
+            self.runtime.prepare_message(message).with_authentication().with_tracking().send_to(account.chain_id);
         }
     }
 }
+
+fn sum_amounts(amounts: impl Iterator<Item = Amount>) -> Result<Amount, TokenError> {
+    amounts.try_fold(Amount::ZERO, |total, amount| total.try_add(amount).map_err(|_| TokenError::Overflow))
+}
@@ -1,7 +1,7 @@
-use async_graphql::{Request, Response};
+use async_graphql::{ComplexObject, Enum, InputObject, Request, Response, SimpleObject};
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{Account, AccountOwner, Amount, ContractAbi, ServiceAbi},
+    linera_base_types::{Account, AccountOwner, Amount, ApplicationId, ContractAbi, ServiceAbi, Timestamp},
 };
 use serde::{Deserialize, Serialize};
 
@@ -9,7 +9,7 @@ pub struct PulseTokenAbi;
 
 impl ContractAbi for PulseTokenAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = Result<(), TokenError>;
 }
 
 impl ServiceAbi for PulseTokenAbi {
@@ -17,6 +17,126 @@ impl ServiceAbi for PulseTokenAbi {
     type QueryResponse = Response;
 }
 
+/// Why pulse_token refused an operation. Every check runs before any state is touched, so an
+/// error leaves balances, allowances and holds exactly as they were.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum TokenError {
+    /// The account holds less than the operation needs
+    InsufficientBalance { available: Amount },
+    /// The caller's allowance is smaller than the amount taken
+    InsufficientAllowance { available: Amount },
+    /// The house treasury cannot cover a payout
+    InsufficientTreasury { available: Amount },
+    /// The signer or calling application is not allowed to perform the operation
+    Unauthorized,
+    /// An amount went out of range, or minting would exceed the max supply
+    Overflow,
+    /// The owner claimed from the faucet too recently
+    FaucetCooldown { next_claim: Timestamp },
+    /// The calling game has no open hold with this reference
+    UnknownHold { reference: u64 },
+    /// The calling game already has an open hold with this reference
+    DuplicateHold { reference: u64 },
+    /// The owner excluded themselves from play until `until`
+    SelfExcluded { until: Timestamp },
+    /// The stake would take the owner over one of their wager limits
+    WagerLimitExceeded { remaining: Amount },
+    /// Losing the stake would take the owner over one of their loss limits
+    LossLimitExceeded { remaining: Amount },
+    /// The owner has less staked than they tried to unstake
+    InsufficientStake { available: Amount },
+    /// The current epoch cannot be closed before `ends_at`
+    EpochNotOver { ends_at: Timestamp },
+    /// Staking runs on the creator chain only, where the treasury earns the profit it shares
+    StakingUnavailable,
+    /// This chain does not know the treasury until it syncs the registry from the creator chain
+    RegistryNotSynced,
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenError::InsufficientBalance { available } => write!(f, "insufficient balance, {available} available"),
+            TokenError::InsufficientAllowance { available } => write!(f, "amount exceeds allowance of {available}"),
+            TokenError::InsufficientTreasury { available } => {
+                write!(f, "house treasury cannot cover this payout, {available} available")
+            }
+            TokenError::Unauthorized => write!(f, "not authorized"),
+            TokenError::Overflow => write!(f, "amount out of range or over the max supply"),
+            TokenError::FaucetCooldown { next_claim } => {
+                write!(f, "faucet cooldown has not elapsed, next claim at {next_claim}")
+            }
+            TokenError::UnknownHold { reference } => write!(f, "no open hold with reference {reference}"),
+            TokenError::DuplicateHold { reference } => write!(f, "a hold with reference {reference} is already open"),
+            TokenError::SelfExcluded { until } => write!(f, "self-excluded from play until {until}"),
+            TokenError::WagerLimitExceeded { remaining } => {
+                write!(f, "stake exceeds the wager limit, {remaining} left")
+            }
+            TokenError::LossLimitExceeded { remaining } => {
+                write!(f, "stake could exceed the loss limit, {remaining} left")
+            }
+            TokenError::InsufficientStake { available } => write!(f, "only {available} is staked"),
+            TokenError::EpochNotOver { ends_at } => write!(f, "the epoch cannot be closed before {ends_at}"),
+            TokenError::StakingUnavailable => write!(f, "staking is only available on the creator chain"),
+            TokenError::RegistryNotSynced => write!(f, "the registry has not been synced from the creator chain"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// Immutable description of the token, passed as the application parameters
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+#[graphql(complex)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub ticker_symbol: String,
+}
+
+#[ComplexObject]
+impl TokenMetadata {
+    /// Balances are `Amount`s, which always have the same number of decimals
+    async fn decimals(&self) -> u8 {
+        Amount::DECIMAL_PLACES
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InstantiationArgument {
+    /// Owner allowed to manage the registry of game applications
+    pub admin: AccountOwner,
+    /// Owner allowed to mint new tokens on the creator chain
+    pub minter: AccountOwner,
+    /// House account that collects stakes and pays out winnings
+    pub treasury: AccountOwner,
+    /// Tokens granted to the admin when the application is created
+    pub initial_supply: Amount,
+    /// Hard cap on the tokens ever minted, including faucet drips
+    pub max_supply: Amount,
+    /// Tokens handed out by a single faucet claim
+    pub faucet_amount: Amount,
+    /// Seconds an owner has to wait between two faucet claims
+    pub faucet_cooldown_secs: u64,
+    /// Minimum length of a staking epoch, at the end of which house profit is shared
+    pub staking_epoch_secs: u64,
+    /// Seconds unstaked tokens stay locked before they can be withdrawn
+    pub unstake_cooldown_secs: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FaucetConfig {
+    pub amount: Amount,
+    pub cooldown_secs: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct StakingConfig {
+    pub epoch_secs: u64,
+    pub unstake_cooldown_secs: u64,
+}
+
+/// `Transfer`, `Claim`, `Approve` and `TransferFrom` have the same shape as in the reference
+/// fungible application, so the mutations generic Linera wallets send work unchanged.
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
     Transfer {
@@ -24,23 +144,460 @@ pub enum Operation {
         amount: Amount,
         target_account: Account,
     },
-    Mint {
+    /// Sends several transfers out of `owner`'s account at once, with one message per target chain
+    BatchTransfer {
+        owner: AccountOwner,
+        transfers: Vec<TransferEntry>,
+    },
+    /// Pulls tokens from the signer's account on `source_account`'s chain to `target_account`
+    Claim {
+        source_account: Account,
+        amount: Amount,
+        target_account: Account,
+    },
+    /// Lets `spender` (a user or an application) take up to `allowance` from `owner`
+    Approve {
         owner: AccountOwner,
+        spender: AccountOwner,
+        allowance: Amount,
+    },
+    /// Moves tokens out of `owner`'s account within `spender`'s allowance. `spender` must be
+    /// the calling application or, without one, the signer of the block.
+    TransferFrom {
+        owner: AccountOwner,
+        spender: AccountOwner,
+        amount: Amount,
+        target_account: Account,
+    },
+    /// Mints new tokens (minter only, on the creator chain, capped by the max supply)
+    Mint {
+        target_account: Account,
         amount: Amount,
     },
-    /// Called by Mines contract to debit tokens for betting
+    /// Claims the fixed faucet drip, at most once per cooldown period
+    Faucet {
+        owner: AccountOwner,
+    },
+    /// Called by an authorized game application to move a stake into the house treasury.
+    ///
+    /// The treasury is an account on the creator chain. Elsewhere the stake leaves the owner's
+    /// account here and reaches the treasury by message, and winnings come back the same way.
+    ///
+    /// If `owner` approved the game (`Approve` with the game application as spender), the debit
+    /// spends that allowance and fails beyond it; an allowance of zero blocks the game. Without
+    /// an allowance, `owner` must have signed the block, as for their own `Transfer`. That grants
+    /// the game nothing new: a game called in a block the owner signed can already transfer
+    /// their tokens with the forwarded signature. Allowances let an owner cap a game further.
     GameDebit {
         owner: AccountOwner,
         amount: Amount,
     },
-    /// Called by Mines contract to credit winnings
+    /// Called by an authorized game application to pay winnings out of the house treasury. Off
+    /// the creator chain the winnings arrive by message, and are owed if the treasury is short.
     GameCredit {
         owner: AccountOwner,
         amount: Amount,
     },
+    /// Called by an authorized game to pay many winners from the treasury in one call
+    BatchCredit {
+        entries: Vec<CreditEntry>,
+    },
+    /// Called by an authorized game to hold `owner`'s stake under `reference` until the game ends.
+    /// Needs the same permission as `GameDebit`: the owner's allowance for the game if they set
+    /// one, their signature on the block otherwise.
+    Lock {
+        owner: AccountOwner,
+        amount: Amount,
+        reference: u64,
+    },
+    /// Called by the game that created the hold: the stake goes to the treasury and `payout`
+    /// is paid out of it
+    Settle {
+        reference: u64,
+        payout: Amount,
+    },
+    /// Called by the game that created the hold: the stake goes back to its owner
+    Release {
+        reference: u64,
+    },
+    /// Adds a game application to the registry (admin only, on the creator chain)
+    AuthorizeGame {
+        application_id: ApplicationId,
+    },
+    /// Removes a game application from the registry (admin only, on the creator chain)
+    RevokeGame {
+        application_id: ApplicationId,
+    },
+    /// Hands the minter role to another owner (admin only, on the creator chain)
+    SetMinter {
+        minter: AccountOwner,
+    },
+    /// Points the house treasury at another account (admin only, on the creator chain)
+    SetTreasury {
+        treasury: AccountOwner,
+    },
+    /// Moves `owner`'s tokens into the house treasury on the creator chain
+    FundTreasury {
+        owner: AccountOwner,
+        amount: Amount,
+    },
+    /// Caps how much games may take from `owner` over rolling windows, replacing earlier limits
+    SetGamingLimits {
+        owner: AccountOwner,
+        limits: GamingLimits,
+    },
+    /// Blocks every game from taking `owner`'s stakes for `duration_secs`. An exclusion can be
+    /// extended but never shortened.
+    SelfExclude {
+        owner: AccountOwner,
+        duration_secs: u64,
+    },
+    /// Locks `owner`'s tokens to share the house profit, starting with the next epoch. Staking
+    /// operations are refused on every chain but the creator chain.
+    Stake {
+        owner: AccountOwner,
+        amount: Amount,
+    },
+    /// Stops `amount` of `owner`'s stake from earning; it can be withdrawn after the cooldown
+    Unstake {
+        owner: AccountOwner,
+        amount: Amount,
+    },
+    /// Returns every unstaked amount of `owner` whose cooldown has elapsed
+    WithdrawUnstaked {
+        owner: AccountOwner,
+    },
+    /// Pays `owner` the rewards earned in closed epochs
+    ClaimRewards {
+        owner: AccountOwner,
+    },
+    /// Ends the current epoch and shares the treasury's net profit since the last distribution
+    /// between the stakers. Anyone can call it once the epoch has lasted long enough.
+    CloseEpoch,
+    /// Pays tokens out of the house treasury (admin only, on the creator chain)
+    WithdrawTreasury {
+        amount: Amount,
+        target_account: Account,
+    },
+    /// Pulls the current registry from the creator chain onto this chain
+    SyncRegistry,
+    /// Pays `owner` the winnings the treasury could not cover when their rounds settled, once it can
+    ClaimWinnings {
+        owner: AccountOwner,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Message {
-    Credit { owner: AccountOwner, amount: Amount },
+    /// Credits `owner`, or refunds `source` on the sending chain if the message bounces
+    Credit {
+        owner: AccountOwner,
+        amount: Amount,
+        source: AccountOwner,
+        kind: LedgerKind,
+    },
+    /// Credits every owner in `credits`, or refunds their sum to `source` if the message bounces
+    BatchCredit {
+        credits: Vec<(AccountOwner, Amount)>,
+        source: AccountOwner,
+    },
+    /// Sent to the chain holding `owner`'s tokens on behalf of a `Claim`
+    Withdraw {
+        owner: AccountOwner,
+        amount: Amount,
+        target_account: Account,
+    },
+    /// Sent to the creator chain, which enforces the cooldown and the supply cap
+    FaucetRequest { owner: AccountOwner },
+    /// Sent to the creator chain to ask for the current registry
+    RegistryRequest,
+    /// Sent by the creator chain with the admin, the treasury and the authorized games
+    RegistrySync {
+        admin: AccountOwner,
+        treasury: AccountOwner,
+        games: Vec<ApplicationId>,
+    },
+    /// Sent to the creator chain when `game` settles a round of `owner` on another chain: the
+    /// treasury takes `stake`, which already left the owner's account, and pays `payout` back to
+    /// the sending chain. Winnings the treasury cannot cover are owed until `ClaimWinnings`.
+    HouseRound {
+        game: ApplicationId,
+        owner: AccountOwner,
+        stake: Amount,
+        payout: Amount,
+    },
+    /// Sent to the creator chain on behalf of a `ClaimWinnings`
+    WinningsRequest { owner: AccountOwner },
+}
+
+/// One leg of a `BatchTransfer`
+#[derive(Clone, Debug, Deserialize, Serialize, InputObject)]
+pub struct TransferEntry {
+    pub target_account: Account,
+    pub amount: Amount,
+}
+
+/// One winner paid by a `BatchCredit`
+#[derive(Clone, Debug, Deserialize, Serialize, InputObject)]
+pub struct CreditEntry {
+    pub owner: AccountOwner,
+    pub amount: Amount,
+}
+
+/// Why an account balance changed
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Enum, PartialEq, Eq)]
+pub enum LedgerKind {
+    TransferIn,
+    TransferOut,
+    Mint,
+    GameDebit,
+    GameCredit,
+    /// A stake was put on hold by a game
+    Lock,
+    /// A held stake was returned by a game
+    Release,
+    /// A cross-chain credit was rejected and returned to the sender
+    Refund,
+    /// Tokens were locked for staking
+    Stake,
+    /// Unstaked tokens came back after their cooldown
+    Unstake,
+    /// House profit was paid to a staker, or taken from the treasury for the stakers
+    StakingReward,
+}
+
+/// One balance change in an owner's append-only ledger
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct LedgerEntry {
+    /// Position in the owner's ledger, starting at 0
+    pub index: u64,
+    pub kind: LedgerKind,
+    pub amount: Amount,
+    /// Balance right after the change
+    pub balance: Amount,
+    /// The other side of a transfer, when there is one
+    pub counterparty: Option<AccountOwner>,
+    /// The application that requested the change, e.g. the game for game debits and credits
+    pub application_id: Option<ApplicationId>,
+    pub timestamp: Timestamp,
+}
+
+/// Stream on which every balance change is emitted as a [`TokenEvent`]
+pub const TOKEN_EVENT_STREAM: &str = "pulse_token_events";
+
+/// Events emitted by the token, other chains can follow them with `subscribe_to_events`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum TokenEvent {
+    Minted {
+        target_account: Account,
+        amount: Amount,
+    },
+    Transferred {
+        source: AccountOwner,
+        target_account: Account,
+        amount: Amount,
+    },
+    /// A credit sent from another chain arrived
+    Received {
+        owner: AccountOwner,
+        source: AccountOwner,
+        amount: Amount,
+    },
+    /// A credit sent to another chain was rejected and returned
+    Refunded {
+        owner: AccountOwner,
+        amount: Amount,
+    },
+    GameDebited {
+        owner: AccountOwner,
+        game: ApplicationId,
+        amount: Amount,
+    },
+    GameCredited {
+        owner: AccountOwner,
+        game: ApplicationId,
+        amount: Amount,
+    },
+    Locked {
+        owner: AccountOwner,
+        game: ApplicationId,
+        reference: u64,
+        amount: Amount,
+    },
+    Released {
+        owner: AccountOwner,
+        game: ApplicationId,
+        reference: u64,
+        amount: Amount,
+    },
+    Staked {
+        owner: AccountOwner,
+        amount: Amount,
+    },
+    Unstaked {
+        owner: AccountOwner,
+        amount: Amount,
+        release_at: Timestamp,
+    },
+    /// An epoch ended and `profit` was set aside for the stakers
+    EpochClosed {
+        epoch: u64,
+        profit: Amount,
+        total_staked: Amount,
+    },
+    RewardsClaimed {
+        owner: AccountOwner,
+        amount: Amount,
+    },
+    /// The treasury could not cover winnings when they were due
+    WinningsOwed {
+        account: Account,
+        game: ApplicationId,
+        amount: Amount,
+    },
+    /// Owed winnings were paid
+    OwedWinningsPaid {
+        account: Account,
+        amount: Amount,
+    },
+}
+
+/// Bankroll totals of the house treasury for one game, kept on the creator chain
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct HouseStats {
+    /// Stakes moved into the treasury
+    pub wagered: Amount,
+    /// Winnings paid out of the treasury
+    pub paid_out: Amount,
+}
+
+/// Limits an owner puts on their own play. Losses are stakes minus winnings. `None` means no limit.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject, InputObject)]
+#[graphql(input_name = "GamingLimitsInput")]
+pub struct GamingLimits {
+    pub daily_wager_limit: Option<Amount>,
+    pub weekly_wager_limit: Option<Amount>,
+    pub daily_loss_limit: Option<Amount>,
+    pub weekly_loss_limit: Option<Amount>,
+}
+
+pub const HOUR_MICROS: u64 = 3_600_000_000;
+pub const DAY_HOURS: u64 = 24;
+pub const WEEK_HOURS: u64 = 7 * DAY_HOURS;
+
+/// Stakes and winnings of one owner during one hour
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ActivityBucket {
+    pub hour: u64,
+    pub wagered: Amount,
+    pub won: Amount,
+}
+
+/// An owner's play over the last week, in hourly buckets, so that rolling windows
+/// can be summed from a single stored value.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PlayerActivity {
+    pub buckets: Vec<ActivityBucket>,
+}
+
+impl PlayerActivity {
+    /// Adds to the bucket of the current hour and drops the ones older than a week.
+    pub fn record(&mut self, now: Timestamp, wagered: Amount, won: Amount) {
+        let hour = now.micros() / HOUR_MICROS;
+        self.buckets.retain(|bucket| bucket.hour + WEEK_HOURS > hour);
+        match self.buckets.last_mut() {
+            Some(bucket) if bucket.hour == hour => {
+                bucket.wagered.saturating_add_assign(wagered);
+                bucket.won.saturating_add_assign(won);
+            }
+            _ => self.buckets.push(ActivityBucket { hour, wagered, won }),
+        }
+    }
+
+    /// Totals over the last `hours` hours, counting the current one.
+    pub fn totals(&self, now: Timestamp, hours: u64) -> ActivityTotals {
+        let hour = now.micros() / HOUR_MICROS;
+        let mut totals = ActivityTotals::default();
+        for bucket in self.buckets.iter().filter(|bucket| bucket.hour + hours > hour) {
+            totals.wagered.saturating_add_assign(bucket.wagered);
+            totals.won.saturating_add_assign(bucket.won);
+        }
+        totals
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, SimpleObject)]
+#[graphql(complex)]
+pub struct ActivityTotals {
+    pub wagered: Amount,
+    pub won: Amount,
+}
+
+#[ComplexObject]
+impl ActivityTotals {
+    /// Stakes minus winnings, zero when the owner is ahead
+    async fn loss(&self) -> Amount {
+        self.wagered.saturating_sub(self.won)
+    }
+}
+
+/// Precision of a staker's share of an epoch, in parts per `SHARE_PRECISION`
+const SHARE_PRECISION: u128 = 1_000_000_000;
+
+/// `stake`'s part of `profit` when `total` is staked. The share is rounded down to
+/// `SHARE_PRECISION` so that the products stay within `u128`; the dust stays in the reward pool.
+pub fn pro_rata(profit: Amount, stake: Amount, total: Amount) -> Amount {
+    let total = u128::from(total);
+    if total == 0 {
+        return Amount::ZERO;
+    }
+    let share = u128::from(stake).saturating_mul(SHARE_PRECISION) / total;
+    let profit = u128::from(profit);
+    let reward = (profit / SHARE_PRECISION).saturating_mul(share)
+        + (profit % SHARE_PRECISION).saturating_mul(share) / SHARE_PRECISION;
+    Amount::from_attos(reward)
+}
+
+/// Unstaked tokens waiting for their cooldown
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Unbonding {
+    pub amount: Amount,
+    pub release_at: Timestamp,
+}
+
+/// One owner's staking position. Rewards are settled lazily, up to `next_epoch`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
+pub struct StakeAccount {
+    /// Stake earning a share of every epoch
+    pub staked: Amount,
+    /// Stake added during the current epoch, which starts earning in epoch `activation_epoch`
+    pub activating: Amount,
+    pub activation_epoch: u64,
+    /// Rewards earned and not claimed yet
+    pub rewards: Amount,
+    /// First closed epoch whose rewards are not in `rewards` yet
+    pub next_epoch: u64,
+    pub unbonding: Vec<Unbonding>,
+}
+
+/// A closed staking epoch
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Epoch {
+    pub index: u64,
+    /// Net house profit shared between the stakers
+    pub profit: Amount,
+    /// Stake that earned a share of `profit`
+    pub total_staked: Amount,
+    pub started_at: Timestamp,
+    pub closed_at: Timestamp,
+}
+
+/// A stake held by a game until the round is settled or released
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Hold {
+    pub owner: AccountOwner,
+    pub game: ApplicationId,
+    pub reference: u64,
+    pub amount: Amount,
+    pub created_at: Timestamp,
 }
@@ -4,15 +4,18 @@ mod state;
 
 use std::sync::Arc;
 
-use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
+use async_graphql::{EmptySubscription, Object, Request, Response, Schema, SimpleObject};
 use linera_sdk::{
     graphql::GraphQLMutationRoot, Service,
     ServiceRuntime,
-    linera_base_types::{AccountOwner, Amount, WithServiceAbi},
-    views::{MapView, View}
+    linera_base_types::{Account, AccountOwner, Amount, ApplicationId, TimeDelta, Timestamp, WithServiceAbi},
+    views::View
 };
 
-use pulse_token::Operation;
+use pulse_token::{
+    ActivityTotals, Epoch, GamingLimits, Hold, HouseStats, LedgerEntry, Operation, StakeAccount, StakingConfig, TokenMetadata,
+    DAY_HOURS, WEEK_HOURS,
+};
 
 use self::state::PulseTokenState;
 
@@ -29,7 +32,7 @@ impl WithServiceAbi for PulseTokenService {
 }
 
 impl Service for PulseTokenService {
-    type Parameters = ();
+    type Parameters = TokenMetadata;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = PulseTokenState::load(runtime.root_view_storage_context())
@@ -56,12 +59,249 @@ impl Service for PulseTokenService {
 
 #[Object]
 impl PulseTokenService {
-    async fn balance(&self, owner: String) -> Amount {
-        let account_owner: AccountOwner = owner.parse().expect("Invalid account owner format");
-        self.state.accounts
-            .get(&account_owner)
-            .await
-            .expect("Failed to read from MapView")
-            .unwrap_or_default() 
+    async fn balance(&self, owner: AccountOwner) -> Amount {
+        self.state.balance(&owner).await
+    }
+
+    async fn token_metadata(&self) -> TokenMetadata {
+        self.runtime.application_parameters()
+    }
+
+    /// Same as `tokenMetadata.tickerSymbol`, under the name the reference fungible application uses
+    async fn ticker_symbol(&self) -> String {
+        self.runtime.application_parameters().ticker_symbol
+    }
+
+    /// Balances, queried like the reference fungible application's
+    /// `accounts { entry(key: ...) { value } }`, or a page at a time with `entries`
+    async fn accounts(&self) -> Accounts<'_> {
+        Accounts { state: &self.state }
+    }
+
+    /// Tokens issued so far, tracked on the creator chain where minting happens
+    async fn total_supply(&self) -> Amount {
+        *self.state.total_supply.get()
+    }
+
+    async fn max_supply(&self) -> Amount {
+        *self.state.max_supply.get()
+    }
+
+    async fn balances(&self, owners: Vec<AccountOwner>) -> Vec<AccountBalance> {
+        let mut entries = Vec::with_capacity(owners.len());
+        for owner in owners {
+            entries.push(AccountBalance {
+                owner,
+                balance: self.state.balance(&owner).await,
+            });
+        }
+        entries
+    }
+
+    /// `owner`'s balance changes, newest first, `first` at a time, starting before the `before` index
+    async fn ledger(&self, owner: AccountOwner, first: Option<usize>, before: Option<u64>) -> Vec<LedgerEntry> {
+        let first = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE) as u64;
+        let len = self.state.ledger_len(&owner).await;
+        let end = before.map_or(len, |before| before.min(len));
+        let start = end.saturating_sub(first);
+        let mut entries = Vec::new();
+        for index in (start..end).rev() {
+            if let Some(entry) = self.state.ledger_entry(owner, index).await {
+                entries.push(entry);
+            }
+        }
+        entries
+    }
+
+    async fn ledger_length(&self, owner: AccountOwner) -> u64 {
+        self.state.ledger_len(&owner).await
+    }
+
+    /// Tokens of `owner` currently held by games
+    async fn locked_balance(&self, owner: AccountOwner) -> Amount {
+        self.state.locked_balance(&owner).await
+    }
+
+    /// Stakes of `owner` that games hold until their rounds end
+    async fn holds(&self, owner: AccountOwner) -> Vec<Hold> {
+        self.state.holds_of(&owner).await
+    }
+
+    /// What `spender` may still take from `owner`, if an allowance was set
+    async fn allowance(&self, owner: AccountOwner, spender: AccountOwner) -> Option<Amount> {
+        self.state.allowance(owner, spender).await
+    }
+
+    async fn gaming_limits(&self, owner: AccountOwner) -> GamingLimits {
+        self.state.gaming_limits(&owner).await
+    }
+
+    /// End of `owner`'s self-exclusion, if they excluded themselves
+    async fn self_excluded_until(&self, owner: AccountOwner) -> Option<Timestamp> {
+        self.state.excluded_until(&owner).await
+    }
+
+    /// What `owner` staked and won over the rolling day and week the limits apply to
+    async fn activity(&self, owner: AccountOwner) -> ActivitySummary {
+        let activity = self.state.activity(&owner).await;
+        let now = self.runtime.system_time();
+        ActivitySummary {
+            daily: activity.totals(now, DAY_HOURS),
+            weekly: activity.totals(now, WEEK_HOURS),
+        }
+    }
+
+    /// `owner`'s stake, unbonding tokens and rewards, including those of every closed epoch
+    async fn stake(&self, owner: AccountOwner) -> StakeAccount {
+        self.state.stake_account(&owner).await
+    }
+
+    async fn staking_config(&self) -> Option<StakingConfig> {
+        self.state.staking.get().clone()
+    }
+
+    /// Stake earning a share of the current epoch
+    async fn total_staked(&self) -> Amount {
+        *self.state.total_staked.get()
+    }
+
+    /// Rewards set aside for the stakers and not claimed yet
+    async fn reward_pool(&self) -> Amount {
+        *self.state.reward_pool.get()
     }
+
+    async fn current_epoch(&self) -> CurrentEpoch {
+        let started_at = *self.state.epoch_started_at.get();
+        let epoch_secs = self.state.staking.get().as_ref().map_or(0, |staking| staking.epoch_secs);
+        CurrentEpoch {
+            index: *self.state.epoch_count.get(),
+            started_at,
+            ends_at: started_at.saturating_add(TimeDelta::from_secs(epoch_secs)),
+            undistributed_profit: self.state.undistributed_profit().unwrap_or(Amount::ZERO),
+        }
+    }
+
+    /// Closed epochs, newest first, starting before the epoch `before`
+    async fn epochs(&self, first: Option<usize>, before: Option<u64>) -> Vec<Epoch> {
+        let first = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE) as u64;
+        let count = *self.state.epoch_count.get();
+        let end = before.map_or(count, |before| before.min(count));
+        let start = end.saturating_sub(first);
+        let mut epochs = Vec::new();
+        for index in (start..end).rev() {
+            if let Some(epoch) = self.state.epoch(index).await {
+                epochs.push(epoch);
+            }
+        }
+        epochs
+    }
+
+    async fn treasury(&self) -> Option<AccountOwner> {
+        *self.state.treasury.get()
+    }
+
+    async fn treasury_balance(&self) -> Amount {
+        match *self.state.treasury.get() {
+            Some(treasury) => self.state.balance(&treasury).await,
+            None => Amount::ZERO,
+        }
+    }
+
+    /// Winnings the treasury owes `account`, paid by `ClaimWinnings` once it can cover them
+    async fn owed_winnings(&self, account: Account) -> Amount {
+        self.state.owed_winnings(&account).await
+    }
+
+    /// Stakes taken and winnings paid by the treasury for `game`, on the creator chain
+    async fn house_stats(&self, game: ApplicationId) -> HouseStats {
+        self.state.house_stats(&game).await
+    }
+
+    async fn admin(&self) -> Option<AccountOwner> {
+        *self.state.admin.get()
+    }
+
+    async fn authorized_games(&self) -> Vec<ApplicationId> {
+        self.state.game_registry().await
+    }
+}
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(SimpleObject)]
+pub struct CurrentEpoch {
+    pub index: u64,
+    pub started_at: Timestamp,
+    /// Earliest time `CloseEpoch` is accepted
+    pub ends_at: Timestamp,
+    /// Profit that would be shared if the epoch closed now, before the treasury balance cap
+    pub undistributed_profit: Amount,
+}
+
+#[derive(SimpleObject)]
+pub struct ActivitySummary {
+    pub daily: ActivityTotals,
+    pub weekly: ActivityTotals,
+}
+
+#[derive(SimpleObject)]
+pub struct AccountBalance {
+    pub owner: AccountOwner,
+    pub balance: Amount,
+}
+
+pub struct Accounts<'a> {
+    state: &'a PulseTokenState,
+}
+
+#[Object]
+impl Accounts<'_> {
+    async fn entry(&self, key: AccountOwner) -> AccountEntry {
+        let value = self.state.accounts.get(&key).await.expect("Failed to read accounts");
+        AccountEntry { key, value }
+    }
+
+    /// Owners with an account on this chain
+    async fn count(&self) -> usize {
+        self.state.account_order.count()
+    }
+
+    /// Balances in the order the accounts were opened, `first` at a time, starting after the
+    /// `after` owner. Accounts are never removed, so a cursor stays valid.
+    async fn entries(&self, first: Option<usize>, after: Option<AccountOwner>) -> async_graphql::Result<AccountsPage> {
+        let first = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        let start = match after {
+            Some(owner) => {
+                let position = self.state.account_position(&owner).await;
+                position.ok_or_else(|| format!("{owner} has no account to page from"))? + 1
+            }
+            None => 0,
+        };
+        let (owners, has_next_page) = self.state.account_range(start, first).await;
+        let mut entries = Vec::with_capacity(owners.len());
+        for key in owners {
+            let value = Some(self.state.balance(&key).await);
+            entries.push(AccountEntry { key, value });
+        }
+        Ok(AccountsPage {
+            end_cursor: entries.last().map(|entry| entry.key),
+            has_next_page,
+            entries,
+        })
+    }
+}
+
+/// An entry of `accounts`, shaped like an entry of the reference application's map view
+#[derive(SimpleObject)]
+pub struct AccountEntry {
+    pub key: AccountOwner,
+    pub value: Option<Amount>,
+}
+
+#[derive(SimpleObject)]
+pub struct AccountsPage {
+    pub entries: Vec<AccountEntry>,
+    pub end_cursor: Option<AccountOwner>,
+    pub has_next_page: bool,
 }
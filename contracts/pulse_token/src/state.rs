@@ -1,32 +1,366 @@
-use linera_sdk::views::{linera_views, RegisterView,MapView,RootView, ViewStorageContext};
+use linera_sdk::views::{linera_views, CollectionView, LogView, RegisterView,MapView,RootView,SetView, ViewStorageContext};
+use pulse_token::{
+    pro_rata, Epoch, FaucetConfig, GamingLimits, Hold, HouseStats, LedgerEntry, PlayerActivity, StakeAccount, StakingConfig,
+    TokenError, DAY_HOURS, WEEK_HOURS,
+};
 use linera_sdk::{
-    linera_base_types::{AccountOwner,Amount},
+    linera_base_types::{Account,AccountOwner,Amount,ApplicationId,Timestamp},
 };
-#[derive(RootView, async_graphql::SimpleObject)]
+#[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct PulseTokenState {
     pub accounts: MapView<AccountOwner,Amount>,
+    /// Owners in the order their accounts were opened, for paging through the balances
+    pub account_order: LogView<AccountOwner>,
+    /// Position of each owner in `account_order`
+    pub account_positions: MapView<AccountOwner,usize>,
+    pub admin: RegisterView<Option<AccountOwner>>,
+    pub authorized_games: SetView<ApplicationId>,
+    pub minter: RegisterView<Option<AccountOwner>>,
+    pub total_supply: RegisterView<Amount>,
+    pub max_supply: RegisterView<Amount>,
+    pub faucet: RegisterView<Option<FaucetConfig>>,
+    pub faucet_claims: MapView<AccountOwner,Timestamp>,
+    /// Allowances keyed by (owner, spender)
+    pub allowances: MapView<(AccountOwner,AccountOwner),Amount>,
+    /// Ledger entries keyed by (owner, index)
+    pub ledger: MapView<(AccountOwner,u64),LedgerEntry>,
+    pub ledger_len: MapView<AccountOwner,u64>,
+    pub treasury: RegisterView<Option<AccountOwner>>,
+    pub house_stats: MapView<ApplicationId,HouseStats>,
+    /// Open holds keyed by (game, reference)
+    pub holds: MapView<(ApplicationId,u64),Hold>,
+    /// Keys of each owner's open holds
+    pub holds_by_owner: CollectionView<AccountOwner,SetView<(ApplicationId,u64)>>,
+    /// Total held for each owner, not part of their spendable balance
+    pub locked: MapView<AccountOwner,Amount>,
+    pub gaming_limits: MapView<AccountOwner,GamingLimits>,
+    /// End of each owner's self-exclusion
+    pub self_exclusions: MapView<AccountOwner,Timestamp>,
+    /// Stakes and winnings of the last week, for the rolling limits
+    pub activity: MapView<AccountOwner,PlayerActivity>,
+    pub staking: RegisterView<Option<StakingConfig>>,
+    pub stakes: MapView<AccountOwner,StakeAccount>,
+    /// Stake earning a share of the current epoch
+    pub total_staked: RegisterView<Amount>,
+    /// Stake added during the current epoch, earning from the next one
+    pub activating_stake: RegisterView<Amount>,
+    /// Closed epochs keyed by index
+    pub epochs: MapView<u64,Epoch>,
+    pub epoch_count: RegisterView<u64>,
+    pub epoch_started_at: RegisterView<Timestamp>,
+    /// Treasury totals over all games on this chain
+    pub house_totals: RegisterView<HouseStats>,
+    /// `house_totals` when profit was last shared with the stakers
+    pub profit_mark: RegisterView<HouseStats>,
+    /// Rewards taken from the treasury and not claimed yet
+    pub reward_pool: RegisterView<Amount>,
+    /// Winnings the treasury could not cover when they were due, by the account they go to
+    pub owed_winnings: MapView<Account,Amount>,
+    /// Sum of `owed_winnings`, which is not profit the stakers can share
+    pub owed_total: RegisterView<Amount>,
 }
 
 impl PulseTokenState{
-    pub async fn initialize_accounts(&mut self,account:AccountOwner,amount:Amount){
-        self.accounts.insert(&account,amount).expect("Error inserting")
-    }
-
     pub async fn balance(&self,account:&AccountOwner)->Amount{
         self.accounts.get(account).await.expect("failed to get balance").unwrap_or(Amount::ZERO)
 
     }
 
-    pub async fn credit(&mut self, account:AccountOwner,amount:Amount){
+    pub async fn credit(&mut self, account:AccountOwner,amount:Amount)->Amount{
         let mut balance=self.balance(&account).await;
         balance.saturating_add_assign(amount);
-        self.accounts.insert(&account,balance).expect("failed to insert");
+        self.set_balance(account, balance).await;
+        balance
     }
 
-    pub async fn debit(&mut self, account: AccountOwner, amount: Amount){
-        let mut balance=self.balance(&account).await;
-        balance.try_sub_assign(amount).expect("insufficent balance");
-        self.accounts.insert(&account,balance).expect("failed to update balance");
+    pub async fn debit(&mut self, account: AccountOwner, amount: Amount)->Result<Amount,TokenError>{
+        let available=self.balance(&account).await;
+        let balance=available.try_sub(amount).map_err(|_| TokenError::InsufficientBalance { available })?;
+        self.set_balance(account, balance).await;
+        Ok(balance)
+    }
+
+    async fn set_balance(&mut self, account: AccountOwner, balance: Amount) {
+        if self.account_position(&account).await.is_none() {
+            let position = self.account_order.count();
+            self.account_order.push(account);
+            self.account_positions.insert(&account, position).expect("failed to index account");
+        }
+        self.accounts.insert(&account, balance).expect("failed to update balance");
+    }
+
+    pub async fn account_position(&self, account: &AccountOwner) -> Option<usize> {
+        self.account_positions.get(account).await.expect("failed to read account index")
+    }
+
+    /// Up to `count` owners from `start` in the order their accounts were opened, and whether
+    /// more follow.
+    pub async fn account_range(&self, start: usize, count: usize) -> (Vec<AccountOwner>, bool) {
+        let len = self.account_order.count();
+        let end = start.saturating_add(count).min(len);
+        let owners = self.account_order.read(start.min(end)..end).await.expect("failed to read account index");
+        (owners, end < len)
+    }
+
+    /// Fails without touching anything if `account` cannot pay `amount`.
+    pub async fn ensure_balance(&self, account: &AccountOwner, amount: Amount) -> Result<(), TokenError> {
+        let available = self.balance(account).await;
+        if available < amount {
+            return Err(TokenError::InsufficientBalance { available });
+        }
+        Ok(())
+    }
+
+    pub async fn locked_balance(&self, owner: &AccountOwner) -> Amount {
+        self.locked.get(owner).await.expect("failed to read locked balance").unwrap_or(Amount::ZERO)
+    }
+
+    pub async fn hold(&self, game: ApplicationId, reference: u64) -> Option<Hold> {
+        self.holds.get(&(game, reference)).await.expect("failed to read holds")
+    }
+
+    pub async fn open_hold(&mut self, hold: Hold) -> Result<(), TokenError> {
+        let key = (hold.game, hold.reference);
+        if self.hold(hold.game, hold.reference).await.is_some() {
+            return Err(TokenError::DuplicateHold { reference: hold.reference });
+        }
+        let mut locked = self.locked_balance(&hold.owner).await;
+        locked.saturating_add_assign(hold.amount);
+        self.locked.insert(&hold.owner, locked).expect("failed to update locked balance");
+        self.holds_by_owner
+            .load_entry_mut(&hold.owner)
+            .await
+            .expect("failed to index hold")
+            .insert(&key)
+            .expect("failed to index hold");
+        self.holds.insert(&key, hold).expect("failed to record hold");
+        Ok(())
+    }
+
+    /// Removes the hold and returns it, so the caller can decide where the stake goes.
+    pub async fn close_hold(&mut self, game: ApplicationId, reference: u64) -> Result<Hold, TokenError> {
+        let hold = self.hold(game, reference).await.ok_or(TokenError::UnknownHold { reference })?;
+        let locked = self.locked_balance(&hold.owner).await.saturating_sub(hold.amount);
+        self.locked.insert(&hold.owner, locked).expect("failed to update locked balance");
+        self.holds_by_owner
+            .load_entry_mut(&hold.owner)
+            .await
+            .expect("failed to index hold")
+            .remove(&(game, reference))
+            .expect("failed to index hold");
+        self.holds.remove(&(game, reference)).expect("failed to remove hold");
+        Ok(hold)
+    }
+
+    pub async fn holds_of(&self, owner: &AccountOwner) -> Vec<Hold> {
+        let Some(keys) = self.holds_by_owner.try_load_entry(owner).await.expect("failed to read holds") else {
+            return Vec::new();
+        };
+        let mut holds = Vec::new();
+        for key in keys.indices().await.expect("failed to read holds") {
+            if let Some(hold) = self.holds.get(&key).await.expect("failed to read holds") {
+                holds.push(hold);
+            }
+        }
+        holds
+    }
+
+    pub async fn ledger_len(&self, owner: &AccountOwner) -> u64 {
+        self.ledger_len.get(owner).await.expect("failed to read ledger").unwrap_or(0)
+    }
+
+    /// Appends `entry` to `owner`'s ledger, assigning it the next index.
+    pub async fn push_ledger_entry(&mut self, owner: AccountOwner, mut entry: LedgerEntry) {
+        let index = self.ledger_len(&owner).await;
+        entry.index = index;
+        self.ledger.insert(&(owner, index), entry).expect("failed to append ledger entry");
+        self.ledger_len.insert(&owner, index + 1).expect("failed to append ledger entry");
+    }
+
+    pub async fn ledger_entry(&self, owner: AccountOwner, index: u64) -> Option<LedgerEntry> {
+        self.ledger.get(&(owner, index)).await.expect("failed to read ledger")
+    }
+
+    pub async fn allowance(&self, owner: AccountOwner, spender: AccountOwner) -> Option<Amount> {
+        self.allowances.get(&(owner, spender)).await.expect("failed to read allowance")
+    }
+
+    pub fn approve(&mut self, owner: AccountOwner, spender: AccountOwner, amount: Amount) {
+        self.allowances.insert(&(owner, spender), amount).expect("failed to update allowance");
+    }
+
+    pub async fn spend_allowance(&mut self, owner: AccountOwner, spender: AccountOwner, amount: Amount) -> Result<(), TokenError> {
+        let available = self.allowance(owner, spender).await.unwrap_or(Amount::ZERO);
+        let allowance = available.try_sub(amount).map_err(|_| TokenError::InsufficientAllowance { available })?;
+        self.approve(owner, spender, allowance);
+        Ok(())
+    }
+
+    /// Records newly issued tokens, refusing to go over the max supply.
+    pub fn issue(&mut self, amount: Amount) -> Result<(), TokenError> {
+        let total_supply = self.total_supply.get().try_add(amount).map_err(|_| TokenError::Overflow)?;
+        if total_supply > *self.max_supply.get() {
+            return Err(TokenError::Overflow);
+        }
+        self.total_supply.set(total_supply);
+        Ok(())
+    }
+
+    pub async fn gaming_limits(&self, owner: &AccountOwner) -> GamingLimits {
+        self.gaming_limits.get(owner).await.expect("failed to read gaming limits").unwrap_or_default()
+    }
+
+    pub async fn excluded_until(&self, owner: &AccountOwner) -> Option<Timestamp> {
+        self.self_exclusions.get(owner).await.expect("failed to read self-exclusions")
+    }
+
+    pub async fn activity(&self, owner: &AccountOwner) -> PlayerActivity {
+        self.activity.get(owner).await.expect("failed to read player activity").unwrap_or_default()
+    }
+
+    pub async fn record_activity(&mut self, owner: AccountOwner, now: Timestamp, wagered: Amount, won: Amount) {
+        let mut activity = self.activity(&owner).await;
+        activity.record(now, wagered, won);
+        self.activity.insert(&owner, activity).expect("failed to update player activity");
+    }
+
+    /// Fails if `owner` is self-excluded, or if staking `amount` now could break one of their limits.
+    pub async fn check_stake(&self, owner: &AccountOwner, amount: Amount, now: Timestamp) -> Result<(), TokenError> {
+        if let Some(until) = self.excluded_until(owner).await {
+            if now < until {
+                return Err(TokenError::SelfExcluded { until });
+            }
+        }
+        let limits = self.gaming_limits(owner).await;
+        let activity = self.activity(owner).await;
+        let windows = [
+            (DAY_HOURS, limits.daily_wager_limit, limits.daily_loss_limit),
+            (WEEK_HOURS, limits.weekly_wager_limit, limits.weekly_loss_limit),
+        ];
+        for (hours, wager_limit, loss_limit) in windows {
+            let totals = activity.totals(now, hours);
+            if let Some(limit) = wager_limit {
+                let remaining = limit.saturating_sub(totals.wagered);
+                if amount > remaining {
+                    return Err(TokenError::WagerLimitExceeded { remaining });
+                }
+            }
+            // Assume the stake is lost, so a limit can never be overshot by a pending bet.
+            if let Some(limit) = loss_limit {
+                let remaining = limit.saturating_sub(totals.wagered.saturating_sub(totals.won));
+                if amount > remaining {
+                    return Err(TokenError::LossLimitExceeded { remaining });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn house_stats(&self, game: &ApplicationId) -> HouseStats {
+        self.house_stats.get(game).await.expect("failed to read house stats").unwrap_or_default()
+    }
+
+    pub async fn record_wager(&mut self, game: ApplicationId, amount: Amount) {
+        let mut stats = self.house_stats(&game).await;
+        stats.wagered.saturating_add_assign(amount);
+        self.house_stats.insert(&game, stats).expect("failed to update house stats");
+        self.house_totals.get_mut().wagered.saturating_add_assign(amount);
+    }
+
+    pub async fn record_payout(&mut self, game: ApplicationId, amount: Amount) {
+        let mut stats = self.house_stats(&game).await;
+        stats.paid_out.saturating_add_assign(amount);
+        self.house_stats.insert(&game, stats).expect("failed to update house stats");
+        self.house_totals.get_mut().paid_out.saturating_add_assign(amount);
+    }
+
+    /// Net profit of the house since it was last shared, or `None` while it is still making up
+    /// for a loss. Losses are carried over, so stakers are only paid from profit above the mark.
+    pub fn undistributed_profit(&self) -> Option<Amount> {
+        let totals = self.house_totals.get();
+        let mark = self.profit_mark.get();
+        let wagered = totals.wagered.saturating_sub(mark.wagered);
+        let paid_out = totals.paid_out.saturating_sub(mark.paid_out);
+        wagered.try_sub(paid_out).ok()
+    }
+
+    pub async fn epoch(&self, index: u64) -> Option<Epoch> {
+        self.epochs.get(&index).await.expect("failed to read epochs")
+    }
+
+    /// `owner`'s staking position with the rewards of every closed epoch settled.
+    pub async fn stake_account(&self, owner: &AccountOwner) -> StakeAccount {
+        let mut account = self.stakes.get(owner).await.expect("failed to read stakes").unwrap_or_default();
+        let epoch_count = *self.epoch_count.get();
+        for index in account.next_epoch..epoch_count {
+            if account.staked == Amount::ZERO && account.activating == Amount::ZERO {
+                break;
+            }
+            if index >= account.activation_epoch {
+                account.staked.saturating_add_assign(account.activating);
+                account.activating = Amount::ZERO;
+            }
+            let epoch = self.epoch(index).await.expect("closed epochs are recorded");
+            account.rewards.saturating_add_assign(pro_rata(epoch.profit, account.staked, epoch.total_staked));
+        }
+        if epoch_count >= account.activation_epoch {
+            account.staked.saturating_add_assign(account.activating);
+            account.activating = Amount::ZERO;
+        }
+        account.next_epoch = epoch_count;
+        account
+    }
+
+    pub fn save_stake_account(&mut self, owner: AccountOwner, account: StakeAccount) {
+        self.stakes.insert(&owner, account).expect("failed to update stakes");
+    }
+
+    pub async fn owed_winnings(&self, account: &Account) -> Amount {
+        self.owed_winnings.get(account).await.expect("failed to read owed winnings").unwrap_or(Amount::ZERO)
+    }
+
+    pub async fn owe_winnings(&mut self, account: Account, amount: Amount) {
+        let mut owed = self.owed_winnings(&account).await;
+        owed.saturating_add_assign(amount);
+        self.owed_winnings.insert(&account, owed).expect("failed to record owed winnings");
+        self.owed_total.get_mut().saturating_add_assign(amount);
+    }
+
+    /// Clears what is owed to `account` and returns it.
+    pub async fn take_owed_winnings(&mut self, account: &Account) -> Amount {
+        let owed = self.owed_winnings(account).await;
+        self.owed_winnings.remove(account).expect("failed to update owed winnings");
+        let owed_total = self.owed_total.get().saturating_sub(owed);
+        self.owed_total.set(owed_total);
+        owed
+    }
+
+    pub async fn is_authorized_game(&self, application_id: &ApplicationId) -> bool {
+        self.authorized_games
+            .contains(application_id)
+            .await
+            .expect("failed to read game registry")
+    }
+
+    pub async fn game_registry(&self) -> Vec<ApplicationId> {
+        self.authorized_games
+            .indices()
+            .await
+            .expect("failed to read game registry")
+    }
+
+    /// Replaces the registry with the one received from the creator chain.
+    pub async fn replace_registry(&mut self, admin: AccountOwner, treasury: AccountOwner, games: Vec<ApplicationId>) {
+        self.admin.set(Some(admin));
+        self.treasury.set(Some(treasury));
+        for game in self.game_registry().await {
+            if !games.contains(&game) {
+                self.authorized_games.remove(&game).expect("failed to update game registry");
+            }
+        }
+        for game in games {
+            self.authorized_games.insert(&game).expect("failed to update game registry");
+        }
     }
 }
@@ -0,0 +1,714 @@
+use std::str::FromStr;
+
+use super::*;
+use pulse_token::GamingLimits;
+use linera_sdk::{
+    linera_base_types::{CryptoHash, Timestamp},
+    util::BlockingWait,
+};
+
+const ALICE: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+const BOB: &str = "0x2222222222222222222222222222222222222222222222222222222222222222";
+const CAROL: &str = "0x3333333333333333333333333333333333333333333333333333333333333333";
+const TREASURY: &str = "0x4444444444444444444444444444444444444444444444444444444444444444";
+
+fn owner(address: &str) -> AccountOwner {
+    owner(address)
+}
+
+fn chain(name: &str) -> ChainId {
+    ChainId(CryptoHash::test_hash(name))
+}
+
+fn game_id() -> ApplicationId {
+    ApplicationId::new(CryptoHash::test_hash("game"))
+}
+
+fn tokens(amount: u128) -> Amount {
+    tokens(amount)
+}
+
+fn at_secs(secs: u64) -> Timestamp {
+    Timestamp::from(secs * 1_000_000)
+}
+
+/// A runtime of the application on `chain_id`, one second after the epoch.
+fn runtime_on(chain_id: ChainId) -> ContractRuntime<PulseTokenContract> {
+    ContractRuntime::new()
+        .with_application_parameters(TokenMetadata { name: "Pulse".to_string(), ticker_symbol: "PULSE".to_string() })
+        .with_application_id(ApplicationId::new(CryptoHash::test_hash("pulse_token")).with_abi())
+        .with_application_creator_chain_id(chain("creator chain"))
+        .with_chain_id(chain_id)
+        .with_system_time(at_secs(1))
+}
+
+fn load(runtime: ContractRuntime<PulseTokenContract>) -> PulseTokenContract {
+    let state = PulseTokenState::load(runtime.root_view_storage_context())
+        .blocking_wait()
+        .expect("Failed to read from mock key value store");
+    PulseTokenContract { state, runtime }
+}
+
+/// The application as instantiated on the creator chain, with `ALICE` as admin and minter
+/// holding the 1,000 tokens of the initial supply, and `ALICE` signing.
+fn instantiated() -> PulseTokenContract {
+    let runtime = runtime_on(chain("creator chain")).with_authenticated_signer(Some(owner(ALICE)));
+    let mut contract = load(runtime);
+    let argument = InstantiationArgument {
+        admin: owner(ALICE),
+        minter: owner(ALICE),
+        treasury: owner(TREASURY),
+        initial_supply: tokens(1_000),
+        max_supply: tokens(1_100),
+        faucet_amount: tokens(10),
+        faucet_cooldown_secs: 3_600,
+        staking_epoch_secs: 100,
+        unstake_cooldown_secs: 50,
+    };
+    contract.instantiate(argument).blocking_wait();
+    contract
+}
+
+/// `instantiated`, with the game registered, calling in a block `ALICE` signed.
+fn instantiated_game() -> PulseTokenContract {
+    let mut contract = instantiated();
+    let authorize = Operation::AuthorizeGame { application_id: game_id() };
+    contract.execute_operation(authorize).blocking_wait().expect("the admin registers games");
+    contract.runtime.set_authenticated_caller_id(Some(game_id()));
+    contract
+}
+
+/// A contract on a chain other than the creator chain, where `instantiate` never ran, with
+/// `ALICE` signing and holding 100 tokens received from elsewhere.
+fn contract_on_another_chain() -> PulseTokenContract {
+    let runtime = runtime_on(chain("user chain")).with_authenticated_signer(Some(owner(ALICE)));
+    let mut contract = load(runtime);
+    contract.state.credit(owner(ALICE), tokens(100)).blocking_wait();
+    contract
+}
+
+#[test]
+fn test_staking_is_refused_outside_the_creator_chain() {
+    let mut contract = contract_on_another_chain();
+    let alice = owner(ALICE);
+    let amount = tokens(10);
+
+    let stake = contract.execute_operation(Operation::Stake { owner: alice, amount }).blocking_wait();
+    assert_eq!(stake, Err(TokenError::StakingUnavailable));
+    let unstake = contract.execute_operation(Operation::Unstake { owner: alice, amount }).blocking_wait();
+    assert_eq!(unstake, Err(TokenError::StakingUnavailable));
+    let close = contract.execute_operation(Operation::CloseEpoch).blocking_wait();
+    assert_eq!(close, Err(TokenError::StakingUnavailable));
+
+    // Nothing was locked
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(100));
+    assert_eq!(*contract.state.activating_stake.get(), Amount::ZERO);
+}
+
+/// A contract on the creator chain, called by an authorized game in a block signed by `ALICE`,
+/// who holds 100 tokens.
+fn contract_called_by_game() -> (PulseTokenContract, ApplicationId) {
+    let runtime = runtime_on(chain("creator chain"))
+        .with_authenticated_signer(Some(owner(ALICE)))
+        .with_authenticated_caller_id(Some(game_id()));
+    let mut contract = load(runtime);
+    contract.state.authorized_games.insert(&game_id()).expect("failed to update game registry");
+    contract.state.credit(owner(ALICE), tokens(100)).blocking_wait();
+    (contract, game_id())
+}
+
+#[test]
+fn test_game_debit_is_capped_by_the_allowance() {
+    let (mut contract, game_id) = contract_called_by_game();
+    let alice = owner(ALICE);
+    contract.state.approve(alice, AccountOwner::from(game_id), tokens(5));
+
+    let debit = Operation::GameDebit { owner: alice, amount: tokens(10) };
+    let result = contract.execute_operation(debit).blocking_wait();
+    assert_eq!(result, Err(TokenError::InsufficientAllowance { available: tokens(5) }));
+}
+
+#[test]
+fn test_zero_allowance_blocks_a_game_the_owner_signed_for() {
+    let (mut contract, game_id) = contract_called_by_game();
+    let alice = owner(ALICE);
+    contract.state.approve(alice, AccountOwner::from(game_id), Amount::ZERO);
+
+    let lock = Operation::Lock { owner: alice, amount: tokens(1), reference: 0 };
+    let result = contract.execute_operation(lock).blocking_wait();
+    assert_eq!(result, Err(TokenError::InsufficientAllowance { available: Amount::ZERO }));
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(100));
+}
+
+#[test]
+fn test_accounts_are_paged_in_the_order_they_were_opened() {
+    let mut contract = contract_on_another_chain();
+    let alice = owner(ALICE);
+    let bob = owner(BOB);
+    let carol = owner(CAROL);
+    contract.state.credit(carol, tokens(1)).blocking_wait();
+    contract.state.credit(bob, tokens(2)).blocking_wait();
+    // A second credit does not move an account
+    contract.state.credit(alice, tokens(3)).blocking_wait();
+
+    assert_eq!(contract.state.account_range(0, 2).blocking_wait(), (vec![alice, carol], true));
+    let after_carol = contract.state.account_position(&carol).blocking_wait().expect("carol has an account") + 1;
+    assert_eq!(contract.state.account_range(after_carol, 2).blocking_wait(), (vec![bob], false));
+    assert_eq!(contract.state.account_range(3, 2).blocking_wait(), (vec![], false));
+}
+
+#[test]
+fn test_holds_are_listed_per_owner() {
+    let (mut contract, game_id) = contract_called_by_game();
+    let alice = owner(ALICE);
+    let bob = owner(BOB);
+    for reference in 0..2 {
+        let lock = Operation::Lock { owner: alice, amount: tokens(1), reference };
+        contract.execute_operation(lock).blocking_wait().expect("alice can lock");
+    }
+    contract.state.treasury.set(Some(bob));
+    let settle = Operation::Settle { reference: 0, payout: Amount::ZERO };
+    contract.execute_operation(settle).blocking_wait().expect("hold settles");
+
+    let holds = contract.state.holds_of(&alice).blocking_wait();
+    assert_eq!(holds.len(), 1);
+    assert_eq!((holds[0].game, holds[0].reference), (game_id, 1));
+    assert!(contract.state.holds_of(&bob).blocking_wait().is_empty());
+    assert_eq!(contract.state.locked_balance(&alice).blocking_wait(), tokens(1));
+}
+
+#[test]
+fn test_treasury_needs_the_registry_off_the_creator_chain() {
+    let mut contract = contract_on_another_chain();
+    let alice = owner(ALICE);
+
+    let fund = Operation::FundTreasury { owner: alice, amount: tokens(10) };
+    assert_eq!(contract.execute_operation(fund).blocking_wait(), Err(TokenError::RegistryNotSynced));
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(100));
+}
+
+#[test]
+fn test_rounds_off_the_creator_chain_settle_with_its_treasury() {
+    let alice = owner(ALICE);
+    let game_id = game_id();
+    let creator_chain_id = chain("creator chain");
+    let runtime = runtime_on(chain("user chain"))
+        .with_authenticated_signer(Some(alice))
+        .with_authenticated_caller_id(Some(game_id));
+    let mut contract = load(runtime);
+    contract.state.authorized_games.insert(&game_id).expect("failed to update game registry");
+    contract.state.credit(alice, tokens(100)).blocking_wait();
+
+    let lock = Operation::Lock { owner: alice, amount: tokens(10), reference: 0 };
+    contract.execute_operation(lock).blocking_wait().expect("alice can lock");
+    let settle = Operation::Settle { reference: 0, payout: tokens(25) };
+    contract.execute_operation(settle).blocking_wait().expect("the round settles without a local treasury");
+
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(90));
+    assert_eq!(contract.state.locked_balance(&alice).blocking_wait(), Amount::ZERO);
+    let messages = contract.runtime.created_send_message_requests();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].destination, creator_chain_id);
+    assert!(messages[0].is_tracked);
+    assert!(matches!(
+        messages[0].message,
+        Message::HouseRound { game, owner, stake, payout }
+            if game == game_id && owner == alice && stake == tokens(10) && payout == tokens(25)
+    ));
+}
+
+#[test]
+fn test_winnings_the_treasury_cannot_cover_are_owed_until_claimed() {
+    let alice = owner(ALICE);
+    let treasury = owner(BOB);
+    let game_id = game_id();
+    let user_chain_id = chain("user chain");
+    let runtime = runtime_on(chain("creator chain"))
+        .with_message_origin_chain_id(user_chain_id)
+        .with_message_is_bouncing(Some(false))
+        .with_authenticated_signer(Some(alice));
+    let mut contract = load(runtime);
+    contract.state.treasury.set(Some(treasury));
+    contract.state.credit(treasury, tokens(5)).blocking_wait();
+
+    // The stake joins the treasury, which still cannot pay 40
+    let round = Message::HouseRound { game: game_id, owner: alice, stake: tokens(10), payout: tokens(40) };
+    contract.execute_message(round).blocking_wait();
+    let account = Account { chain_id: user_chain_id, owner: alice };
+    assert_eq!(contract.state.balance(&treasury).blocking_wait(), tokens(15));
+    assert_eq!(contract.state.owed_winnings(&account).blocking_wait(), tokens(40));
+    assert_eq!(*contract.state.owed_total.get(), tokens(40));
+    let stats = contract.state.house_stats(&game_id).blocking_wait();
+    assert_eq!((stats.wagered, stats.paid_out), (tokens(10), tokens(40)));
+
+    contract.state.credit(treasury, tokens(30)).blocking_wait();
+    contract.execute_message(Message::WinningsRequest { owner: alice }).blocking_wait();
+    assert_eq!(contract.state.owed_winnings(&account).blocking_wait(), Amount::ZERO);
+    assert_eq!(*contract.state.owed_total.get(), Amount::ZERO);
+    assert_eq!(contract.state.balance(&treasury).blocking_wait(), tokens(5));
+    let messages = contract.runtime.created_send_message_requests();
+    assert!(matches!(
+        messages.last().map(|request| &request.message),
+        Some(Message::Credit { owner, amount, kind: LedgerKind::GameCredit, .. })
+            if *owner == alice && *amount == tokens(40)
+    ));
+}
+
+#[test]
+fn test_only_registered_games_move_stakes() {
+    let mut contract = instantiated();
+    let alice = owner(ALICE);
+    let game_operations = || {
+        [
+            Operation::GameDebit { owner: alice, amount: tokens(1) },
+            Operation::GameCredit { owner: alice, amount: tokens(1) },
+            Operation::BatchCredit { entries: vec![CreditEntry { owner: alice, amount: tokens(1) }] },
+            Operation::Lock { owner: alice, amount: tokens(1), reference: 0 },
+            Operation::Settle { reference: 0, payout: tokens(1) },
+            Operation::Release { reference: 0 },
+        ]
+    };
+
+    // Neither the owner on their own, nor an application missing from the registry
+    for caller in [None, Some(game_id())] {
+        contract.runtime.set_authenticated_caller_id(caller);
+        for operation in game_operations() {
+            assert_eq!(contract.execute_operation(operation).blocking_wait(), Err(TokenError::Unauthorized));
+        }
+    }
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(1_000));
+
+    contract.runtime.set_authenticated_caller_id(None);
+    let authorize = Operation::AuthorizeGame { application_id: game_id() };
+    contract.execute_operation(authorize).blocking_wait().expect("the admin registers games");
+    contract.runtime.set_authenticated_caller_id(Some(game_id()));
+    let debit = Operation::GameDebit { owner: alice, amount: tokens(1) };
+    contract.execute_operation(debit).blocking_wait().expect("a registered game takes stakes");
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(999));
+
+    contract.runtime.set_authenticated_caller_id(None);
+    let revoke = Operation::RevokeGame { application_id: game_id() };
+    contract.execute_operation(revoke).blocking_wait().expect("the admin revokes games");
+    contract.runtime.set_authenticated_caller_id(Some(game_id()));
+    let debit = Operation::GameDebit { owner: alice, amount: tokens(1) };
+    assert_eq!(contract.execute_operation(debit).blocking_wait(), Err(TokenError::Unauthorized));
+}
+
+#[test]
+fn test_only_the_admin_manages_the_registry() {
+    let mut contract = instantiated();
+    contract.runtime.set_authenticated_signer(Some(owner(BOB)));
+    let authorize = Operation::AuthorizeGame { application_id: game_id() };
+    assert_eq!(contract.execute_operation(authorize).blocking_wait(), Err(TokenError::Unauthorized));
+    assert!(!contract.state.is_authorized_game(&game_id()).blocking_wait());
+}
+
+#[test]
+fn test_minting_stops_at_the_max_supply() {
+    let mut contract = instantiated();
+    let target_account = Account { chain_id: chain("creator chain"), owner: owner(BOB) };
+
+    let mint = Operation::Mint { target_account, amount: tokens(101) };
+    assert_eq!(contract.execute_operation(mint).blocking_wait(), Err(TokenError::Overflow));
+    let mint = Operation::Mint { target_account, amount: tokens(100) };
+    contract.execute_operation(mint).blocking_wait().expect("the minter mints up to the cap");
+    assert_eq!(*contract.state.total_supply.get(), tokens(1_100));
+    assert_eq!(contract.state.balance(&owner(BOB)).blocking_wait(), tokens(100));
+
+    contract.runtime.set_authenticated_signer(Some(owner(BOB)));
+    let mint = Operation::Mint { target_account, amount: tokens(0) };
+    assert_eq!(contract.execute_operation(mint).blocking_wait(), Err(TokenError::Unauthorized));
+}
+
+#[test]
+fn test_faucet_pays_once_per_cooldown_and_within_the_max_supply() {
+    let mut contract = instantiated();
+    let bob = owner(BOB);
+    contract.runtime.set_authenticated_signer(Some(bob));
+
+    contract.execute_operation(Operation::Faucet { owner: bob }).blocking_wait().expect("first claim");
+    assert_eq!(contract.state.balance(&bob).blocking_wait(), tokens(10));
+
+    contract.runtime.set_system_time(at_secs(3_600));
+    let next_claim = at_secs(3_601);
+    let claim = contract.execute_operation(Operation::Faucet { owner: bob }).blocking_wait();
+    assert_eq!(claim, Err(TokenError::FaucetCooldown { next_claim }));
+
+    contract.runtime.set_system_time(next_claim);
+    contract.execute_operation(Operation::Faucet { owner: bob }).blocking_wait().expect("claim after the cooldown");
+    assert_eq!(contract.state.balance(&bob).blocking_wait(), tokens(20));
+    assert_eq!(*contract.state.total_supply.get(), tokens(1_020));
+
+    // Drips count towards the supply cap like any other mint
+    contract.state.total_supply.set(tokens(1_095));
+    contract.runtime.set_system_time(at_secs(10_000));
+    let claim = contract.execute_operation(Operation::Faucet { owner: bob }).blocking_wait();
+    assert_eq!(claim, Err(TokenError::Overflow));
+    assert_eq!(contract.state.balance(&bob).blocking_wait(), tokens(20));
+}
+
+#[test]
+fn test_remote_transfers_are_tracked_and_refunded_when_they_bounce() {
+    let mut contract = instantiated();
+    let (alice, bob) = (owner(ALICE), owner(BOB));
+    let target_account = Account { chain_id: chain("user chain"), owner: bob };
+
+    let transfer = Operation::Transfer { owner: alice, amount: tokens(30), target_account };
+    contract.execute_operation(transfer).blocking_wait().expect("alice can transfer");
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(970));
+    {
+        let messages = contract.runtime.created_send_message_requests();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].destination, chain("user chain"));
+        assert!(messages[0].is_tracked);
+    }
+
+    // The target chain rejected the credit, which comes back to the sender
+    contract.runtime.set_message_is_bouncing(Some(true));
+    let bounced = Message::Credit { owner: bob, amount: tokens(30), source: alice, kind: LedgerKind::TransferIn };
+    contract.execute_message(bounced).blocking_wait();
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(1_000));
+    assert_eq!(contract.state.balance(&bob).blocking_wait(), Amount::ZERO);
+    let last = contract.state.ledger_len(&alice).blocking_wait() - 1;
+    let refund = contract.state.ledger_entry(alice, last).blocking_wait().expect("refund is in the ledger");
+    assert_eq!((refund.kind, refund.amount, refund.balance), (LedgerKind::Refund, tokens(30), tokens(1_000)));
+
+    // A bounced batch refunds the sum of its credits
+    let carol_account = Account { chain_id: chain("user chain"), owner: owner(CAROL) };
+    let transfers = vec![
+        TransferEntry { target_account, amount: tokens(5) },
+        TransferEntry { target_account: carol_account, amount: tokens(7) },
+    ];
+    contract.execute_operation(Operation::BatchTransfer { owner: alice, transfers }).blocking_wait().expect("alice can transfer");
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(988));
+    let bounced = Message::BatchCredit { credits: vec![(bob, tokens(5)), (owner(CAROL), tokens(7))], source: alice };
+    contract.execute_message(bounced).blocking_wait();
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(1_000));
+}
+
+/// `owner`'s ledger as (kind, amount, balance after) triples, oldest first.
+fn ledger(contract: &PulseTokenContract, owner: AccountOwner) -> Vec<(LedgerKind, Amount, Amount)> {
+    let len = contract.state.ledger_len(&owner).blocking_wait();
+    (0..len)
+        .map(|index| {
+            let entry = contract.state.ledger_entry(owner, index).blocking_wait().expect("ledger has no gaps");
+            assert_eq!(entry.index, index);
+            (entry.kind, entry.amount, entry.balance)
+        })
+        .collect()
+}
+
+#[test]
+fn test_every_balance_change_is_in_the_ledger() {
+    let mut contract = instantiated_game();
+    let (alice, bob, treasury) = (owner(ALICE), owner(BOB), owner(TREASURY));
+
+    contract.runtime.set_authenticated_caller_id(None);
+    let target_account = Account { chain_id: chain("creator chain"), owner: bob };
+    contract.execute_operation(Operation::Transfer { owner: alice, amount: tokens(30), target_account }).blocking_wait().expect("alice can transfer");
+    contract.runtime.set_authenticated_caller_id(Some(game_id()));
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(10) }).blocking_wait().expect("game takes the stake");
+    contract.execute_operation(Operation::GameCredit { owner: alice, amount: tokens(4) }).blocking_wait().expect("game pays");
+    contract.execute_operation(Operation::Lock { owner: alice, amount: tokens(5), reference: 0 }).blocking_wait().expect("game holds the stake");
+    contract.execute_operation(Operation::Release { reference: 0 }).blocking_wait().expect("game releases the stake");
+
+    assert_eq!(
+        ledger(&contract, alice),
+        vec![
+            (LedgerKind::Mint, tokens(1_000), tokens(1_000)),
+            (LedgerKind::TransferOut, tokens(30), tokens(970)),
+            (LedgerKind::GameDebit, tokens(10), tokens(960)),
+            (LedgerKind::GameCredit, tokens(4), tokens(964)),
+            (LedgerKind::Lock, tokens(5), tokens(959)),
+            (LedgerKind::Release, tokens(5), tokens(964)),
+        ]
+    );
+    assert_eq!(ledger(&contract, bob), vec![(LedgerKind::TransferIn, tokens(30), tokens(30))]);
+    assert_eq!(
+        ledger(&contract, treasury),
+        vec![
+            (LedgerKind::GameDebit, tokens(10), tokens(10)),
+            (LedgerKind::GameCredit, tokens(4), tokens(6)),
+        ]
+    );
+
+    let stake = contract.state.ledger_entry(treasury, 0).blocking_wait().expect("stake is in the ledger");
+    assert_eq!(stake.counterparty, Some(alice));
+    assert_eq!(stake.application_id, Some(game_id()));
+}
+
+#[test]
+fn test_every_balance_change_is_emitted() {
+    let mut contract = instantiated_game();
+    let (alice, bob) = (owner(ALICE), owner(BOB));
+    let game = game_id();
+
+    let target_account = Account { chain_id: chain("creator chain"), owner: bob };
+    contract.execute_operation(Operation::Transfer { owner: alice, amount: tokens(30), target_account }).blocking_wait().expect("alice can transfer");
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(10) }).blocking_wait().expect("game takes the stake");
+    contract.execute_operation(Operation::GameCredit { owner: alice, amount: tokens(4) }).blocking_wait().expect("game pays");
+    contract.execute_operation(Operation::Lock { owner: alice, amount: tokens(5), reference: 7 }).blocking_wait().expect("game holds the stake");
+    contract.execute_operation(Operation::Release { reference: 7 }).blocking_wait().expect("game releases the stake");
+
+    let events: Vec<TokenEvent> = (0..6)
+        .map(|index| {
+            contract
+                .runtime
+                .read_event(chain("creator chain"), StreamName::from(TOKEN_EVENT_STREAM), index)
+        })
+        .collect();
+    assert!(matches!(events[0], TokenEvent::Minted { target_account, amount } if target_account.owner == alice && amount == tokens(1_000)));
+    assert!(matches!(events[1], TokenEvent::Transferred { source, target_account: target, amount } if source == alice && target == target_account && amount == tokens(30)));
+    assert!(matches!(events[2], TokenEvent::GameDebited { owner, game: id, amount } if owner == alice && id == game && amount == tokens(10)));
+    assert!(matches!(events[3], TokenEvent::GameCredited { owner, game: id, amount } if owner == alice && id == game && amount == tokens(4)));
+    assert!(matches!(events[4], TokenEvent::Locked { owner, reference: 7, amount, .. } if owner == alice && amount == tokens(5)));
+    assert!(matches!(events[5], TokenEvent::Released { owner, reference: 7, amount, .. } if owner == alice && amount == tokens(5)));
+}
+
+#[test]
+fn test_payouts_the_treasury_cannot_cover_are_refused() {
+    let mut contract = instantiated_game();
+    let alice = owner(ALICE);
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(10) }).blocking_wait().expect("game takes the stake");
+
+    let credit = Operation::GameCredit { owner: alice, amount: tokens(11) };
+    let refused = Err(TokenError::InsufficientTreasury { available: tokens(10) });
+    assert_eq!(contract.execute_operation(credit).blocking_wait(), refused);
+
+    // The held stake counts towards the payout, but not beyond
+    contract.execute_operation(Operation::Lock { owner: alice, amount: tokens(5), reference: 0 }).blocking_wait().expect("game holds the stake");
+    let settle = Operation::Settle { reference: 0, payout: tokens(16) };
+    let refused = Err(TokenError::InsufficientTreasury { available: tokens(15) });
+    assert_eq!(contract.execute_operation(settle).blocking_wait(), refused);
+    assert!(contract.state.hold(game_id(), 0).blocking_wait().is_some());
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(985));
+    assert_eq!(contract.state.balance(&owner(TREASURY)).blocking_wait(), tokens(10));
+
+    contract.execute_operation(Operation::Settle { reference: 0, payout: tokens(15) }).blocking_wait().expect("treasury covers 15");
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(1_000));
+    assert_eq!(contract.state.balance(&owner(TREASURY)).blocking_wait(), Amount::ZERO);
+}
+
+#[test]
+fn test_refused_operations_report_why_and_change_nothing() {
+    let mut contract = instantiated_game();
+    let (alice, bob) = (owner(ALICE), owner(BOB));
+    let target_account = Account { chain_id: chain("creator chain"), owner: bob };
+    contract.execute_operation(Operation::Lock { owner: alice, amount: tokens(5), reference: 0 }).blocking_wait().expect("game holds the stake");
+
+    let cases = [
+        (
+            Operation::GameDebit { owner: alice, amount: tokens(996) },
+            TokenError::InsufficientBalance { available: tokens(995) },
+        ),
+        (Operation::Lock { owner: alice, amount: tokens(1), reference: 0 }, TokenError::DuplicateHold { reference: 0 }),
+        (Operation::Release { reference: 1 }, TokenError::UnknownHold { reference: 1 }),
+        (Operation::Settle { reference: 1, payout: Amount::ZERO }, TokenError::UnknownHold { reference: 1 }),
+        (
+            Operation::TransferFrom { owner: alice, spender: AccountOwner::from(game_id()), amount: tokens(1), target_account },
+            TokenError::InsufficientAllowance { available: Amount::ZERO },
+        ),
+        (Operation::Transfer { owner: bob, amount: tokens(1), target_account }, TokenError::Unauthorized),
+        (Operation::Unstake { owner: alice, amount: tokens(1) }, TokenError::InsufficientStake { available: Amount::ZERO }),
+        (Operation::CloseEpoch, TokenError::EpochNotOver { ends_at: at_secs(101) }),
+    ];
+    for (operation, error) in cases {
+        assert_eq!(contract.execute_operation(operation).blocking_wait(), Err(error));
+    }
+
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(995));
+    assert_eq!(contract.state.locked_balance(&alice).blocking_wait(), tokens(5));
+    assert_eq!(contract.state.balance(&bob).blocking_wait(), Amount::ZERO);
+    assert_eq!(*contract.state.epoch_count.get(), 0);
+}
+
+#[test]
+fn test_every_error_has_its_own_message() {
+    let errors = [
+        TokenError::InsufficientBalance { available: Amount::ZERO },
+        TokenError::InsufficientAllowance { available: Amount::ZERO },
+        TokenError::InsufficientTreasury { available: Amount::ZERO },
+        TokenError::Unauthorized,
+        TokenError::Overflow,
+        TokenError::FaucetCooldown { next_claim: Timestamp::from(0) },
+        TokenError::UnknownHold { reference: 0 },
+        TokenError::DuplicateHold { reference: 0 },
+        TokenError::SelfExcluded { until: Timestamp::from(0) },
+        TokenError::WagerLimitExceeded { remaining: Amount::ZERO },
+        TokenError::LossLimitExceeded { remaining: Amount::ZERO },
+        TokenError::InsufficientStake { available: Amount::ZERO },
+        TokenError::EpochNotOver { ends_at: Timestamp::from(0) },
+        TokenError::StakingUnavailable,
+        TokenError::RegistryNotSynced,
+    ];
+    let messages: std::collections::BTreeSet<String> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(messages.len(), errors.len());
+}
+
+#[test]
+fn test_batches_move_everything_or_nothing() {
+    let mut contract = instantiated_game();
+    let (alice, bob, carol) = (owner(ALICE), owner(BOB), owner(CAROL));
+    let here = |owner| Account { chain_id: chain("creator chain"), owner };
+    let there = |owner| Account { chain_id: chain("user chain"), owner };
+
+    // The last entry takes the total past alice's balance
+    let transfers = vec![
+        TransferEntry { target_account: here(bob), amount: tokens(600) },
+        TransferEntry { target_account: here(carol), amount: tokens(401) },
+    ];
+    let refused = Err(TokenError::InsufficientBalance { available: tokens(1_000) });
+    assert_eq!(contract.execute_operation(Operation::BatchTransfer { owner: alice, transfers }).blocking_wait(), refused);
+    let transfers = vec![
+        TransferEntry { target_account: here(bob), amount: Amount::MAX },
+        TransferEntry { target_account: here(carol), amount: tokens(1) },
+    ];
+    let refused = Err(TokenError::Overflow);
+    assert_eq!(contract.execute_operation(Operation::BatchTransfer { owner: alice, transfers }).blocking_wait(), refused);
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(1_000));
+    assert_eq!(contract.state.balance(&bob).blocking_wait(), Amount::ZERO);
+
+    // Winners are only paid if the treasury covers all of them
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(10) }).blocking_wait().expect("game takes the stake");
+    let entries = vec![CreditEntry { owner: bob, amount: tokens(6) }, CreditEntry { owner: carol, amount: tokens(5) }];
+    let refused = Err(TokenError::InsufficientTreasury { available: tokens(10) });
+    assert_eq!(contract.execute_operation(Operation::BatchCredit { entries }).blocking_wait(), refused);
+    assert_eq!(contract.state.balance(&bob).blocking_wait(), Amount::ZERO);
+    assert_eq!(contract.state.balance(&owner(TREASURY)).blocking_wait(), tokens(10));
+
+    // A batch sends one message per remote chain
+    let transfers = vec![
+        TransferEntry { target_account: here(bob), amount: tokens(1) },
+        TransferEntry { target_account: there(bob), amount: tokens(2) },
+        TransferEntry { target_account: there(carol), amount: tokens(3) },
+    ];
+    contract.execute_operation(Operation::BatchTransfer { owner: alice, transfers }).blocking_wait().expect("alice can cover the batch");
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(984));
+    assert_eq!(contract.state.balance(&bob).blocking_wait(), tokens(1));
+    let messages = contract.runtime.created_send_message_requests();
+    assert_eq!(messages.len(), 1);
+    assert!(matches!(
+        &messages[0].message,
+        Message::BatchCredit { credits, source } if *source == alice && *credits == vec![(bob, tokens(2)), (carol, tokens(3))]
+    ));
+}
+
+#[test]
+fn test_wager_limits_roll_by_the_hour() {
+    let mut contract = instantiated_game();
+    let alice = owner(ALICE);
+    let limits = GamingLimits { daily_wager_limit: Some(tokens(10)), ..GamingLimits::default() };
+    contract.execute_operation(Operation::SetGamingLimits { owner: alice, limits }).blocking_wait().expect("alice sets her limits");
+
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(6) }).blocking_wait().expect("within the limit");
+    let refused = Err(TokenError::WagerLimitExceeded { remaining: tokens(4) });
+    assert_eq!(contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(5) }).blocking_wait(), refused);
+    // A hold is a stake like any other
+    let lock = Operation::Lock { owner: alice, amount: tokens(5), reference: 0 };
+    assert_eq!(contract.execute_operation(lock).blocking_wait(), refused);
+
+    // The first stake leaves the window with its hour, 24 hours later
+    contract.runtime.set_system_time(at_secs(23 * 3_600));
+    assert_eq!(contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(5) }).blocking_wait(), refused);
+    contract.runtime.set_system_time(at_secs(24 * 3_600));
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(5) }).blocking_wait().expect("the window moved on");
+}
+
+#[test]
+fn test_loss_limits_count_winnings_and_releases() {
+    let mut contract = instantiated_game();
+    let alice = owner(ALICE);
+    let limits = GamingLimits { daily_loss_limit: Some(tokens(10)), ..GamingLimits::default() };
+    contract.execute_operation(Operation::SetGamingLimits { owner: alice, limits }).blocking_wait().expect("alice sets her limits");
+
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(8) }).blocking_wait().expect("within the limit");
+    contract.execute_operation(Operation::GameCredit { owner: alice, amount: tokens(5) }).blocking_wait().expect("game pays");
+    // Down 3, and the next stake is assumed lost
+    let refused = Err(TokenError::LossLimitExceeded { remaining: tokens(7) });
+    assert_eq!(contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(8) }).blocking_wait(), refused);
+
+    // A released hold was never lost
+    contract.execute_operation(Operation::Lock { owner: alice, amount: tokens(7), reference: 0 }).blocking_wait().expect("within the limit");
+    contract.execute_operation(Operation::Release { reference: 0 }).blocking_wait().expect("game releases the stake");
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(7) }).blocking_wait().expect("still down 3");
+}
+
+#[test]
+fn test_self_exclusion_blocks_stakes_and_cannot_be_shortened() {
+    let mut contract = instantiated_game();
+    let alice = owner(ALICE);
+    contract.execute_operation(Operation::SelfExclude { owner: alice, duration_secs: 3_600 }).blocking_wait().expect("alice excludes herself");
+    contract.execute_operation(Operation::SelfExclude { owner: alice, duration_secs: 60 }).blocking_wait().expect("a shorter exclusion is ignored");
+
+    let refused = Err(TokenError::SelfExcluded { until: at_secs(3_601) });
+    assert_eq!(contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(1) }).blocking_wait(), refused);
+    let lock = Operation::Lock { owner: alice, amount: tokens(1), reference: 0 };
+    assert_eq!(contract.execute_operation(lock).blocking_wait(), refused);
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(1_000));
+
+    contract.runtime.set_system_time(at_secs(3_601));
+    contract.execute_operation(Operation::GameDebit { owner: alice, amount: tokens(1) }).blocking_wait().expect("the exclusion is over");
+}
+
+/// Runs `operation` signed by `signer`.
+fn execute_as(contract: &mut PulseTokenContract, signer: AccountOwner, operation: Operation) -> Result<(), TokenError> {
+    contract.runtime.set_authenticated_signer(Some(signer));
+    contract.execute_operation(operation).blocking_wait()
+}
+
+#[test]
+fn test_epoch_profit_is_shared_pro_rata_from_the_epoch_after_staking() {
+    let mut contract = instantiated_game();
+    let (alice, bob, carol) = (owner(ALICE), owner(BOB), owner(CAROL));
+    for (owner, amount) in [(bob, tokens(100)), (carol, tokens(200))] {
+        let target_account = Account { chain_id: chain("creator chain"), owner };
+        execute_as(&mut contract, alice, Operation::Transfer { owner: alice, amount, target_account }).expect("alice can transfer");
+    }
+    execute_as(&mut contract, alice, Operation::Stake { owner: alice, amount: tokens(30) }).expect("alice stakes");
+    execute_as(&mut contract, bob, Operation::Stake { owner: bob, amount: tokens(10) }).expect("bob stakes");
+
+    contract.runtime.set_system_time(at_secs(101));
+    contract.execute_operation(Operation::CloseEpoch).blocking_wait().expect("epoch 0 is over");
+    assert_eq!(*contract.state.total_staked.get(), tokens(40));
+
+    // Epoch 1: the house wins carol's 100, and she stakes 50 halfway through
+    execute_as(&mut contract, carol, Operation::GameDebit { owner: carol, amount: tokens(100) }).expect("game takes the stake");
+    contract.runtime.set_system_time(at_secs(150));
+    execute_as(&mut contract, carol, Operation::Stake { owner: carol, amount: tokens(50) }).expect("carol stakes");
+
+    contract.runtime.set_system_time(at_secs(201));
+    contract.execute_operation(Operation::CloseEpoch).blocking_wait().expect("epoch 1 is over");
+    let epoch = contract.state.epoch(1).blocking_wait().expect("epoch 1 is closed");
+    assert_eq!((epoch.profit, epoch.total_staked), (tokens(100), tokens(40)));
+    assert_eq!(*contract.state.reward_pool.get(), tokens(100));
+    assert_eq!(*contract.state.total_staked.get(), tokens(90));
+
+    assert_eq!(contract.state.stake_account(&alice).blocking_wait().rewards, tokens(75));
+    assert_eq!(contract.state.stake_account(&bob).blocking_wait().rewards, tokens(25));
+    let carol_stake = contract.state.stake_account(&carol).blocking_wait();
+    assert_eq!((carol_stake.rewards, carol_stake.staked), (Amount::ZERO, tokens(50)));
+
+    execute_as(&mut contract, alice, Operation::ClaimRewards { owner: alice }).expect("alice claims");
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(745));
+    assert_eq!(*contract.state.reward_pool.get(), tokens(25));
+}
+
+#[test]
+fn test_unstaked_tokens_are_withdrawn_after_the_cooldown() {
+    let mut contract = instantiated();
+    let alice = owner(ALICE);
+    contract.execute_operation(Operation::Stake { owner: alice, amount: tokens(30) }).blocking_wait().expect("alice stakes");
+    contract.execute_operation(Operation::Unstake { owner: alice, amount: tokens(10) }).blocking_wait().expect("alice unstakes");
+
+    contract.runtime.set_system_time(at_secs(50));
+    contract.execute_operation(Operation::WithdrawUnstaked { owner: alice }).blocking_wait().expect("nothing to withdraw yet");
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(970));
+    assert_eq!(contract.state.stake_account(&alice).blocking_wait().unbonding.len(), 1);
+
+    contract.runtime.set_system_time(at_secs(51));
+    contract.execute_operation(Operation::WithdrawUnstaked { owner: alice }).blocking_wait().expect("the cooldown is over");
+    assert_eq!(contract.state.balance(&alice).blocking_wait(), tokens(980));
+    let account = contract.state.stake_account(&alice).blocking_wait();
+    assert!(account.unbonding.is_empty());
+    assert_eq!(account.staked.saturating_add(account.activating), tokens(20));
+}
@@ -0,0 +1,344 @@
+//! Recomputes the outcome and payout of any PulseBet casino bet from its seeds, using the same
+//! outcome functions as the game contracts. Nothing here talks to a Linera node.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use coin_toss::{CoinTossParams, Prediction};
+use dice::{DiceParams, RollType};
+use keno::KenoParams;
+use memory_game::{CardDraw, MemoryParams};
+use mines::MinesParams;
+use plinko::PlinkoParams;
+use pulse_common::{config::BASIS_POINTS, fairness};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use wheel::WheelParams;
+
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
+
+#[derive(Parser)]
+#[command(name = "pulsebet-verify", about = "Reproduce a PulseBet game outcome from its seeds")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct Seeds {
+    /// Revealed server seed, hex-encoded
+    #[arg(long)]
+    server_seed: String,
+    #[arg(long)]
+    client_seed: String,
+    #[arg(long)]
+    nonce: u64,
+    /// Commitment shown before the bet; verification fails if the server seed does not match it
+    #[arg(long)]
+    server_seed_hash: Option<String>,
+    /// House edge of the game config the bet was placed under, as recorded in its round
+    #[arg(long)]
+    house_edge_bps: u16,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Dice {
+        #[command(flatten)]
+        seeds: Seeds,
+        #[arg(long)]
+        amount: u64,
+        #[arg(long)]
+        target: u8,
+        /// `over` or `under`
+        #[arg(long, value_parser = parse_roll_type)]
+        roll_type: RollType,
+    },
+    CoinToss {
+        #[command(flatten)]
+        seeds: Seeds,
+        #[arg(long)]
+        amount: u64,
+        /// `heads` or `tails`
+        #[arg(long, value_parser = parse_prediction)]
+        prediction: Prediction,
+    },
+    Wheel {
+        #[command(flatten)]
+        seeds: Seeds,
+        #[arg(long)]
+        amount: u64,
+    },
+    Keno {
+        #[command(flatten)]
+        seeds: Seeds,
+        #[arg(long)]
+        bet_amount: u64,
+        /// Comma-separated numbers between 1 and 40
+        #[arg(long, value_delimiter = ',')]
+        picks: Vec<u8>,
+    },
+    Mines {
+        #[command(flatten)]
+        seeds: Seeds,
+        #[arg(long)]
+        amount: u64,
+        #[arg(long)]
+        mines_count: u8,
+        /// Comma-separated tiles the player opened, in order
+        #[arg(long, value_delimiter = ',')]
+        revealed_tiles: Vec<u8>,
+    },
+    Plinko {
+        #[command(flatten)]
+        seeds: Seeds,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Pass the revealed link of the game's last draw as the server seed
+    Memory {
+        #[command(flatten)]
+        seeds: Seeds,
+        #[arg(long, default_value_t = 0)]
+        stake_amount: u64,
+        #[arg(long, default_value_t = 0)]
+        turn_count: u8,
+        /// Comma-separated cards in the order they were drawn, each as
+        /// `card:nonce:server_seed_hash`
+        #[arg(long, value_delimiter = ',', value_parser = parse_draw)]
+        draws: Vec<CardDraw>,
+    },
+    /// Verifies a round saved as JSON from the game's `round` or `rounds` query, and checks that
+    /// it paid what its seeds give
+    Record {
+        path: PathBuf,
+        /// Game the round was played on
+        #[arg(long, value_enum)]
+        game: Game,
+        /// Server seed of the round once revealed, hex-encoded
+        #[arg(long)]
+        server_seed: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Game {
+    Dice,
+    CoinToss,
+    Wheel,
+    Keno,
+    Mines,
+    Plinko,
+    Memory,
+}
+
+/// A round as the game services return it, either alone or as the whole response of a `round`
+/// query. It must include `params`, `payout`, `houseEdgeBps` and `seeds`; other fields are ignored.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Round {
+    params: Value,
+    payout: u64,
+    house_edge_bps: u16,
+    seeds: RoundSeeds,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoundSeeds {
+    server_seed_hash: String,
+    client_seed: String,
+    nonce: u64,
+}
+
+enum Bet {
+    Dice(DiceParams),
+    CoinToss(CoinTossParams),
+    Wheel(WheelParams),
+    Keno(KenoParams),
+    Mines(MinesParams),
+    Plinko(PlinkoParams),
+    Memory(MemoryParams),
+}
+
+/// What a bet's seeds give
+struct Replay {
+    server_seed_hash: String,
+    outcome: Value,
+    payout: u64,
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Record { path, game, server_seed } => read_round(&path).and_then(|round| verify_round(round, game, server_seed)),
+        command => {
+            let (seeds, bet) = into_bet(command);
+            replay(&seeds, &bet)
+        }
+    };
+    match result {
+        Ok(replay) => {
+            println!("server seed hash: {}", replay.server_seed_hash);
+            println!("{}", serde_json::to_string_pretty(&replay.outcome).expect("Outcomes serialize to JSON"));
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn into_bet(command: Command) -> (Seeds, Bet) {
+    match command {
+        Command::Dice { seeds, amount, target, roll_type } => {
+            (seeds, Bet::Dice(DiceParams { amount, target, roll_type }))
+        }
+        Command::CoinToss { seeds, amount, prediction } => {
+            (seeds, Bet::CoinToss(CoinTossParams { amount, prediction }))
+        }
+        Command::Wheel { seeds, amount } => (seeds, Bet::Wheel(WheelParams { amount })),
+        Command::Keno { seeds, bet_amount, picks } => (seeds, Bet::Keno(KenoParams { bet_amount, picks })),
+        Command::Mines { seeds, amount, mines_count, revealed_tiles } => {
+            (seeds, Bet::Mines(MinesParams { amount, mines_count, revealed_tiles }))
+        }
+        Command::Plinko { seeds, amount } => (seeds, Bet::Plinko(PlinkoParams { amount })),
+        Command::Memory { seeds, stake_amount, turn_count, draws } => {
+            (seeds, Bet::Memory(MemoryParams { stake_amount, turn_count, draws }))
+        }
+        Command::Record { .. } => unreachable!("rounds are read from their file"),
+    }
+}
+
+fn read_round(path: &Path) -> Result<Round, String> {
+    let contents = fs::read_to_string(path).map_err(|error| format!("Cannot read {}: {error}", path.display()))?;
+    parse_round(&contents)
+}
+
+fn parse_round(contents: &str) -> Result<Round, String> {
+    let mut value: Value = serde_json::from_str(contents).map_err(|error| format!("Invalid round: {error}"))?;
+    if let Some(round) = value.pointer_mut("/data/round").map(Value::take) {
+        value = round;
+    }
+    serde_json::from_value(value).map_err(|error| format!("Invalid round: {error}"))
+}
+
+/// Replays `round` and fails unless it paid what its seeds give.
+fn verify_round(round: Round, game: Game, server_seed: String) -> Result<Replay, String> {
+    let bet = match game {
+        Game::Dice => Bet::Dice(parse_params(round.params)?),
+        Game::CoinToss => Bet::CoinToss(parse_params(round.params)?),
+        Game::Wheel => Bet::Wheel(parse_params(round.params)?),
+        Game::Keno => Bet::Keno(parse_params(round.params)?),
+        Game::Mines => Bet::Mines(parse_params(round.params)?),
+        Game::Plinko => Bet::Plinko(parse_params(round.params)?),
+        Game::Memory => Bet::Memory(parse_params(round.params)?),
+    };
+    let seeds = Seeds {
+        server_seed,
+        client_seed: round.seeds.client_seed,
+        nonce: round.seeds.nonce,
+        server_seed_hash: Some(round.seeds.server_seed_hash),
+        house_edge_bps: round.house_edge_bps,
+    };
+    let replay = replay(&seeds, &bet)?;
+    if replay.payout != round.payout {
+        return Err(format!("Round paid {}, but its seeds give {}", round.payout, replay.payout));
+    }
+    Ok(replay)
+}
+
+fn parse_params<Params: DeserializeOwned>(params: Value) -> Result<Params, String> {
+    serde_json::from_value(params).map_err(|error| format!("Invalid round params: {error}"))
+}
+
+fn replay(seeds: &Seeds, bet: &Bet) -> Result<Replay, String> {
+    if u64::from(seeds.house_edge_bps) >= BASIS_POINTS {
+        return Err("House edge must be below 100%".to_string());
+    }
+    let (server_seed_hash, mut rng) = fairness::replay(&seeds.server_seed, &seeds.client_seed, seeds.nonce)?;
+    if let Some(expected) = &seeds.server_seed_hash {
+        if !expected.eq_ignore_ascii_case(&server_seed_hash) {
+            return Err(format!(
+                "Server seed hashes to {server_seed_hash}, not to the committed {expected}"
+            ));
+        }
+    }
+
+    let edge = seeds.house_edge_bps;
+    let (outcome, payout) = match bet {
+        Bet::Dice(params) => {
+            params.check()?;
+            let outcome = dice::roll_dice(&mut rng, params, edge);
+            (serde_json::to_value(&outcome), outcome.payout)
+        }
+        Bet::CoinToss(params) => {
+            params.check()?;
+            let outcome = coin_toss::toss_coin(&mut rng, params, edge);
+            (serde_json::to_value(&outcome), outcome.payout)
+        }
+        Bet::Wheel(params) => {
+            params.check()?;
+            let outcome = wheel::spin_wheel(&mut rng, params, edge);
+            (serde_json::to_value(&outcome), outcome.payout)
+        }
+        Bet::Keno(params) => {
+            params.check()?;
+            let outcome = keno::play_keno(&mut rng, params, edge);
+            (serde_json::to_value(&outcome), outcome.payout)
+        }
+        Bet::Mines(params) => {
+            params.check()?;
+            let outcome = mines::replay_mines(&mut rng, params, edge);
+            (serde_json::to_value(&outcome), outcome.payout)
+        }
+        Bet::Plinko(params) => {
+            params.check()?;
+            let outcome = plinko::drop_ball(&mut rng, params, edge);
+            (serde_json::to_value(&outcome), outcome.payout)
+        }
+        Bet::Memory(params) => {
+            params.check()?;
+            let outcome = memory_game::replay_board(&seeds.server_seed, &seeds.client_seed, params, edge)?;
+            (serde_json::to_value(&outcome), outcome.payout)
+        }
+    };
+    let outcome = outcome.expect("Outcomes serialize to JSON");
+    Ok(Replay { server_seed_hash, outcome, payout })
+}
+
+fn parse_roll_type(value: &str) -> Result<RollType, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "over" => Ok(RollType::Over),
+        "under" => Ok(RollType::Under),
+        _ => Err(format!("expected `over` or `under`, got `{value}`")),
+    }
+}
+
+fn parse_draw(value: &str) -> Result<CardDraw, String> {
+    let malformed = || format!("expected `card:nonce:server_seed_hash`, got `{value}`");
+    let mut parts = value.split(':');
+    let (Some(card), Some(nonce), Some(server_seed_hash), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(malformed());
+    };
+    Ok(CardDraw {
+        card: card.parse().map_err(|_| malformed())?,
+        nonce: nonce.parse().map_err(|_| malformed())?,
+        server_seed_hash: server_seed_hash.to_string(),
+    })
+}
+
+fn parse_prediction(value: &str) -> Result<Prediction, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "heads" => Ok(Prediction::Heads),
+        "tails" => Ok(Prediction::Tails),
+        _ => Err(format!("expected `heads` or `tails`, got `{value}`")),
+    }
+}
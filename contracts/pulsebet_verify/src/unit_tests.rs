@@ -0,0 +1,153 @@
+use serde_json::json;
+
+use super::*;
+
+// Expected outcomes below are those the game contracts settle for these seeds, at a 1% edge.
+const SERVER_SEED: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+const SERVER_SEED_HASH: &str = "630dcd2966c4336691125448bbb25b4ff412a49c732db2c8abc1b8581bd710dd";
+const HOUSE_EDGE_BPS: u16 = 100;
+
+fn seeds(nonce: u64) -> Seeds {
+    Seeds {
+        server_seed: SERVER_SEED.to_string(),
+        client_seed: "alice".to_string(),
+        nonce,
+        server_seed_hash: Some(SERVER_SEED_HASH.to_string()),
+        house_edge_bps: HOUSE_EDGE_BPS,
+    }
+}
+
+fn assert_replays(nonce: u64, bet: Bet, outcome: Value) {
+    let replay = replay(&seeds(nonce), &bet).expect("bet replays");
+    assert_eq!(replay.server_seed_hash, SERVER_SEED_HASH);
+    assert_eq!(replay.outcome, outcome);
+    assert_eq!(Some(replay.payout), outcome["payout"].as_u64());
+}
+
+#[test]
+fn test_dice_golden() {
+    let bet = Bet::Dice(DiceParams { amount: 1000, target: 50, roll_type: RollType::Under });
+    assert_replays(0, bet, json!({"roll": 10, "won": true, "payout": 1980, "multiplier_x100": 198}));
+}
+
+#[test]
+fn test_coin_toss_golden() {
+    let bet = Bet::CoinToss(CoinTossParams { amount: 1000, prediction: Prediction::Tails });
+    assert_replays(1, bet, json!({"result": "Tails", "won": true, "payout": 1980}));
+}
+
+#[test]
+fn test_wheel_golden() {
+    let bet = Bet::Wheel(WheelParams { amount: 1000 });
+    assert_replays(2, bet, json!({"segment": 6, "multiplier": 495, "payout": 4950}));
+}
+
+#[test]
+fn test_keno_golden() {
+    let bet = Bet::Keno(KenoParams { bet_amount: 1000, picks: vec![5, 6, 11, 1, 2] });
+    let outcome = json!({
+        "drawn_numbers": [5, 6, 11, 18, 21, 24, 27, 34, 35, 37],
+        "hits": 3,
+        "multiplier": 198,
+        "payout": 1980,
+    });
+    assert_replays(3, bet, outcome);
+}
+
+#[test]
+fn test_mines_golden() {
+    let bet = Bet::Mines(MinesParams { amount: 1000, mines_count: 3, revealed_tiles: vec![1, 2] });
+    let outcome = json!({"mine_indices": [5, 0, 23], "result": "CashedOut", "multiplier": 128, "payout": 1280});
+    assert_replays(4, bet, outcome);
+}
+
+#[test]
+fn test_plinko_golden() {
+    let bet = Bet::Plinko(PlinkoParams { amount: 1000 });
+    let outcome = json!({
+        "path": ["Left", "Left", "Right", "Left", "Left", "Right", "Right", "Right"],
+        "slot": 4,
+        "multiplier": 247,
+        "payout": 2470,
+    });
+    assert_replays(5, bet, outcome);
+}
+
+#[test]
+fn test_memory_golden() {
+    // Card 4 was drawn from the link before the server seed, which is its hash
+    let draws = vec![
+        parse_draw("4:6:2f287b4d3d4910f6cada9e1bd1b4648099e8c52c81aa4a6aebfa6fc86f19834e").expect("draw parses"),
+        parse_draw(&format!("9:7:{SERVER_SEED_HASH}")).expect("draw parses"),
+    ];
+    let bet = Bet::Memory(MemoryParams { stake_amount: 1000, turn_count: 8, draws });
+    let outcome = json!({
+        "image_ids": [null, null, null, null, 5, null, null, null, null, 4, null, null],
+        "payout": 4950,
+    });
+    assert_replays(7, bet, outcome);
+}
+
+fn dice_round(payout: u64) -> String {
+    json!({
+        "data": {
+            "round": {
+                "roundId": 0,
+                "owner": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "params": {"amount": 1000, "target": 50, "rollType": "UNDER"},
+                "outcome": {"roll": 10, "won": true, "payout": 1980, "multiplierX100": 198},
+                "payout": payout,
+                "houseEdgeBps": HOUSE_EDGE_BPS,
+                "timestamp": 0,
+                "seeds": {"serverSeedHash": SERVER_SEED_HASH, "clientSeed": "alice", "nonce": 0},
+            }
+        }
+    })
+    .to_string()
+}
+
+#[test]
+fn test_round_query_response_verifies() {
+    let round = parse_round(&dice_round(1980)).expect("round parses");
+    let replay = verify_round(round, Game::Dice, SERVER_SEED.to_string()).expect("round verifies");
+    assert_eq!(replay.payout, 1980);
+}
+
+#[test]
+fn test_round_with_wrong_payout_fails() {
+    let round = parse_round(&dice_round(2000)).expect("round parses");
+    let error = verify_round(round, Game::Dice, SERVER_SEED.to_string()).err();
+    assert_eq!(error.as_deref(), Some("Round paid 2000, but its seeds give 1980"));
+}
+
+#[test]
+fn test_round_with_another_server_seed_fails() {
+    let round = parse_round(&dice_round(1980)).expect("round parses");
+    let error = verify_round(round, Game::Dice, "00".to_string()).expect_err("seed does not match");
+    assert!(error.contains("not to the committed"), "{error}");
+}
+
+#[test]
+fn test_bad_params_are_rejected() {
+    let cases = [
+        (Bet::CoinToss(CoinTossParams { amount: 0, prediction: Prediction::Heads }), "Bet amount must be positive"),
+        (Bet::Wheel(WheelParams { amount: 0 }), "Bet amount must be positive"),
+        (Bet::Plinko(PlinkoParams { amount: 0 }), "Bet amount must be positive"),
+        (Bet::Keno(KenoParams { bet_amount: 10, picks: vec![41] }), "Numbers must be between 1 and 40"),
+        (
+            Bet::Memory(MemoryParams { stake_amount: 10, turn_count: 3, draws: Vec::new() }),
+            "A board takes at least one turn per pair to clear",
+        ),
+    ];
+    for (bet, expected) in cases {
+        assert_eq!(replay(&seeds(0), &bet).err().as_deref(), Some(expected));
+    }
+}
+
+#[test]
+fn test_house_edge_of_100_percent_is_rejected() {
+    let mut seeds = seeds(2);
+    seeds.house_edge_bps = 10_000;
+    let error = replay(&seeds, &Bet::Wheel(WheelParams { amount: 1000 })).err();
+    assert_eq!(error.as_deref(), Some("House edge must be below 100%"));
+}
@@ -1,23 +1,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 mod state;
+#[cfg(test)]
+#[path = "unit_tests.rs"]
+mod unit_tests;
 
 use linera_sdk::{
     abi::WithContractAbi,
     views::{RootView, View},
+    linera_base_types::AccountOwner,
     Contract, ContractRuntime,
-    linera_base_types::{AccountOwner, Amount, ApplicationId},
 };
-use linera_base::crypto::{CryptoHash, BcsHashable};
-use wheel::{Operation, WheelAbi, InstantiationArgument, MULTIPLIERS};
+use wheel::{Operation, WheelAbi, WheelParams, InstantiationArgument, SpinOutcome};
+use pulse_common::{bet, pending, GameConfig, GameKind, GameSettled, PendingBet, SettledBet};
 use state::{WheelState, Game};
-use serde::{Serialize, Deserialize};
-use std::str::FromStr;
-
-#[derive(Serialize, Deserialize)]
-struct SeedWrapper(Vec<u8>);
-
-impl<'de> BcsHashable<'de> for SeedWrapper {}
 
 pub struct WheelContract {
     state: WheelState,
@@ -30,13 +26,11 @@ impl WithContractAbi for WheelContract {
     type Abi = WheelAbi;
 }
 
-const PULSE_TOKEN_APP_ID: &str = "8e7498a4564d33c50bc4a3053eba7b51a4f5e7085111dbcc7cd3efe6072a7961";
-
 impl Contract for WheelContract {
     type Message = ();
-    type Parameters = ();
+    type Parameters = GameConfig;
     type InstantiationArgument = InstantiationArgument;
-    type EventValue = (); // No events for now, direct response
+    type EventValue = GameSettled<SpinOutcome>;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = WheelState::load(runtime.root_view_storage_context())
@@ -46,12 +40,27 @@ impl Contract for WheelContract {
     }
 
     async fn instantiate(&mut self, argument: InstantiationArgument) {
-        self.state.pulse_token_id.set(Some(argument.pulse_token_id));
+        self.state.setup.instantiate(&mut self.runtime, argument.pulse_token_id, argument.leaderboard_id);
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
         match operation {
-            Operation::SpinWheel { amount, owner } => self.execute_spin(amount, owner).await,
+            Operation::SpinWheel { amount, owner, client_seed } => self.execute_spin(amount, owner, client_seed).await,
+            Operation::Refund { owner } => {
+                self.state.setup.refund_bet(&mut self.runtime, &mut self.state.pending, owner).await;
+            }
+            Operation::UpdateConfig { config } => self.state.setup.update_config(&mut self.runtime, config),
+            Operation::CommitHouseSeeds { hash } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                self.state.seeds.commit_house_chain(hash);
+            }
+            Operation::PublishHouseSeed { seed } => {
+                self.state.setup.check_admin(&mut self.runtime);
+                let revealed = self.state.seeds.publish_house_seed(&mut self.runtime, seed);
+                for spin in pending::take_due(&mut self.state.pending, &revealed.server_seed_hash).await {
+                    self.settle(spin).await;
+                }
+            }
         }
     }
 
@@ -63,61 +72,47 @@ impl Contract for WheelContract {
 }
 
 impl WheelContract {
-    async fn execute_spin(&mut self, amount: u64, owner: String) -> Vec<u8> {
-        assert!(amount > 0, "Bet amount must be positive");
-
-        let token_app_id = match self.state.pulse_token_id.get() {
-            Some(id) => *id,
-            None => ApplicationId::from_str(PULSE_TOKEN_APP_ID).expect("Invalid hardcoded PulseToken App ID"),
-        };
-        let token_app_id = token_app_id.with_abi::<pulse_token::PulseTokenAbi>();
-        
-        let account_owner = AccountOwner::from_str(&owner).expect("Invalid owner address");
-        
-        let debit_op = pulse_token::Operation::GameDebit {
-            owner: account_owner,
-            amount: Amount::from_tokens(amount.into()),
-        };
-        
-        
-        self.runtime.call_application(true, token_app_id, &debit_op);
-
-        let nonce = *self.state.nonce.get();
-        self.state.nonce.set(nonce + 1);
-
-        let data = (self.runtime.chain_id(), self.runtime.system_time(), amount, nonce);
-        let bytes = bcs::to_bytes(&data).expect("Serialization failed");
-        let seed = SeedWrapper(bytes);
-        let hash = CryptoHash::new(&seed);
-        
-        // Take first byte mod 10 for 10 segments (simple uniform distribution)
-        // For production, maybe use u16 mod 360 for degrees, but mod 10 is fine for this hackathon
-        let segment_index = (hash.as_bytes()[0] % 10) as u8;
-
-        // 3. Calculate Result
-        let multiplier = MULTIPLIERS[segment_index as usize];
-        let payout = (amount * multiplier) / 100;
+    async fn execute_spin(&mut self, amount: u64, owner: AccountOwner, client_seed: String) {
+        let config = self.state.setup.config();
+        config.check_stake(amount);
+        config.check_payout(bet::apply_multiplier(amount, wheel::max_multiplier(config.house_edge_bps)));
+        bet::check_signer(&mut self.runtime, owner);
+        let waiting = self.state.pending.contains_key(&owner).await.expect("Failed to read pending spins");
+        assert!(!waiting, "A spin is already waiting for the house");
+
+        let params = WheelParams { amount };
+        let spin = self.state.setup.lock_bet(&mut self.runtime, &mut self.state.seeds, owner, amount, params, client_seed);
+        self.state.pending.insert(&owner, spin).expect("Failed to save spin");
+    }
 
-        // 4. Credit Tokens (if won)
-        if payout > 0 {
-             let credit_op = pulse_token::Operation::GameCredit {
-                owner: account_owner,
-                amount: Amount::from_tokens(payout.into()),
-            };
-            self.runtime.call_application(true, token_app_id, &credit_op);
-        }
+    async fn settle(&mut self, spin: PendingBet<WheelParams>) {
+        let mut rng = self.state.seeds.rng_for(&spin.seeds).await.expect("the spin's link is published");
+        let outcome = wheel::spin_wheel(&mut rng, &spin.params, spin.house_edge_bps);
+        let SpinOutcome { segment, multiplier, payout } = outcome.clone();
+        self.state.setup.token().settle(&mut self.runtime, spin.hold_reference, bet::tokens(payout));
 
-        // 5. Save History (Optional, keeps last game state)
         let game = Game {
-            owner,
-            bet_amount: amount,
-            result_segment: segment_index,
+            owner: spin.owner,
+            bet_amount: spin.stake,
+            result_segment: segment,
             multiplier,
             payout,
+            seeds: spin.seeds.clone(),
         };
-        self.state.active_game.set(Some(game));
-
-        // 6. Return Result
-        vec![segment_index]
+        let settled = SettledBet {
+            game: GameKind::Wheel,
+            owner: spin.owner,
+            stake: spin.stake,
+            params: spin.params,
+            outcome,
+            payout,
+            house_edge_bps: spin.house_edge_bps,
+            seeds: spin.seeds,
+        };
+        self.state
+            .setup
+            .record_round(&mut self.runtime, &mut self.state.rounds, &mut self.state.round_index, settled)
+            .await;
+        self.state.active_games.insert(&game.owner, game).expect("Failed to save game");
     }
 }
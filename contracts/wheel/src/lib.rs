@@ -1,21 +1,25 @@
-use async_graphql::{Request, Response};
+use async_graphql::{InputObject, Request, Response, SimpleObject};
 use linera_sdk::{
     abi::{ContractAbi, ServiceAbi},
     graphql::GraphQLMutationRoot,
-    linera_base_types::ApplicationId,
+    linera_base_types::{AccountOwner, ApplicationId},
 };
+use pulse_common::{bet, config, history, GameConfig, GameRng};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InstantiationArgument {
     pub pulse_token_id: ApplicationId,
+    /// Leaderboard the game reports its settled bets to, if any
+    #[serde(default)]
+    pub leaderboard_id: Option<ApplicationId>,
 }
 
 pub struct WheelAbi;
 
 impl ContractAbi for WheelAbi {
     type Operation = Operation;
-    type Response = Vec<u8>; // Returns winning segment index as bytes
+    type Response = ();
 }
 
 impl ServiceAbi for WheelAbi {
@@ -25,7 +29,17 @@ impl ServiceAbi for WheelAbi {
 
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
-    SpinWheel { amount: u64, owner: String },
+    /// Holds the stake; the wheel is spun when the house publishes its next link
+    SpinWheel { amount: u64, owner: AccountOwner, client_seed: String },
+    /// Returns the stake of a spin the house has left waiting too long
+    Refund { owner: AccountOwner },
+    /// Admin only
+    UpdateConfig { config: GameConfig },
+    /// Admin only: starts a house seed chain from the hash of its first link
+    CommitHouseSeeds { hash: String },
+    /// Admin only: publishes the next link of the house seed chain and settles the spins
+    /// waiting for it
+    PublishHouseSeed { seed: String },
 }
 
 pub const MULTIPLIERS: [u64; 10] = [
@@ -40,3 +54,45 @@ pub const MULTIPLIERS: [u64; 10] = [
     0,    // 8
     300,  // 9
 ];
+
+/// The bet a spin is verified against
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject, InputObject)]
+#[graphql(input_name = "WheelParamsInput")]
+pub struct WheelParams {
+    pub amount: u64,
+}
+
+impl WheelParams {
+    pub fn check(&self) -> Result<(), &'static str> {
+        if self.amount > 0 {
+            Ok(())
+        } else {
+            Err("Bet amount must be positive")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SimpleObject)]
+pub struct SpinOutcome {
+    pub segment: u8,
+    pub multiplier: u64,
+    pub payout: u64,
+}
+
+/// The jackpot multiplier after the house edge, in percent.
+pub fn max_multiplier(house_edge_bps: u16) -> u64 {
+    let jackpot = MULTIPLIERS.iter().copied().max().unwrap_or_default();
+    config::apply_house_edge(jackpot, house_edge_bps)
+}
+
+/// Spins the wheel for a bet; shared by the contract, the service and pulsebet-verify.
+pub fn spin_wheel(rng: &mut GameRng, params: &WheelParams, house_edge_bps: u16) -> SpinOutcome {
+    // 10 equally likely segments
+    let segment = rng.below(MULTIPLIERS.len() as u64) as u8;
+    let multiplier = config::apply_house_edge(MULTIPLIERS[segment as usize], house_edge_bps);
+    let payout = bet::apply_multiplier(params.amount, multiplier);
+    SpinOutcome { segment, multiplier, payout }
+}
+
+/// A settled spin, as kept in its owner's history
+pub type Round = history::Round<WheelParams, SpinOutcome>;
@@ -1,22 +1,32 @@
 use linera_sdk::{
-    linera_base_types::ApplicationId,
-    views::{RegisterView, RootView, ViewStorageContext},
+    linera_base_types::AccountOwner,
+    views::{LogView, MapView, RegisterView, RootView, ViewStorageContext},
 };
+use wheel::{Round, WheelParams};
+use pulse_common::{BetSeeds, GameSetup, PendingBet, RoundIndex, SeedBook};
 use serde::{Deserialize, Serialize};
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct WheelState {
-    pub pulse_token_id: RegisterView<Option<ApplicationId>>,
+    pub setup: GameSetup,
     pub nonce: RegisterView<u64>,
-    pub active_game: RegisterView<Option<Game>>,
+    /// Each owner's last settled spin
+    pub active_games: MapView<AccountOwner, Game>,
+    /// Spins waiting for the house link they are drawn from
+    pub pending: MapView<AccountOwner, PendingBet<WheelParams>>,
+    pub seeds: SeedBook,
+    /// Every settled round; a round's id is its index
+    pub rounds: LogView<Round>,
+    pub round_index: RoundIndex,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Game {
-    pub owner: String,
+    pub owner: AccountOwner,
     pub bet_amount: u64,
     pub result_segment: u8, // 0-9
     pub multiplier: u64,    // e.g. 200 = 2.0x
     pub payout: u64,
+    pub seeds: BetSeeds,
 }
@@ -0,0 +1,89 @@
+use super::*;
+use linera_sdk::{linera_base_types::Amount, util::BlockingWait};
+use pulse_common::{
+    fairness,
+    testing::{self, GameUnderTest, ALICE},
+    GameSetup,
+};
+
+impl GameUnderTest for WheelContract {
+    fn deploy(runtime: ContractRuntime<Self>) -> Self {
+        let state = WheelState::load(runtime.root_view_storage_context())
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        WheelContract { state, runtime }
+    }
+
+    fn parts(&mut self) -> (&mut GameSetup, &mut ContractRuntime<Self>) {
+        (&mut self.state.setup, &mut self.runtime)
+    }
+
+    fn bet(owner: AccountOwner) -> Operation {
+        Operation::SpinWheel { amount: 10, owner, client_seed: "seed".into() }
+    }
+
+    fn refund(owner: AccountOwner) -> Operation {
+        Operation::Refund { owner }
+    }
+
+    fn update_config(config: GameConfig) -> Operation {
+        Operation::UpdateConfig { config }
+    }
+
+    fn commit_house_seeds(hash: String) -> Operation {
+        Operation::CommitHouseSeeds { hash }
+    }
+
+    fn publish_house_seed(seed: String) -> Operation {
+        Operation::PublishHouseSeed { seed }
+    }
+}
+
+#[test]
+fn test_shared_game_rules() {
+    testing::check_shared_rules::<WheelContract>();
+}
+
+/// Settles a spin of Alice's whose segment pays out or not as asked, returning its outcome and
+/// the payout of its hold.
+fn settled_spin(pays: bool) -> (WheelContract, SpinOutcome, Amount) {
+    let params = WheelParams { amount: 10 };
+    let link = &testing::house_links()[0];
+    let client_seed = testing::client_seed_where(link, 0, |rng| {
+        (wheel::spin_wheel(rng, &params, 100).payout > 0) == pays
+    });
+    let (_, mut rng) = fairness::replay(link, &client_seed, 0).expect("test seeds are hex-encoded");
+    let outcome = wheel::spin_wheel(&mut rng, &params, 100);
+
+    let spin = Operation::SpinWheel { amount: 10, owner: bet::parse_owner(ALICE), client_seed };
+    let (contract, payout) = testing::settle_bet::<WheelContract>(spin, 10);
+    (contract, outcome, payout)
+}
+
+/// Alice's settled spin, checking it no longer waits for the house.
+fn saved_spin(contract: &WheelContract) -> Game {
+    let alice = bet::parse_owner(ALICE);
+    let waiting = contract.state.pending.contains_key(&alice).blocking_wait();
+    assert!(!waiting.expect("Failed to read pending spins"));
+    let game = contract.state.active_games.get(&alice).blocking_wait();
+    game.expect("Failed to read games").expect("the spin is saved")
+}
+
+#[test]
+fn test_paying_segment_pays_out() {
+    let (contract, outcome, payout) = settled_spin(true);
+
+    assert!(outcome.payout > 0);
+    assert_eq!(payout, bet::tokens(outcome.payout));
+    let game = saved_spin(&contract);
+    assert_eq!((game.result_segment, game.payout), (outcome.segment, outcome.payout));
+}
+
+#[test]
+fn test_empty_segment_pays_nothing() {
+    let (contract, outcome, payout) = settled_spin(false);
+
+    assert_eq!(payout, Amount::ZERO);
+    let game = saved_spin(&contract);
+    assert_eq!((game.result_segment, game.payout), (outcome.segment, 0));
+}
@@ -1,5 +1,6 @@
 import { useState, useCallback, useEffect } from 'react';
 import { useLineraWallet } from './useLineraWallet';
+import { newClientSeed } from '../utils/clientSeed';
 
 export interface GameLog {
     owner: string;
@@ -90,7 +91,7 @@ export const useCoinToss = () => {
         setLoading(true);
         // prediction is Enum in Rust: Heads, Tails (Uppercase for GraphQL)
         const mutation = `mutation {
-            playToss(amount: ${amount}, prediction: ${prediction}, owner: "${owner}")
+            playToss(amount: ${amount}, prediction: ${prediction}, owner: "${owner}", clientSeed: "${newClientSeed()}")
         }`;
         console.log("playToss mutation:", mutation);
 
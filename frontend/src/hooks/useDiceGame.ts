@@ -1,6 +1,7 @@
 import { useState, useCallback, useEffect } from 'react';
 import { useLineraWallet } from './useLineraWallet';
 import { usePulseToken } from './usePulseToken';
+import { newClientSeed } from '../utils/clientSeed';
 
 export interface DiceGameState {
     owner: string;
@@ -48,9 +49,9 @@ export const useDiceGame = () => {
     }, [client, chainId, DICE_APP_ID, autosignerOwner]);
 
     const fetchState = useCallback(async () => {
-        if (!isConnected) return;
+        if (!isConnected || !owner) return;
         const FETCH_GAME_STATE = `{
-            activeGame {
+            activeGame(owner: "${owner}") {
                 owner
                 betAmount
                 target
@@ -68,7 +69,7 @@ export const useDiceGame = () => {
         } catch (e) {
             console.error("[Dice] Fetch state failed", e);
         }
-    }, [isConnected, executeQuery]);
+    }, [isConnected, executeQuery, owner]);
 
     const rollDice = async (amount: number, target: number, mode: 'Over' | 'Under') => {
         if (!owner) {
@@ -85,7 +86,7 @@ export const useDiceGame = () => {
 
         const modeEnum = mode.toUpperCase(); // GraphQL Enums are typically ALL_CAPS
         const mutation = `mutation {
-            rollDice(amount: ${amount}, target: ${target}, rollType: ${modeEnum}, owner: "${owner}")
+            rollDice(amount: ${amount}, target: ${target}, rollType: ${modeEnum}, owner: "${owner}", clientSeed: "${newClientSeed()}")
         }`;
         console.log("[Dice] Mutation:", mutation);
 
@@ -111,10 +112,12 @@ export const useDiceGame = () => {
         }
     };
 
-    // Initial fetch
+    // Initial fetch, then poll: a roll settles when the house publishes its next seed
     useEffect(() => {
         if (isConnected) {
             fetchState();
+            const interval = setInterval(fetchState, 2000);
+            return () => clearInterval(interval);
         }
     }, [isConnected, fetchState]);
 
@@ -1,5 +1,6 @@
 import { useState, useCallback, useEffect } from 'react';
 import { useLineraWallet } from './useLineraWallet';
+import { newClientSeed } from '../utils/clientSeed';
 
 export interface KenoGameState {
     owner: string;
@@ -93,7 +94,7 @@ export const useKenoGame = () => {
         console.log("[Keno] Casting amount to integer for u64:", amountInt);
 
         const mutation = `mutation {
-            playKeno(betAmount: ${amountInt}, picks: ${picksString}, owner: "${owner}")
+            playKeno(betAmount: ${amountInt}, picks: ${picksString}, owner: "${owner}", clientSeed: "${newClientSeed()}")
         }`;
 
         console.log("[Keno] Mutation:", mutation);
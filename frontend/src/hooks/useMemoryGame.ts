@@ -1,11 +1,13 @@
 import { useState, useEffect, useCallback } from 'react';
 import { useLineraWallet } from './useLineraWallet';
+import { newClientSeed } from '../utils/clientSeed';
 
 const MEMORY_GAME_APP_ID = import.meta.env.VITE_MEMORY_GAME_APP_ID;
 
 interface Card {
     position: number;
-    imageId: number;
+    // Null until the card is first turned up and the house publishes the link it is drawn from
+    imageId: number | null;
 }
 
 interface Game {
@@ -15,7 +17,8 @@ interface Game {
     matchedCardsCount: number;
     matchedCards: number[];
     firstRevealedCard: number | null;
-    state: 'PLAYING' | 'FINISHED' | 'CLAIMED';
+    waitingCard: number | null;
+    state: 'PLAYING' | 'FINISHED' | 'CLAIMED' | 'FORFEITED' | 'REFUNDED';
     potentialPayout: number;
 }
 
@@ -25,11 +28,11 @@ interface CardRevealResponse {
     turnCount: number;
     matchedCardsCount: number;
     matchedCards: number[];
-    gameState: 'PLAYING' | 'FINISHED' | 'CLAIMED';
+    gameState: 'PLAYING' | 'FINISHED' | 'CLAIMED' | 'FORFEITED' | 'REFUNDED';
 }
 
 export const useMemoryGame = () => {
-    const { client, chainId, owner, autosignerOwner } = useLineraWallet();
+    const { client, chainId, owner } = useLineraWallet();
     const [gameState, setGameState] = useState<Game | null>(null);
     const [cards, setCards] = useState<Card[]>([]);
     const [loading, setLoading] = useState(false);
@@ -55,13 +58,14 @@ export const useMemoryGame = () => {
         if (!owner) return false;
 
         const query = `{
-      activeGame {
+      activeGame(owner: "${owner}") {
         player
         stakeAmount
         turnCount
         matchedCardsCount
         matchedCards
         firstRevealedCard
+        waitingCard
         state
         potentialPayout
       }
@@ -102,8 +106,8 @@ export const useMemoryGame = () => {
         }
         return false;
     }, [fetchGame]);
-    const fetchCards = useCallback(async () => {
-        if (!owner) return;
+    const fetchCards = useCallback(async (): Promise<Card[]> => {
+        if (!owner) return [];
 
         const query = `{
       cards(player: "${owner}") {
@@ -115,9 +119,21 @@ export const useMemoryGame = () => {
         const data = await executeQuery(query);
         if (data?.cards) {
             setCards(data.cards);
+            return data.cards;
         }
+        return [];
     }, [executeQuery, owner]);
 
+    // A card seen for the first time shows once the house publishes the link its image is drawn from
+    const waitForImage = useCallback(async (cardId: number, maxAttempts = 30, delayMs = 1000): Promise<Card[]> => {
+        for (let i = 0; i < maxAttempts; i++) {
+            const latest = await fetchCards();
+            if (latest.find(c => c.position === cardId)?.imageId != null) return latest;
+            await new Promise(r => setTimeout(r, delayMs));
+        }
+        throw new Error('The house has not turned up the card yet. Please refresh.');
+    }, [fetchCards]);
+
     const createGame = useCallback(async (stakeAmount: number) => {
         if (!owner) throw new Error('Wallet not connected');
 
@@ -126,7 +142,7 @@ export const useMemoryGame = () => {
 
         try {
             const mutation = `mutation {
-        createGame(stakeAmount: ${stakeAmount}, owner: "${owner}")
+        createGame(stakeAmount: ${stakeAmount}, owner: "${owner}", clientSeed: "${newClientSeed()}")
       }`;
 
             console.log('🎮 Creating game with mutation:', mutation);
@@ -161,25 +177,27 @@ export const useMemoryGame = () => {
             const mutation = `mutation {
         revealCard(cardId: ${cardId})
       }`;
-            if (!client || !chainId || !MEMORY_GAME_APP_ID || !autosignerOwner) throw new Error("Wallet not fully connected");
+            if (!client || !chainId || !MEMORY_GAME_APP_ID || !owner) throw new Error("Wallet not connected");
             const chain = await client.chain(chainId);
             const app = await chain.application(MEMORY_GAME_APP_ID);
             const requestBody = JSON.stringify({ query: mutation });
-            const responseJson = await app.query(requestBody, { owner: autosignerOwner });
+            // The contract finds the game from the signer, so sign as the player who created it
+            const responseJson = await app.query(requestBody, { owner });
             const response = JSON.parse(responseJson);
             const result = response.data;
             if (!result) {
                 throw new Error("Transaction cancelled or failed");
             }
-            const card = cards.find(c => c.position === cardId);
-            if (!card) throw new Error('Card data not found');
+            const latestCards = await waitForImage(cardId);
+            const card = latestCards.find(c => c.position === cardId);
+            if (!card || card.imageId === null) throw new Error('Card data not found');
 
             let isMatch = null;
             let firstCardId = gameState?.firstRevealedCard;
 
             if (firstCardId !== null && firstCardId !== undefined) {
                 // This is the second card
-                const firstCard = cards.find(c => c.position === firstCardId);
+                const firstCard = latestCards.find(c => c.position === firstCardId);
                 if (firstCard) {
                     isMatch = firstCard.imageId === card.imageId;
                 }
@@ -202,7 +220,7 @@ export const useMemoryGame = () => {
         } finally {
             setLoading(false);
         }
-    }, [executeQuery, fetchGame, gameState, cards]);
+    }, [executeQuery, fetchGame, gameState, waitForImage]);
 
     const claimPayout = useCallback(async () => {
         setLoading(true);
@@ -1,5 +1,6 @@
 import { useState, useCallback, useEffect } from 'react';
 import { useLineraWallet } from './useLineraWallet';
+import { newClientSeed } from '../utils/clientSeed';
 
 export interface GameState {
     owner: string;
@@ -12,7 +13,7 @@ export interface GameState {
 }
 
 export const useMinesGame = () => {
-    const { client, chainId, isConnected, owner, autosignerOwner } = useLineraWallet();
+    const { client, chainId, isConnected, owner } = useLineraWallet();
     const [gameState, setGameState] = useState<GameState | null>(null);
     const [loading, setLoading] = useState(false);
     const APP_ID = import.meta.env.VITE_MINES_APP_ID;
@@ -51,9 +52,9 @@ export const useMinesGame = () => {
     }, [client, chainId, APP_ID]);
 
     const refreshState = useCallback(async () => {
-        if (!isConnected) return;
+        if (!isConnected || !owner) return;
         const FETCH_GAME_STATE = `{
-            activeGame {
+            activeGame(owner: "${owner}") {
                 owner
                 minesCount
                 betAmount
@@ -82,9 +83,10 @@ export const useMinesGame = () => {
         } catch (e) {
             // console.error(e);
         }
-    }, [isConnected, executeQuery, client, chainId]);
+    }, [isConnected, executeQuery, client, chainId, owner]);
 
-    const startGame = async (amount: number, mines: number) => {
+    // Tiles are opened in order once the house publishes the link the layout is drawn from
+    const startGame = async (amount: number, mines: number, tiles: number[]) => {
         if (!owner) {
             console.error("Bet failed: Wallet not connected, owner is null");
             throw new Error("Wallet not connected");
@@ -93,10 +95,10 @@ export const useMinesGame = () => {
             console.error("Bet failed: VITE_MINES_APP_ID not set");
             throw new Error("Mines App ID not configured");
         }
-        console.log("Starting bet:", { amount, mines, owner, APP_ID });
+        console.log("Starting bet:", { amount, mines, tiles, owner, APP_ID });
         setLoading(true);
         const mutation = `mutation {
-            bet(amount: ${amount}, minesCount: ${mines}, owner: "${owner}")
+            bet(amount: ${amount}, minesCount: ${mines}, tiles: ${JSON.stringify(tiles)}, owner: "${owner}", clientSeed: "${newClientSeed()}")
         }`;
         console.log("Bet mutation:", mutation);
         try {
@@ -113,29 +115,11 @@ export const useMinesGame = () => {
         }
     };
 
-    const revealTile = async (tileId: number) => {
+    // Takes the stake back once the house has left the game waiting too long
+    const refund = async () => {
         setLoading(true);
         const mutation = `mutation {
-            reveal(tileId: ${tileId})
-        }`;
-        try {
-            const chain = await client.chain(chainId);
-            const app = await chain.application(APP_ID);
-            const requestBody = JSON.stringify({ query: mutation });
-            await app.query(requestBody, { owner: autosignerOwner });
-            await refreshState();
-        } catch (e) {
-            // console.error("Reveal failed:", e);
-        } finally {
-            setLoading(false);
-        }
-    };
-
-    const cashOut = async () => {
-        setLoading(true);
-        // Fixed: Use snake_case mutation name to match contract
-        const mutation = `mutation {
-            cashOut
+            refund(owner: "${owner}")
         }`;
         try {
             const chain = await client.chain(chainId);
@@ -144,7 +128,7 @@ export const useMinesGame = () => {
             await app.query(requestBody, { owner });
             await refreshState();
         } catch (e) {
-            // console.error("Cashout failed:", e);
+            // console.error("Refund failed:", e);
         } finally {
             setLoading(false);
         }
@@ -160,8 +144,7 @@ export const useMinesGame = () => {
         gameState,
         loading,
         startGame,
-        revealTile,
-        cashOut,
+        refund,
         refreshState
     };
 };
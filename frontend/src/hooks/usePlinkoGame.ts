@@ -1,5 +1,6 @@
 import { useState, useCallback, useEffect } from 'react';
 import { useLineraWallet } from './useLineraWallet';
+import { newClientSeed } from '../utils/clientSeed';
 
 // Define the shape of our Game State from the contract
 interface PlinkoGame {
@@ -15,7 +16,7 @@ interface PlinkoGame {
 export type Direction = 'Left' | 'Right';
 
 export const usePlinkoGame = () => {
-    const { client, chainId, isConnected, owner, autosignerOwner } = useLineraWallet();
+    const { client, chainId, isConnected, owner } = useLineraWallet();
     const [gameState, setGameState] = useState<PlinkoGame | null>(null);
     const [loading, setLoading] = useState(false);
 
@@ -49,9 +50,9 @@ export const usePlinkoGame = () => {
     }, [client, chainId, APP_ID]);
 
     const refreshState = useCallback(async () => {
-        if (!isConnected) return;
+        if (!isConnected || !owner) return;
         const FETCH_GAME_STATE = `{
-            activeGame {
+            activeGame(owner: "${owner}") {
                 owner
                 betAmount
                 currentRow
@@ -72,7 +73,7 @@ export const usePlinkoGame = () => {
         } catch (e) {
             // console.error(e);
         }
-    }, [isConnected, executeQuery]);
+    }, [isConnected, executeQuery, owner]);
 
     // Mutations
     const startGame = async (amount: number) => {
@@ -80,7 +81,7 @@ export const usePlinkoGame = () => {
         setLoading(true);
         // Contract expects u64, ensure integer
         const mutation = `mutation {
-            startGame(amount: ${Math.floor(amount)}, owner: "${owner}")
+            startGame(amount: ${Math.floor(amount)}, owner: "${owner}", clientSeed: "${newClientSeed()}")
         }`;
         try {
             const chain = await client.chain(chainId);
@@ -95,19 +96,20 @@ export const usePlinkoGame = () => {
         }
     };
 
-    const advanceBatch = async (targetRow: number) => {
-        if (!APP_ID || !autosignerOwner) return;
+    // Takes the stake back once the house has left the drop waiting too long
+    const refund = async () => {
+        if (!APP_ID || !owner) return;
         const mutation = `mutation {
-            advanceBatch(targetRow: ${targetRow})
+            refund(owner: "${owner}")
         }`;
         try {
             const chain = await client.chain(chainId);
             const app = await chain.application(APP_ID);
             const requestBody = JSON.stringify({ query: mutation });
-            await app.query(requestBody, { owner: autosignerOwner });
+            await app.query(requestBody, { owner });
             await refreshState();
         } catch (e) {
-            console.error("Advance failed:", e);
+            console.error("Refund failed:", e);
         }
         // setLoading(false);
     };
@@ -122,7 +124,7 @@ export const usePlinkoGame = () => {
         gameState,
         loading,
         startGame,
-        advanceBatch,
+        refund,
         refreshState
     };
 };
@@ -25,14 +25,14 @@ export const usePulseToken = () => {
         }
     }, [getApplication, chainId, owner, client]);
 
-    const mint = useCallback(async (amount: string) => {
+    const claimFaucet = useCallback(async () => {
         if (!client || !chainId || !PULSE_TOKEN_APP_ID || !owner) {
             throw new Error("Wallet not connected or Token App ID missing");
         }
 
         const mutation = `
             mutation {
-                mint(owner: "${owner}", amount: "${amount}")
+                faucet(owner: "${owner}")
             }
         `;
 
@@ -54,7 +54,7 @@ export const usePulseToken = () => {
 
     return {
         tokenBalance,
-        mint,
+        claimFaucet,
         refreshBalance: fetchTokenBalance,
         appId: PULSE_TOKEN_APP_ID,
         isReady
@@ -1,6 +1,7 @@
 import { useState, useCallback } from 'react';
 import { useLineraWallet } from './useLineraWallet';
 import { usePulseToken } from './usePulseToken';
+import { newClientSeed } from '../utils/clientSeed';
 
 const WHEEL_APP_ID = import.meta.env.VITE_WHEEL_APP_ID;
 
@@ -24,7 +25,7 @@ export const useWheelGame = () => {
         try {
             // 1. Send Mutation
             const mutation = `mutation {
-                spinWheel(amount: ${betAmount}, owner: "${owner}")
+                spinWheel(amount: ${betAmount}, owner: "${owner}", clientSeed: "${newClientSeed()}")
             }`;
 
             const chain = await client.chain(chainId);
@@ -38,9 +39,13 @@ export const useWheelGame = () => {
             console.log("Spin Mutation Result:", result);
 
             // 2. Query State for Result
-            // We need to fetch the active game state to see where the wheel effectively landed.
+            // The spin lands when the house publishes its next seed, so wait until it is no
+            // longer pending, then read where the wheel landed.
             const queryState = `{
-                activeGame {
+                pendingSpin(owner: "${owner}") {
+                    stake
+                }
+                activeGame(owner: "${owner}") {
                     resultSegment
                     multiplier
                     payout
@@ -48,12 +53,17 @@ export const useWheelGame = () => {
             }`;
 
             const stateRequestBody = JSON.stringify({ query: queryState });
-            const stateResponseJson = await app.query(stateRequestBody);
-            const stateResult = JSON.parse(stateResponseJson);
+            let stateResult = null;
+            for (let attempt = 0; attempt < 60; attempt++) {
+                const stateResponseJson = await app.query(stateRequestBody);
+                stateResult = JSON.parse(stateResponseJson);
+                if (!stateResult?.data?.pendingSpin) break;
+                await new Promise(r => setTimeout(r, 1000));
+            }
             console.log("Spin State Result:", stateResult);
 
             let segmentIndex = -1;
-            if (stateResult?.data?.activeGame?.resultSegment !== undefined) {
+            if (!stateResult?.data?.pendingSpin && stateResult?.data?.activeGame?.resultSegment !== undefined) {
                 segmentIndex = stateResult.data.activeGame.resultSegment;
             }
 
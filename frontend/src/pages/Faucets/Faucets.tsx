@@ -6,17 +6,16 @@ import { GameOverlay } from '../../components/GameOverlay';
 
 export default function Faucets() {
     const { balance, owner, connect } = useLineraWallet();
-    const { tokenBalance, mint, isReady } = usePulseToken();
+    const { tokenBalance, claimFaucet, isReady } = usePulseToken();
     const [loading, setLoading] = useState(false);
-    const [tokenMintAmount, setTokenMintAmount] = useState('100');
 
 
     const handleTokenMint = async () => {
         setLoading(true);
         try {
-            await mint(tokenMintAmount);
+            await claimFaucet();
         } catch (error) {
-            console.error("Token mint failed:", error);
+            console.error("Faucet claim failed:", error);
         } finally {
             setLoading(false);
         }
@@ -95,31 +94,20 @@ export default function Faucets() {
                             </div>
 
                             <div className="mt-8 space-y-4">
-                                <div className="space-y-2">
-                                    <label className="text-xs font-medium text-zinc-500 uppercase tracking-widest">Mint Allocation</label>
-                                    <div className="relative">
-                                        <input
-                                            type="number"
-                                            value={tokenMintAmount}
-                                            onChange={(e) => setTokenMintAmount(e.target.value)}
-                                            className="w-full bg-zinc-900/50 text-white font-mono border border-zinc-800 rounded-xl px-4 py-3 focus:outline-none focus:border-white transition-colors"
-                                            placeholder="0"
-                                            min="1"
-                                        />
-                                        <span className="absolute right-4 top-1/2 -translate-y-1/2 text-xs text-zinc-600 font-bold">PT</span>
-                                    </div>
-                                </div>
+                                <p className="text-xs text-zinc-500">
+                                    Each wallet can claim a fixed drip once per cooldown period.
+                                </p>
 
                                 <button
                                     onClick={handleTokenMint}
-                                    disabled={loading || !isReady || !tokenMintAmount}
+                                    disabled={loading || !isReady}
                                     className="w-full bg-zinc-800 hover:bg-zinc-700 text-white font-bold py-4 rounded-xl transition-all transform active:scale-[0.99] disabled:opacity-50 disabled:cursor-not-allowed flex items-center justify-center gap-2 border border-zinc-700"
                                 >
                                     {loading ? (
                                         <span className="animate-pulse">MINTING...</span>
                                     ) : (
                                         <>
-                                            MINT TOKENS
+                                            CLAIM TOKENS
                                         </>
                                     )}
                                 </button>
@@ -3,7 +3,7 @@ import { Card } from './Card';
 import { Trophy } from 'lucide-react';
 
 interface GameBoardProps {
-    cards: Array<{ position: number; imageId: number }>;
+    cards: Array<{ position: number; imageId: number | null }>;
     gameState: {
         turnCount: number;
         matchedCardsCount: number;
@@ -31,7 +31,7 @@ export const GameBoard = ({ cards, gameState, onRevealCard, loading }: GameBoard
             gameState.matchedCards.forEach(pos => {
                 newMatched.add(pos);
                 const card = cards.find(c => c.position === pos);
-                if (card) {
+                if (card && card.imageId !== null) {
                     newRevealed.set(pos, card.imageId);
                 }
             });
@@ -41,7 +41,7 @@ export const GameBoard = ({ cards, gameState, onRevealCard, loading }: GameBoard
         if (gameState.firstRevealedCard !== null && gameState.firstRevealedCard !== undefined) {
             setFirstCard(gameState.firstRevealedCard);
             const card = cards.find(c => c.position === gameState.firstRevealedCard);
-            if (card) {
+            if (card && card.imageId !== null) {
                 newRevealed.set(gameState.firstRevealedCard, card.imageId);
             }
         } else {
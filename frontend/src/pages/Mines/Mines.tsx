@@ -20,7 +20,7 @@ const MINES_RULES = (
         <div className="space-y-3 pt-2">
             <div className="p-3 bg-zinc-950/50 rounded-lg border border-zinc-800 flex items-center gap-4">
                 <Gem className="w-5 h-5 text-white" />
-                <div className="text-sm text-zinc-300"><strong>Reveal Gems</strong> to increase your payout multiplier.</div>
+                <div className="text-sm text-zinc-300"><strong>Pick Tiles</strong> before betting; each Gem increases your payout multiplier.</div>
             </div>
             <div className="p-3 bg-zinc-950/50 rounded-lg border border-zinc-800 flex items-center gap-4">
                 <Bomb className="w-5 h-5 text-zinc-500" />
@@ -28,7 +28,7 @@ const MINES_RULES = (
             </div>
             <div className="p-3 bg-zinc-950/50 rounded-lg border border-zinc-800 flex items-center gap-4">
                 <div className="w-5 h-5 rounded bg-green-500/20 text-green-500 flex items-center justify-center font-bold text-xs">$</div>
-                <div className="text-sm text-zinc-300"><strong>Cash Out</strong> at any time to secure your winnings.</div>
+                <div className="text-sm text-zinc-300"><strong>Cash Out</strong> happens after your last tile, once the house seed is published.</div>
             </div>
         </div>
 
@@ -40,14 +40,23 @@ const MINES_RULES = (
 );
 
 export const Mines = () => {
-    const { gameState, loading, startGame, revealTile, cashOut } = useMinesGame();
+    const { gameState, loading, startGame, refund } = useMinesGame();
     const { isConnected, connect } = useLineraWallet();
     const { tokenBalance } = usePulseToken();
     const navigate = useNavigate();
     const [betAmount, setBetAmount] = useState<number>(0);
     const [minesCount, setMinesCount] = useState<number>(3);
+    // Tiles to open, in order, picked before the bet
+    const [selectedTiles, setSelectedTiles] = useState<number[]>([]);
     const isGameActive = gameState?.result === 'ACTIVE';
-    const revealedTiles = gameState?.revealedTiles || [];
+    // Picking tiles for a new bet clears the last game from the grid
+    const revealedTiles = selectedTiles.length === 0 && !isGameActive ? gameState?.revealedTiles || [] : [];
+
+    const toggleTile = (id: number) => {
+        setSelectedTiles(prev => prev.includes(id)
+            ? prev.filter(tile => tile !== id)
+            : prev.length < 25 - minesCount ? [...prev, id] : prev);
+    };
     const isGameOver = gameState?.result === 'LOST' || gameState?.result === 'WON' || gameState?.result === 'CASHED_OUT';
 
     // Overlay State
@@ -159,7 +168,15 @@ export const Mines = () => {
             });
             return;
         }
-        startGame(betAmount, minesCount);
+        if (selectedTiles.length === 0) {
+            toast.error("No Tiles Selected", {
+                description: "Pick the tiles to open before placing the bet.",
+                duration: 3000,
+            });
+            return;
+        }
+        startGame(betAmount, minesCount, selectedTiles);
+        setSelectedTiles([]);
     };
 
     return (
@@ -250,11 +267,11 @@ export const Mines = () => {
                         INITIALIZE WALLET
                     </button>
                 ) : isGameActive ? (
-                    <button onClick={cashOut} disabled={loading} className="w-full bg-white hover:bg-zinc-200 text-black font-bold py-4 rounded-xl shadow-lg shadow-white/10 transition-all disabled:opacity-50 disabled:cursor-not-allowed uppercase tracking-wider relative overflow-hidden group">
+                    <button onClick={refund} disabled={loading} className="w-full bg-white hover:bg-zinc-200 text-black font-bold py-4 rounded-xl shadow-lg shadow-white/10 transition-all disabled:opacity-50 disabled:cursor-not-allowed uppercase tracking-wider relative overflow-hidden group">
                         {loading ? "PROCESSING..." : (
                             <div className="flex flex-col items-center leading-none gap-1">
-                                <span className="text-xs font-bold tracking-widest text-zinc-500 group-hover:text-black transition-colors">SECURE YIELD</span>
-                                <span className="text-xl font-mono tracking-tight">{(betAmount * ((gameState?.currentMultiplier || 100) / 100)).toFixed(4)} PT</span>
+                                <span className="text-xs font-bold tracking-widest text-zinc-500 group-hover:text-black transition-colors">AWAITING HOUSE SEED</span>
+                                <span className="text-xs font-mono tracking-tight">Refund if the house stalls</span>
                             </div>
                         )}
                     </button>
@@ -361,22 +378,27 @@ export const Mines = () => {
                     {Array.from({ length: 25 }).map((_, i) => {
                         const isRevealed = revealedTiles.includes(i);
                         const isMine = gameState?.mineIndices?.includes(i) || false;
-                        const showMine = isMine && isGameOver;
+                        const showMine = isMine && isGameOver && selectedTiles.length === 0;
+                        const selection = selectedTiles.indexOf(i);
 
                         return (
                             <button
                                 key={i}
-                                disabled={!isGameActive || isRevealed || loading}
-                                onClick={() => revealTile(i)}
+                                disabled={isGameActive || loading}
+                                onClick={() => toggleTile(i)}
                                 className={`
                                     rounded-xl transition-all duration-300 relative overflow-hidden flex items-center justify-center outline-none select-none w-full h-full
                                     ${isRevealed || showMine
                                         ? `bg-black border border-zinc-900 ${showMine ? 'border-zinc-700' : ''}` // Revealed
                                         : 'bg-zinc-800 border border-zinc-700 hover:bg-zinc-700 hover:border-zinc-500 active:scale-95' // Hidden
                                     }
-                                    ${!isGameActive && !isRevealed ? 'opacity-20 cursor-not-allowed' : ''}
+                                    ${selection >= 0 ? 'ring-2 ring-white' : ''}
+                                    ${isGameActive ? 'opacity-20 cursor-not-allowed' : ''}
                                 `}
                             >
+                                {selection >= 0 && (
+                                    <span className="absolute top-1 left-2 text-[10px] font-mono font-bold text-white">{selection + 1}</span>
+                                )}
                                 <div className={`transition-all duration-300 w-full h-full flex items-center justify-center ${isRevealed || showMine ? 'scale-100 opacity-100' : 'scale-50 opacity-0'}`}>
                                     {getTileContent(i)}
                                 </div>
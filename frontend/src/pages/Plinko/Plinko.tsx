@@ -8,7 +8,6 @@ import { GameOverlay } from '../../components/GameOverlay';
 import { motion, AnimatePresence } from 'framer-motion';
 
 const ROWS = 8;
-const CHECKPOINTS = [1, 3, 5, 7, 8];
 const MULTIPLIERS = [10.0, 0.25, 1.5, 0.5, 2.5, 0.5, 1.5, 0.25, 10.0];
 
 const PLINKO_RULES = (
@@ -33,7 +32,7 @@ const PLINKO_RULES = (
 );
 
 export const Plinko = () => {
-    const { gameState, loading: gameLoading, startGame, advanceBatch } = usePlinkoGame();
+    const { gameState, loading: gameLoading, startGame } = usePlinkoGame();
     const { isConnected, connect } = useLineraWallet();
     const { tokenBalance } = usePulseToken();
     const navigate = useNavigate();
@@ -51,21 +50,6 @@ export const Plinko = () => {
         return { x, y };
     };
 
-    useEffect(() => {
-        if (!gameState) return;
-        if (gameState.result !== 'ACTIVE') return;
-
-        const r = gameState.currentRow;
-        const nextTarget = CHECKPOINTS.find(cp => cp > r);
-
-        if (nextTarget) {
-            const timer = setTimeout(() => {
-                advanceBatch(nextTarget);
-            }, 600);
-            return () => clearTimeout(timer);
-        }
-    }, [gameState]);
-
     const isGameActive = gameState?.result === 'ACTIVE';
     const ballPos = gameState ? getBallPos(gameState.currentRow, gameState.currentCol) : getBallPos(0, 0);
 
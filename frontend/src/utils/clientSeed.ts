@@ -0,0 +1,6 @@
+// Fresh per-bet client seed, mixed with the committed server seed so the house
+// cannot pick outcomes on its own.
+export const newClientSeed = (): string => {
+    const bytes = crypto.getRandomValues(new Uint8Array(16));
+    return Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+};